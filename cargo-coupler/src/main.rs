@@ -11,6 +11,8 @@ use std::path::{Path, PathBuf};
 use std::process::{self, Command};
 use std::str::{self, FromStr};
 
+mod validate;
+
 #[derive(Parser)]
 #[clap(bin_name = "cargo")]
 enum Cargo {
@@ -22,6 +24,7 @@ enum Cargo {
 #[clap(version, about, long_about = None)]
 enum Coupler {
     Bundle(Bundle),
+    Validate(Validate),
 }
 
 #[derive(Args, Debug)]
@@ -73,6 +76,17 @@ struct Bundle {
     offline: bool,
 }
 
+#[derive(Args, Debug)]
+struct Validate {
+    /// Paths to `.clap` or `.vst3` bundles
+    #[clap(required = true, parse(from_os_str))]
+    paths: Vec<PathBuf>,
+
+    /// Seed for randomized checks, such as buffer sizes
+    #[clap(long)]
+    seed: Option<u64>,
+}
+
 #[derive(Deserialize)]
 struct PackageMetadata {
     coupler: Option<CouplerMetadata>,
@@ -197,6 +211,9 @@ fn main() {
         Coupler::Bundle(cmd) => {
             bundle(&cmd);
         }
+        Coupler::Validate(cmd) => {
+            validate::validate(&cmd);
+        }
     }
 }
 
@@ -0,0 +1,167 @@
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::Validate;
+
+mod clap;
+mod vst3;
+
+/// A small xorshift generator, so that failures can be reproduced with `--seed`.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Returns a value in `0..=max`.
+    pub fn below_or_eq(&mut self, max: usize) -> usize {
+        (self.next_u64() % (max as u64 + 1)) as usize
+    }
+
+    pub fn sample(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32 * 2.0 - 1.0
+    }
+}
+
+pub struct Report {
+    results: Vec<(String, Result<(), String>)>,
+}
+
+impl Report {
+    fn new() -> Report {
+        Report {
+            results: Vec::new(),
+        }
+    }
+
+    /// Runs a single check, recording a failure if it returns an error or panics.
+    pub fn check<F>(&mut self, name: impl Into<String>, f: F)
+    where
+        F: FnOnce() -> Result<(), String>,
+    {
+        let name = name.into();
+
+        let result = match panic::catch_unwind(AssertUnwindSafe(f)) {
+            Ok(result) => result,
+            Err(payload) => Err(format!("panicked: {}", panic_message(&payload))),
+        };
+
+        match &result {
+            Ok(()) => println!("PASS  {name}"),
+            Err(error) => println!("FAIL  {name}: {error}"),
+        }
+
+        self.results.push((name, result));
+    }
+
+    pub fn fail(&mut self, name: impl Into<String>, error: impl Into<String>) {
+        let error = error.into();
+        self.check(name, || Err(error));
+    }
+
+    fn failures(&self) -> usize {
+        self.results.iter().filter(|(_, result)| result.is_err()).count()
+    }
+}
+
+fn panic_message(payload: &Box<dyn Any + Send>) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+/// Finds the shared library inside a bundle for the current platform.
+fn bundle_binary(path: &Path) -> PathBuf {
+    let Some(name) = path.file_stem() else {
+        return path.to_path_buf();
+    };
+    let is_vst3 = path.extension().is_some_and(|ext| ext == "vst3");
+
+    if cfg!(target_os = "macos") && path.is_dir() {
+        return path.join("Contents/MacOS").join(name);
+    }
+
+    if is_vst3 && path.is_dir() {
+        let arch = if cfg!(target_arch = "x86_64") {
+            "x86_64"
+        } else if cfg!(target_arch = "aarch64") {
+            "aarch64"
+        } else {
+            "i386"
+        };
+
+        if cfg!(target_os = "windows") {
+            let arch = match arch {
+                "aarch64" => "arm64",
+                "i386" => "x86",
+                arch => arch,
+            };
+            return path.join(format!("Contents/{arch}-win")).join(path.file_name().unwrap());
+        }
+
+        let mut file_name = name.to_os_string();
+        file_name.push(".so");
+        return path.join(format!("Contents/{arch}-linux")).join(file_name);
+    }
+
+    path.to_path_buf()
+}
+
+pub fn validate(cmd: &Validate) {
+    let seed = cmd.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(1)
+    });
+    println!("seed: {seed}");
+
+    let mut report = Report::new();
+
+    for path in &cmd.paths {
+        println!();
+        println!("{}", path.display());
+
+        let binary = bundle_binary(path);
+        let mut rng = Rng::new(seed);
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("clap") => clap::validate(&binary, &mut report, &mut rng),
+            Some("vst3") => vst3::validate(&binary, &mut report, &mut rng),
+            _ => {
+                report.fail(
+                    "format",
+                    format!("unrecognized bundle extension for `{}`", path.display()),
+                );
+            }
+        }
+    }
+
+    let failures = report.failures();
+    println!();
+    println!(
+        "{} checks, {} passed, {} failed",
+        report.results.len(),
+        report.results.len() - failures,
+        failures
+    );
+
+    if failures > 0 {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,711 @@
+use clap_sys::ext::{audio_ports::*, params::*, state::*, thread_check::*};
+use clap_sys::plugin_factory::{CLAP_PLUGIN_FACTORY_ID, clap_plugin_factory};
+use clap_sys::version::{CLAP_VERSION, clap_version_is_compatible};
+use clap_sys::{
+    audio_buffer::*, entry::*, events::*, host::*, id::*, plugin::*, process::*, stream::*,
+};
+use libloading::Library;
+
+use std::ffi::{CStr, CString, c_char, c_void};
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, ThreadId};
+use std::{mem, ptr, slice};
+
+use super::{Report, Rng};
+
+const SAMPLE_RATE: f64 = 44100.0;
+const MAX_BUFFER_SIZE: usize = 1024;
+const BLOCK_COUNT: usize = 64;
+
+#[repr(C)]
+struct Host {
+    clap_host: clap_host,
+    main_thread: ThreadId,
+    audio_thread: Mutex<Option<ThreadId>>,
+    callback_requested: AtomicBool,
+    violations: Mutex<Vec<String>>,
+}
+
+impl Host {
+    fn new() -> Box<Host> {
+        Box::new(Host {
+            clap_host: clap_host {
+                clap_version: CLAP_VERSION,
+                host_data: ptr::null_mut(),
+                name: c"cargo-coupler validate".as_ptr(),
+                vendor: c"".as_ptr(),
+                url: c"".as_ptr(),
+                version: c"".as_ptr(),
+                get_extension: Some(Self::get_extension),
+                request_restart: Some(Self::request_restart),
+                request_process: Some(Self::request_process),
+                request_callback: Some(Self::request_callback),
+            },
+            main_thread: thread::current().id(),
+            audio_thread: Mutex::new(None),
+            callback_requested: AtomicBool::new(false),
+            violations: Mutex::new(Vec::new()),
+        })
+    }
+
+    unsafe fn from_ptr<'a>(host: *const clap_host) -> &'a Host {
+        unsafe { &*(host as *const Host) }
+    }
+
+    fn is_main_thread(&self) -> bool {
+        thread::current().id() == self.main_thread
+    }
+
+    fn is_audio_thread(&self) -> bool {
+        *self.audio_thread.lock().unwrap() == Some(thread::current().id())
+    }
+
+    fn require_main_thread(&self, function: &str) {
+        if !self.is_main_thread() {
+            self.violations
+                .lock()
+                .unwrap()
+                .push(format!("{function} called off the main thread"));
+        }
+    }
+
+    fn forbid_audio_thread(&self, function: &str) {
+        if self.is_audio_thread() {
+            self.violations
+                .lock()
+                .unwrap()
+                .push(format!("{function} called on the audio thread"));
+        }
+    }
+
+    const THREAD_CHECK: clap_host_thread_check = clap_host_thread_check {
+        is_main_thread: Some(Self::thread_check_is_main_thread),
+        is_audio_thread: Some(Self::thread_check_is_audio_thread),
+    };
+
+    const PARAMS: clap_host_params = clap_host_params {
+        rescan: Some(Self::params_rescan),
+        clear: Some(Self::params_clear),
+        request_flush: Some(Self::params_request_flush),
+    };
+
+    const STATE: clap_host_state = clap_host_state {
+        mark_dirty: Some(Self::state_mark_dirty),
+    };
+
+    unsafe extern "C" fn get_extension(
+        _host: *const clap_host,
+        extension_id: *const c_char,
+    ) -> *const c_void {
+        let extension_id = unsafe { CStr::from_ptr(extension_id) };
+
+        if extension_id == CLAP_EXT_THREAD_CHECK {
+            return &Self::THREAD_CHECK as *const _ as *const c_void;
+        }
+
+        if extension_id == CLAP_EXT_PARAMS {
+            return &Self::PARAMS as *const _ as *const c_void;
+        }
+
+        if extension_id == CLAP_EXT_STATE {
+            return &Self::STATE as *const _ as *const c_void;
+        }
+
+        ptr::null()
+    }
+
+    unsafe extern "C" fn request_restart(_host: *const clap_host) {}
+
+    unsafe extern "C" fn request_process(_host: *const clap_host) {}
+
+    unsafe extern "C" fn request_callback(host: *const clap_host) {
+        let host = unsafe { Self::from_ptr(host) };
+        host.callback_requested.store(true, Ordering::Release);
+    }
+
+    unsafe extern "C" fn thread_check_is_main_thread(host: *const clap_host) -> bool {
+        unsafe { Self::from_ptr(host) }.is_main_thread()
+    }
+
+    unsafe extern "C" fn thread_check_is_audio_thread(host: *const clap_host) -> bool {
+        unsafe { Self::from_ptr(host) }.is_audio_thread()
+    }
+
+    unsafe extern "C" fn params_rescan(host: *const clap_host, _flags: clap_param_rescan_flags) {
+        unsafe { Self::from_ptr(host) }.require_main_thread("clap_host_params.rescan");
+    }
+
+    unsafe extern "C" fn params_clear(
+        host: *const clap_host,
+        _param_id: clap_id,
+        _flags: clap_param_clear_flags,
+    ) {
+        unsafe { Self::from_ptr(host) }.require_main_thread("clap_host_params.clear");
+    }
+
+    unsafe extern "C" fn params_request_flush(host: *const clap_host) {
+        unsafe { Self::from_ptr(host) }.forbid_audio_thread("clap_host_params.request_flush");
+    }
+
+    unsafe extern "C" fn state_mark_dirty(host: *const clap_host) {
+        unsafe { Self::from_ptr(host) }.require_main_thread("clap_host_state.mark_dirty");
+    }
+}
+
+unsafe extern "C" fn input_events_size(_list: *const clap_input_events) -> u32 {
+    0
+}
+
+unsafe extern "C" fn input_events_get(
+    _list: *const clap_input_events,
+    _index: u32,
+) -> *const clap_event_header {
+    ptr::null()
+}
+
+const INPUT_EVENTS: clap_input_events = clap_input_events {
+    ctx: ptr::null_mut(),
+    size: Some(input_events_size),
+    get: Some(input_events_get),
+};
+
+unsafe extern "C" fn output_events_try_push(
+    _list: *const clap_output_events,
+    _event: *const clap_event_header,
+) -> bool {
+    true
+}
+
+const OUTPUT_EVENTS: clap_output_events = clap_output_events {
+    ctx: ptr::null_mut(),
+    try_push: Some(output_events_try_push),
+};
+
+unsafe extern "C" fn ostream_write(
+    stream: *const clap_ostream,
+    buffer: *const c_void,
+    size: u64,
+) -> i64 {
+    let data = unsafe { &mut *((*stream).ctx as *mut Vec<u8>) };
+    if size > 0 {
+        data.extend_from_slice(unsafe {
+            slice::from_raw_parts(buffer as *const u8, size as usize)
+        });
+    }
+
+    size as i64
+}
+
+unsafe extern "C" fn istream_read(
+    stream: *const clap_istream,
+    buffer: *mut c_void,
+    size: u64,
+) -> i64 {
+    let data = unsafe { &mut *((*stream).ctx as *mut &[u8]) };
+
+    let count = (size as usize).min(data.len());
+    if count > 0 {
+        let buffer = unsafe { slice::from_raw_parts_mut(buffer as *mut u8, count) };
+        buffer.copy_from_slice(&data[..count]);
+        *data = &data[count..];
+    }
+
+    count as i64
+}
+
+/// Pointers which are moved to the audio thread while processing.
+struct SendPtr<T>(*const T);
+
+unsafe impl<T> Send for SendPtr<T> {}
+
+impl<T> SendPtr<T> {
+    fn get(self) -> *const T {
+        self.0
+    }
+}
+
+struct Instance<'a> {
+    host: &'a Host,
+    plugin: *const clap_plugin,
+}
+
+impl<'a> Instance<'a> {
+    fn new(
+        factory: *const clap_plugin_factory,
+        host: &'a Host,
+        id: &CStr,
+    ) -> Result<Instance<'a>, String> {
+        let plugin =
+            unsafe { (*factory).create_plugin.unwrap()(factory, &host.clap_host, id.as_ptr()) };
+        if plugin.is_null() {
+            return Err("create_plugin returned null".to_string());
+        }
+
+        let instance = Instance { host, plugin };
+        if !unsafe { (*plugin).init.unwrap()(plugin) } {
+            return Err("init returned false".to_string());
+        }
+
+        Ok(instance)
+    }
+
+    fn extension<T>(&self, id: &CStr) -> Option<&T> {
+        let ext = unsafe { (*self.plugin).get_extension.unwrap()(self.plugin, id.as_ptr()) };
+        unsafe { (ext as *const T).as_ref() }
+    }
+
+    fn on_main_thread(&self) {
+        if self.host.callback_requested.swap(false, Ordering::Acquire) {
+            unsafe { (*self.plugin).on_main_thread.unwrap()(self.plugin) };
+        }
+    }
+
+    fn params(&self) -> Result<Vec<clap_param_info>, String> {
+        let Some(params) = self.extension::<clap_plugin_params>(CLAP_EXT_PARAMS) else {
+            return Ok(Vec::new());
+        };
+
+        let count = unsafe { params.count.unwrap()(self.plugin) };
+        (0..count)
+            .map(|index| {
+                let mut info: clap_param_info = unsafe { mem::zeroed() };
+                if unsafe { params.get_info.unwrap()(self.plugin, index, &mut info) } {
+                    Ok(info)
+                } else {
+                    Err(format!("get_info failed for parameter {index}"))
+                }
+            })
+            .collect()
+    }
+
+    fn value_to_text(
+        &self,
+        params: &clap_plugin_params,
+        id: clap_id,
+        value: f64,
+    ) -> Option<String> {
+        let mut buffer = [0 as c_char; 256];
+        let result = unsafe {
+            params.value_to_text.unwrap()(
+                self.plugin,
+                id,
+                value,
+                buffer.as_mut_ptr(),
+                buffer.len() as u32,
+            )
+        };
+
+        if !result || !buffer.contains(&0) {
+            return None;
+        }
+
+        let text = unsafe { CStr::from_ptr(buffer.as_ptr()) };
+        Some(text.to_string_lossy().into_owned())
+    }
+
+    fn save(&self) -> Result<Vec<u8>, String> {
+        let state = self
+            .extension::<clap_plugin_state>(CLAP_EXT_STATE)
+            .ok_or("no state extension")?;
+
+        let mut data = Vec::new();
+        let stream = clap_ostream {
+            ctx: &mut data as *mut Vec<u8> as *mut c_void,
+            write: Some(ostream_write),
+        };
+
+        if !unsafe { state.save.unwrap()(self.plugin, &stream) } {
+            return Err("save returned false".to_string());
+        }
+
+        Ok(data)
+    }
+
+    fn load(&self, mut data: &[u8]) -> Result<(), String> {
+        let state = self
+            .extension::<clap_plugin_state>(CLAP_EXT_STATE)
+            .ok_or("no state extension")?;
+
+        let stream = clap_istream {
+            ctx: &mut data as *mut &[u8] as *mut c_void,
+            read: Some(istream_read),
+        };
+
+        let result = unsafe { state.load.unwrap()(self.plugin, &stream) };
+        self.on_main_thread();
+
+        if !result {
+            return Err("load returned false".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn channel_counts(&self, is_input: bool) -> Result<Vec<usize>, String> {
+        let Some(audio_ports) = self.extension::<clap_plugin_audio_ports>(CLAP_EXT_AUDIO_PORTS)
+        else {
+            return Ok(Vec::new());
+        };
+
+        let count = unsafe { audio_ports.count.unwrap()(self.plugin, is_input) };
+        (0..count)
+            .map(|index| {
+                let mut info: clap_audio_port_info = unsafe { mem::zeroed() };
+                if unsafe { audio_ports.get.unwrap()(self.plugin, index, is_input, &mut info) } {
+                    Ok(info.channel_count as usize)
+                } else {
+                    Err(format!("get failed for audio port {index}"))
+                }
+            })
+            .collect()
+    }
+}
+
+impl<'a> Drop for Instance<'a> {
+    fn drop(&mut self) {
+        unsafe { (*self.plugin).destroy.unwrap()(self.plugin) };
+    }
+}
+
+pub fn validate(path: &Path, report: &mut Report, rng: &mut Rng) {
+    let library = match unsafe { Library::new(path) } {
+        Ok(library) => library,
+        Err(error) => {
+            report.fail("load library", error.to_string());
+            return;
+        }
+    };
+
+    let entry = match unsafe { library.get::<*const clap_plugin_entry>(b"clap_entry\0") } {
+        Ok(entry) => unsafe { &**entry },
+        Err(error) => {
+            report.fail("find clap_entry", error.to_string());
+            return;
+        }
+    };
+
+    report.check("entry version", || {
+        if clap_version_is_compatible(entry.clap_version) {
+            Ok(())
+        } else {
+            Err("incompatible CLAP version".to_string())
+        }
+    });
+
+    let path_str = CString::new(path.to_string_lossy().as_bytes()).unwrap_or_default();
+    if !unsafe { entry.init.unwrap()(path_str.as_ptr()) } {
+        report.fail("entry init", "init returned false");
+        return;
+    }
+
+    let factory = unsafe { entry.get_factory.unwrap()(CLAP_PLUGIN_FACTORY_ID.as_ptr()) }
+        as *const clap_plugin_factory;
+
+    let mut ids = Vec::new();
+    report.check("plugin factory", || {
+        if factory.is_null() {
+            return Err("get_factory returned null".to_string());
+        }
+
+        let count = unsafe { (*factory).get_plugin_count.unwrap()(factory) };
+        if count == 0 {
+            return Err("factory contains no plugins".to_string());
+        }
+
+        for index in 0..count {
+            let desc = unsafe { (*factory).get_plugin_descriptor.unwrap()(factory, index) };
+            let Some(desc) = (unsafe { desc.as_ref() }) else {
+                return Err(format!("descriptor {index} is null"));
+            };
+
+            if !clap_version_is_compatible(desc.clap_version) {
+                return Err(format!(
+                    "descriptor {index} has an incompatible CLAP version"
+                ));
+            }
+            if desc.id.is_null() || desc.name.is_null() {
+                return Err(format!("descriptor {index} is missing an id or name"));
+            }
+
+            let id = unsafe { CStr::from_ptr(desc.id) };
+            if id.is_empty() {
+                return Err(format!("descriptor {index} has an empty id"));
+            }
+            if ids.iter().any(|other: &CString| other.as_c_str() == id) {
+                return Err(format!("duplicate plugin id {id:?}"));
+            }
+            ids.push(id.to_owned());
+        }
+
+        Ok(())
+    });
+
+    for id in &ids {
+        validate_plugin(factory, id, report, rng);
+    }
+
+    unsafe { entry.deinit.unwrap()() };
+}
+
+fn validate_plugin(
+    factory: *const clap_plugin_factory,
+    id: &CStr,
+    report: &mut Report,
+    rng: &mut Rng,
+) {
+    let name = id.to_string_lossy();
+    let host = Host::new();
+
+    report.check(format!("{name}: create and destroy"), || {
+        for _ in 0..3 {
+            let instance = Instance::new(factory, &host, id)?;
+            instance.on_main_thread();
+        }
+
+        Ok(())
+    });
+
+    let instance = match Instance::new(factory, &host, id) {
+        Ok(instance) => instance,
+        Err(error) => {
+            report.fail(format!("{name}: instantiate"), error);
+            return;
+        }
+    };
+
+    report.check(format!("{name}: parameter info"), || {
+        let params = instance.params()?;
+
+        for (index, info) in params.iter().enumerate() {
+            if params[..index].iter().any(|other| other.id == info.id) {
+                return Err(format!("duplicate parameter id {}", info.id));
+            }
+            if !(info.min_value <= info.default_value && info.default_value <= info.max_value) {
+                return Err(format!(
+                    "default value of parameter {} is out of range",
+                    info.id
+                ));
+            }
+        }
+
+        Ok(())
+    });
+
+    report.check(format!("{name}: parameter text round trip"), || {
+        let Some(params_ext) = instance.extension::<clap_plugin_params>(CLAP_EXT_PARAMS) else {
+            return Ok(());
+        };
+
+        for info in instance.params()? {
+            let mut value = 0.0;
+            if !unsafe { params_ext.get_value.unwrap()(instance.plugin, info.id, &mut value) } {
+                return Err(format!("get_value failed for parameter {}", info.id));
+            }
+            if !(info.min_value..=info.max_value).contains(&value) {
+                return Err(format!("value of parameter {} is out of range", info.id));
+            }
+
+            let random = rng.next_u64() as f64 / u64::MAX as f64;
+            let random = info.min_value + random * (info.max_value - info.min_value);
+            for value in [info.min_value, info.default_value, info.max_value, random] {
+                let value = if info.flags & CLAP_PARAM_IS_STEPPED != 0 {
+                    value.round()
+                } else {
+                    value
+                };
+
+                let Some(text) = instance.value_to_text(params_ext, info.id, value) else {
+                    return Err(format!("value_to_text failed for parameter {}", info.id));
+                };
+
+                let text_c = CString::new(text.as_bytes()).unwrap_or_default();
+                let mut parsed = 0.0;
+                let result = unsafe {
+                    params_ext.text_to_value.unwrap()(
+                        instance.plugin,
+                        info.id,
+                        text_c.as_ptr(),
+                        &mut parsed,
+                    )
+                };
+                if !result {
+                    return Err(format!(
+                        "text_to_value failed for parameter {} with text {text:?}",
+                        info.id
+                    ));
+                }
+
+                let round_trip = instance.value_to_text(params_ext, info.id, parsed);
+                if round_trip.as_deref() != Some(text.as_str()) {
+                    return Err(format!(
+                        "parameter {} displays {text:?}, which parses to a value displayed as \
+                         {round_trip:?}",
+                        info.id
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    });
+
+    report.check(format!("{name}: state determinism"), || {
+        let first = instance.save()?;
+        let second = instance.save()?;
+        if first != second {
+            return Err("saving twice produced different state".to_string());
+        }
+
+        instance.load(&first)?;
+        let reloaded = instance.save()?;
+        if first != reloaded {
+            return Err("saving after loading produced different state".to_string());
+        }
+
+        let fresh = Instance::new(factory, &host, id)?;
+        fresh.load(&first)?;
+        if fresh.save()? != first {
+            return Err("loading state into a new instance produced different state".to_string());
+        }
+
+        Ok(())
+    });
+
+    report.check(format!("{name}: process"), || process(&instance, rng));
+
+    report.check(format!("{name}: thread safety"), || {
+        let violations = host.violations.lock().unwrap();
+        if let Some(violation) = violations.first() {
+            return Err(format!(
+                "{violation} ({} violations in total)",
+                violations.len()
+            ));
+        }
+
+        Ok(())
+    });
+}
+
+fn process(instance: &Instance, rng: &mut Rng) -> Result<(), String> {
+    let input_channels = instance.channel_counts(true)?;
+    let output_channels = instance.channel_counts(false)?;
+
+    let plugin = instance.plugin;
+    if !unsafe { (*plugin).activate.unwrap()(plugin, SAMPLE_RATE, 1, MAX_BUFFER_SIZE as u32) } {
+        return Err("activate returned false".to_string());
+    }
+
+    // Always include a zero-length block and a maximum-length block.
+    let mut sizes = vec![0, MAX_BUFFER_SIZE];
+    sizes.extend((0..BLOCK_COUNT).map(|_| rng.below_or_eq(MAX_BUFFER_SIZE)));
+
+    let mut inputs: Vec<Vec<Vec<f32>>> = input_channels
+        .iter()
+        .map(|&count| vec![vec![0.0; MAX_BUFFER_SIZE]; count])
+        .collect();
+    for sample in inputs.iter_mut().flatten().flatten() {
+        *sample = rng.sample();
+    }
+
+    let host = instance.host;
+    let plugin_ptr = SendPtr(plugin);
+    let result = thread::scope(|scope| {
+        scope
+            .spawn(move || {
+                let plugin = plugin_ptr.get();
+                *host.audio_thread.lock().unwrap() = Some(thread::current().id());
+
+                let result = process_blocks(plugin, &sizes, &mut inputs, &output_channels);
+
+                *host.audio_thread.lock().unwrap() = None;
+                result
+            })
+            .join()
+            .unwrap_or_else(|_| Err("audio thread panicked".to_string()))
+    });
+
+    unsafe { (*plugin).deactivate.unwrap()(plugin) };
+    instance.on_main_thread();
+
+    result
+}
+
+fn process_blocks(
+    plugin: *const clap_plugin,
+    sizes: &[usize],
+    inputs: &mut [Vec<Vec<f32>>],
+    output_channels: &[usize],
+) -> Result<(), String> {
+    if !unsafe { (*plugin).start_processing.unwrap()(plugin) } {
+        return Err("start_processing returned false".to_string());
+    }
+
+    let mut outputs: Vec<Vec<Vec<f32>>> = output_channels
+        .iter()
+        .map(|&count| vec![vec![0.0; MAX_BUFFER_SIZE]; count])
+        .collect();
+
+    let mut input_ptrs: Vec<Vec<*mut f32>> = inputs
+        .iter_mut()
+        .map(|bus| bus.iter_mut().map(|channel| channel.as_mut_ptr()).collect())
+        .collect();
+    let mut output_ptrs: Vec<Vec<*mut f32>> = outputs
+        .iter_mut()
+        .map(|bus| bus.iter_mut().map(|channel| channel.as_mut_ptr()).collect())
+        .collect();
+
+    let audio_buffer = |ptrs: &mut Vec<*mut f32>| clap_audio_buffer {
+        data32: ptrs.as_mut_ptr(),
+        data64: ptr::null_mut(),
+        channel_count: ptrs.len() as u32,
+        latency: 0,
+        constant_mask: 0,
+    };
+    let audio_inputs: Vec<clap_audio_buffer> = input_ptrs.iter_mut().map(audio_buffer).collect();
+    let mut audio_outputs: Vec<clap_audio_buffer> =
+        output_ptrs.iter_mut().map(audio_buffer).collect();
+
+    let mut steady_time = 0;
+    let mut result = Ok(());
+    for &size in sizes {
+        let process = clap_process {
+            steady_time,
+            frames_count: size as u32,
+            transport: ptr::null(),
+            audio_inputs: audio_inputs.as_ptr(),
+            audio_outputs: audio_outputs.as_mut_ptr(),
+            audio_inputs_count: audio_inputs.len() as u32,
+            audio_outputs_count: audio_outputs.len() as u32,
+            in_events: &INPUT_EVENTS,
+            out_events: &OUTPUT_EVENTS,
+        };
+
+        let status = unsafe { (*plugin).process.unwrap()(plugin, &process) };
+        if status == CLAP_PROCESS_ERROR {
+            result = Err(format!(
+                "process returned an error for a block of {size} frames"
+            ));
+            break;
+        }
+
+        for bus in &output_ptrs {
+            for &channel in bus {
+                let samples = unsafe { slice::from_raw_parts(channel, size) };
+                if samples.iter().any(|sample| !sample.is_finite()) {
+                    result = Err(format!("non-finite output for a block of {size} frames"));
+                }
+            }
+        }
+        if result.is_err() {
+            break;
+        }
+
+        steady_time += size as i64;
+    }
+
+    unsafe { (*plugin).stop_processing.unwrap()(plugin) };
+
+    result
+}
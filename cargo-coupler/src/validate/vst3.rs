@@ -0,0 +1,727 @@
+use libloading::Library;
+use vst3::Steinberg::Vst::*;
+use vst3::Steinberg::*;
+use vst3::{Class, ComPtr, ComRef, ComWrapper, Interface};
+
+use std::cell::{Cell, RefCell};
+use std::ffi::{CStr, c_char, c_void};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, ThreadId};
+use std::{mem, ptr, slice};
+
+use super::{Report, Rng};
+
+const SAMPLE_RATE: f64 = 44100.0;
+const MAX_BUFFER_SIZE: usize = 1024;
+const BLOCK_COUNT: usize = 64;
+
+// kVstAudioEffectClass from ivstaudioprocessor.h.
+const AUDIO_EFFECT_CLASS: &CStr = c"Audio Module Class";
+
+struct ThreadChecker {
+    main_thread: ThreadId,
+    violations: Mutex<Vec<String>>,
+}
+
+impl ThreadChecker {
+    fn new() -> ThreadChecker {
+        ThreadChecker {
+            main_thread: thread::current().id(),
+            violations: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn require_main_thread(&self, function: &str) {
+        if thread::current().id() != self.main_thread {
+            self.violations
+                .lock()
+                .unwrap()
+                .push(format!("{function} called off the main thread"));
+        }
+    }
+}
+
+struct ComponentHandler {
+    checker: Arc<ThreadChecker>,
+}
+
+impl Class for ComponentHandler {
+    type Interfaces = (IComponentHandler,);
+}
+
+impl IComponentHandlerTrait for ComponentHandler {
+    unsafe fn beginEdit(&self, _id: ParamID) -> tresult {
+        self.checker.require_main_thread("IComponentHandler::beginEdit");
+        kResultOk
+    }
+
+    unsafe fn performEdit(&self, _id: ParamID, _valueNormalized: ParamValue) -> tresult {
+        self.checker.require_main_thread("IComponentHandler::performEdit");
+        kResultOk
+    }
+
+    unsafe fn endEdit(&self, _id: ParamID) -> tresult {
+        self.checker.require_main_thread("IComponentHandler::endEdit");
+        kResultOk
+    }
+
+    unsafe fn restartComponent(&self, _flags: int32) -> tresult {
+        self.checker.require_main_thread("IComponentHandler::restartComponent");
+        kResultOk
+    }
+}
+
+struct MemoryStream {
+    data: RefCell<Vec<u8>>,
+    position: Cell<usize>,
+}
+
+impl MemoryStream {
+    fn new(data: Vec<u8>) -> ComWrapper<MemoryStream> {
+        ComWrapper::new(MemoryStream {
+            data: RefCell::new(data),
+            position: Cell::new(0),
+        })
+    }
+}
+
+impl Class for MemoryStream {
+    type Interfaces = (IBStream,);
+}
+
+impl IBStreamTrait for MemoryStream {
+    unsafe fn read(
+        &self,
+        buffer: *mut c_void,
+        num_bytes: int32,
+        bytes_read: *mut int32,
+    ) -> tresult {
+        let data = self.data.borrow();
+        let position = self.position.get().min(data.len());
+        let count = (num_bytes.max(0) as usize).min(data.len() - position);
+
+        if count > 0 {
+            let buffer = unsafe { slice::from_raw_parts_mut(buffer as *mut u8, count) };
+            buffer.copy_from_slice(&data[position..position + count]);
+            self.position.set(position + count);
+        }
+
+        if !bytes_read.is_null() {
+            unsafe { *bytes_read = count as int32 };
+        }
+
+        kResultOk
+    }
+
+    unsafe fn write(
+        &self,
+        buffer: *mut c_void,
+        num_bytes: int32,
+        bytes_written: *mut int32,
+    ) -> tresult {
+        let mut data = self.data.borrow_mut();
+        let position = self.position.get();
+        let count = num_bytes.max(0) as usize;
+
+        if count > 0 {
+            let buffer = unsafe { slice::from_raw_parts(buffer as *const u8, count) };
+            if data.len() < position + count {
+                data.resize(position + count, 0);
+            }
+            data[position..position + count].copy_from_slice(buffer);
+            self.position.set(position + count);
+        }
+
+        if !bytes_written.is_null() {
+            unsafe { *bytes_written = count as int32 };
+        }
+
+        kResultOk
+    }
+
+    unsafe fn seek(&self, pos: int64, mode: int32, result: *mut int64) -> tresult {
+        // Values from IBStream::IStreamSeekMode in ibstream.h.
+        let base = match mode {
+            0 => 0,
+            1 => self.position.get() as int64,
+            2 => self.data.borrow().len() as int64,
+            _ => return kInvalidArgument,
+        };
+
+        let position = base + pos;
+        if position < 0 {
+            return kInvalidArgument;
+        }
+        self.position.set(position as usize);
+
+        if !result.is_null() {
+            unsafe { *result = position };
+        }
+
+        kResultOk
+    }
+
+    unsafe fn tell(&self, pos: *mut int64) -> tresult {
+        if !pos.is_null() {
+            unsafe { *pos = self.position.get() as int64 };
+        }
+
+        kResultOk
+    }
+}
+
+/// Pointers which are moved to the audio thread while processing.
+struct SendPtr<T>(*mut T);
+
+unsafe impl<T> Send for SendPtr<T> {}
+
+impl<T> SendPtr<T> {
+    fn get(self) -> *mut T {
+        self.0
+    }
+}
+
+fn string_from_chars(chars: &[c_char]) -> String {
+    let bytes: Vec<u8> = chars.iter().map(|&c| c as u8).take_while(|&c| c != 0).collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn string_from_wchars(wchars: &[char16]) -> String {
+    let utf16 = wchars.iter().map(|&c| c as u16).take_while(|&c| c != 0);
+    char::decode_utf16(utf16)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+fn create<I: Interface>(factory: &ComPtr<IPluginFactory>, cid: &TUID) -> Result<ComPtr<I>, String> {
+    let mut obj = ptr::null_mut();
+    let result =
+        unsafe { factory.createInstance(cid.as_ptr(), I::IID.as_ptr() as FIDString, &mut obj) };
+    if result != kResultOk {
+        return Err(format!("createInstance returned {result}"));
+    }
+
+    unsafe { ComPtr::from_raw(obj as *mut I) }.ok_or_else(|| "createInstance returned null".into())
+}
+
+struct Instance {
+    component: ComPtr<IComponent>,
+    controller: ComPtr<IEditController>,
+    separate_controller: bool,
+}
+
+impl Instance {
+    fn new(factory: &ComPtr<IPluginFactory>, cid: &TUID) -> Result<Instance, String> {
+        let component = create::<IComponent>(factory, cid)?;
+
+        let result = unsafe { component.initialize(ptr::null_mut()) };
+        if result != kResultOk {
+            return Err(format!("IComponent::initialize returned {result}"));
+        }
+
+        if let Some(controller) = component.cast::<IEditController>() {
+            return Ok(Instance {
+                component,
+                controller,
+                separate_controller: false,
+            });
+        }
+
+        let mut controller_cid: TUID = [0; 16];
+        let result = unsafe { component.getControllerClassId(&mut controller_cid) };
+        if result != kResultOk {
+            unsafe { component.terminate() };
+            return Err("component has no edit controller".to_string());
+        }
+
+        let controller = match create::<IEditController>(factory, &controller_cid) {
+            Ok(controller) => controller,
+            Err(error) => {
+                unsafe { component.terminate() };
+                return Err(error);
+            }
+        };
+
+        let result = unsafe { controller.initialize(ptr::null_mut()) };
+        if result != kResultOk {
+            unsafe { component.terminate() };
+            return Err(format!("IEditController::initialize returned {result}"));
+        }
+
+        Ok(Instance {
+            component,
+            controller,
+            separate_controller: true,
+        })
+    }
+
+    fn params(&self) -> Result<Vec<ParameterInfo>, String> {
+        (0..unsafe { self.controller.getParameterCount() })
+            .map(|index| {
+                let mut info: ParameterInfo = unsafe { mem::zeroed() };
+                let result = unsafe { self.controller.getParameterInfo(index, &mut info) };
+                if result != kResultOk {
+                    return Err(format!("getParameterInfo failed for parameter {index}"));
+                }
+
+                Ok(info)
+            })
+            .collect()
+    }
+
+    fn value_to_text(&self, id: ParamID, value: ParamValue) -> Option<String> {
+        let mut string: String128 = [0; 128];
+        let result = unsafe { self.controller.getParamStringByValue(id, value, &mut string) };
+        (result == kResultOk).then(|| string_from_wchars(&string))
+    }
+
+    fn text_to_value(&self, id: ParamID, text: &str) -> Option<ParamValue> {
+        let mut string: Vec<TChar> = text.encode_utf16().map(|c| c as TChar).collect();
+        string.push(0);
+
+        let mut value = 0.0;
+        let result =
+            unsafe { self.controller.getParamValueByString(id, string.as_mut_ptr(), &mut value) };
+        (result == kResultOk).then_some(value)
+    }
+
+    fn save(&self) -> Result<Vec<u8>, String> {
+        let stream = MemoryStream::new(Vec::new());
+        let stream_ptr = stream.as_com_ref::<IBStream>().unwrap().as_ptr();
+
+        let result = unsafe { self.component.getState(stream_ptr) };
+        if result != kResultOk {
+            return Err(format!("IComponent::getState returned {result}"));
+        }
+
+        Ok(stream.data.take())
+    }
+
+    fn load(&self, data: &[u8]) -> Result<(), String> {
+        let stream = MemoryStream::new(data.to_vec());
+        let stream_ptr = stream.as_com_ref::<IBStream>().unwrap().as_ptr();
+
+        let result = unsafe { self.component.setState(stream_ptr) };
+        if result != kResultOk {
+            return Err(format!("IComponent::setState returned {result}"));
+        }
+
+        if self.separate_controller {
+            stream.position.set(0);
+            let result = unsafe { self.controller.setComponentState(stream_ptr) };
+            if result != kResultOk {
+                return Err(format!(
+                    "IEditController::setComponentState returned {result}"
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn channel_counts(&self, dir: BusDirections) -> Result<Vec<usize>, String> {
+        let media_type = MediaTypes_::kAudio as MediaType;
+        let dir = dir as BusDirection;
+
+        (0..unsafe { self.component.getBusCount(media_type, dir) })
+            .map(|index| {
+                let mut info: BusInfo = unsafe { mem::zeroed() };
+                let result =
+                    unsafe { self.component.getBusInfo(media_type, dir, index, &mut info) };
+                if result != kResultOk {
+                    return Err(format!("getBusInfo failed for bus {index}"));
+                }
+
+                Ok(info.channelCount as usize)
+            })
+            .collect()
+    }
+}
+
+impl Drop for Instance {
+    fn drop(&mut self) {
+        unsafe {
+            self.controller.setComponentHandler(ptr::null_mut());
+            if self.separate_controller {
+                self.controller.terminate();
+            }
+            self.component.terminate();
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn module_entry(library: &Library) -> Result<(), String> {
+    type ModuleEntry = unsafe extern "system" fn(*mut c_void) -> bool;
+
+    if let Ok(entry) = unsafe { library.get::<ModuleEntry>(b"ModuleEntry\0") }
+        && !unsafe { entry(ptr::null_mut()) }
+    {
+        return Err("ModuleEntry returned false".to_string());
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn module_entry(library: &Library) -> Result<(), String> {
+    type InitDll = unsafe extern "system" fn() -> bool;
+
+    if let Ok(entry) = unsafe { library.get::<InitDll>(b"InitDll\0") }
+        && !unsafe { entry() }
+    {
+        return Err("InitDll returned false".to_string());
+    }
+
+    Ok(())
+}
+
+// bundleEntry on macOS requires a CFBundleRef, which we don't have. Plugins built with Coupler do
+// not depend on it being called.
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn module_entry(_library: &Library) -> Result<(), String> {
+    Ok(())
+}
+
+fn module_exit(library: &Library) {
+    type ModuleExit = unsafe extern "system" fn() -> bool;
+
+    let name: &[u8] = if cfg!(target_os = "windows") {
+        b"ExitDll\0"
+    } else {
+        b"ModuleExit\0"
+    };
+
+    if let Ok(exit) = unsafe { library.get::<ModuleExit>(name) } {
+        unsafe { exit() };
+    }
+}
+
+pub fn validate(path: &Path, report: &mut Report, rng: &mut Rng) {
+    let library = match unsafe { Library::new(path) } {
+        Ok(library) => library,
+        Err(error) => {
+            report.fail("load library", error.to_string());
+            return;
+        }
+    };
+
+    if let Err(error) = module_entry(&library) {
+        report.fail("module entry", error);
+        return;
+    }
+
+    type GetPluginFactory = unsafe extern "system" fn() -> *mut IPluginFactory;
+    let get_plugin_factory = match unsafe { library.get::<GetPluginFactory>(b"GetPluginFactory\0") }
+    {
+        Ok(get_plugin_factory) => get_plugin_factory,
+        Err(error) => {
+            report.fail("find GetPluginFactory", error.to_string());
+            module_exit(&library);
+            return;
+        }
+    };
+
+    let Some(factory) = (unsafe { ComPtr::from_raw(get_plugin_factory()) }) else {
+        report.fail("plugin factory", "GetPluginFactory returned null");
+        module_exit(&library);
+        return;
+    };
+
+    let mut classes = Vec::new();
+    report.check("plugin factory", || {
+        let mut factory_info: PFactoryInfo = unsafe { mem::zeroed() };
+        let result = unsafe { factory.getFactoryInfo(&mut factory_info) };
+        if result != kResultOk {
+            return Err(format!("getFactoryInfo returned {result}"));
+        }
+
+        let count = unsafe { factory.countClasses() };
+        for index in 0..count {
+            let mut info: PClassInfo = unsafe { mem::zeroed() };
+            let result = unsafe { factory.getClassInfo(index, &mut info) };
+            if result != kResultOk {
+                return Err(format!("getClassInfo returned {result} for class {index}"));
+            }
+
+            if classes.iter().any(|(cid, _)| *cid == info.cid) {
+                return Err(format!("duplicate class id for class {index}"));
+            }
+
+            let category = string_from_chars(&info.category);
+            if category == AUDIO_EFFECT_CLASS.to_str().unwrap() {
+                classes.push((info.cid, string_from_chars(&info.name)));
+            }
+        }
+
+        if classes.is_empty() {
+            return Err("factory contains no audio effect classes".to_string());
+        }
+
+        Ok(())
+    });
+
+    for (cid, name) in &classes {
+        validate_plugin(&factory, cid, name, report, rng);
+    }
+
+    drop(factory);
+    module_exit(&library);
+}
+
+fn validate_plugin(
+    factory: &ComPtr<IPluginFactory>,
+    cid: &TUID,
+    name: &str,
+    report: &mut Report,
+    rng: &mut Rng,
+) {
+    let checker = Arc::new(ThreadChecker::new());
+    let handler = ComWrapper::new(ComponentHandler {
+        checker: checker.clone(),
+    });
+    let handler_ptr = handler.as_com_ref::<IComponentHandler>().unwrap().as_ptr();
+
+    report.check(format!("{name}: create and destroy"), || {
+        for _ in 0..3 {
+            Instance::new(factory, cid)?;
+        }
+
+        Ok(())
+    });
+
+    let instance = match Instance::new(factory, cid) {
+        Ok(instance) => instance,
+        Err(error) => {
+            report.fail(format!("{name}: instantiate"), error);
+            return;
+        }
+    };
+    unsafe { instance.controller.setComponentHandler(handler_ptr) };
+
+    report.check(format!("{name}: parameter info"), || {
+        let params = instance.params()?;
+
+        for (index, info) in params.iter().enumerate() {
+            if params[..index].iter().any(|other| other.id == info.id) {
+                return Err(format!("duplicate parameter id {}", info.id));
+            }
+            if !(0.0..=1.0).contains(&info.defaultNormalizedValue) {
+                return Err(format!(
+                    "default value of parameter {} is out of range",
+                    info.id
+                ));
+            }
+
+            let value = unsafe { instance.controller.getParamNormalized(info.id) };
+            if !(0.0..=1.0).contains(&value) {
+                return Err(format!("value of parameter {} is out of range", info.id));
+            }
+        }
+
+        Ok(())
+    });
+
+    report.check(format!("{name}: parameter text round trip"), || {
+        for info in instance.params()? {
+            let random = rng.next_u64() as f64 / u64::MAX as f64;
+            for value in [0.0, info.defaultNormalizedValue, 1.0, random] {
+                let Some(text) = instance.value_to_text(info.id, value) else {
+                    return Err(format!(
+                        "getParamStringByValue failed for parameter {}",
+                        info.id
+                    ));
+                };
+
+                let Some(parsed) = instance.text_to_value(info.id, &text) else {
+                    return Err(format!(
+                        "getParamValueByString failed for parameter {} with text {text:?}",
+                        info.id
+                    ));
+                };
+
+                let round_trip = instance.value_to_text(info.id, parsed);
+                if round_trip.as_deref() != Some(text.as_str()) {
+                    return Err(format!(
+                        "parameter {} displays {text:?}, which parses to a value displayed as \
+                         {round_trip:?}",
+                        info.id
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    });
+
+    report.check(format!("{name}: state determinism"), || {
+        let first = instance.save()?;
+        let second = instance.save()?;
+        if first != second {
+            return Err("saving twice produced different state".to_string());
+        }
+
+        instance.load(&first)?;
+        let reloaded = instance.save()?;
+        if first != reloaded {
+            return Err("saving after loading produced different state".to_string());
+        }
+
+        let fresh = Instance::new(factory, cid)?;
+        fresh.load(&first)?;
+        if fresh.save()? != first {
+            return Err("loading state into a new instance produced different state".to_string());
+        }
+
+        Ok(())
+    });
+
+    report.check(format!("{name}: process"), || process(&instance, rng));
+
+    report.check(format!("{name}: thread safety"), || {
+        let violations = checker.violations.lock().unwrap();
+        if let Some(violation) = violations.first() {
+            return Err(format!(
+                "{violation} ({} violations in total)",
+                violations.len()
+            ));
+        }
+
+        Ok(())
+    });
+}
+
+fn process(instance: &Instance, rng: &mut Rng) -> Result<(), String> {
+    let input_channels = instance.channel_counts(BusDirections_::kInput)?;
+    let output_channels = instance.channel_counts(BusDirections_::kOutput)?;
+
+    let processor = instance
+        .component
+        .cast::<IAudioProcessor>()
+        .ok_or("component does not implement IAudioProcessor")?;
+
+    let mut setup = ProcessSetup {
+        processMode: ProcessModes_::kRealtime as int32,
+        symbolicSampleSize: SymbolicSampleSizes_::kSample32 as int32,
+        maxSamplesPerBlock: MAX_BUFFER_SIZE as int32,
+        sampleRate: SAMPLE_RATE,
+    };
+    let result = unsafe { processor.setupProcessing(&mut setup) };
+    if result != kResultOk {
+        return Err(format!("setupProcessing returned {result}"));
+    }
+
+    let result = unsafe { instance.component.setActive(1) };
+    if result != kResultOk {
+        return Err(format!("setActive returned {result}"));
+    }
+
+    // Always include a zero-length block and a maximum-length block.
+    let mut sizes = vec![0, MAX_BUFFER_SIZE];
+    sizes.extend((0..BLOCK_COUNT).map(|_| rng.below_or_eq(MAX_BUFFER_SIZE)));
+
+    let mut inputs: Vec<Vec<Vec<f32>>> = input_channels
+        .iter()
+        .map(|&count| vec![vec![0.0; MAX_BUFFER_SIZE]; count])
+        .collect();
+    for sample in inputs.iter_mut().flatten().flatten() {
+        *sample = rng.sample();
+    }
+
+    let processor_ptr = SendPtr(processor.as_ptr());
+    let result = thread::scope(|scope| {
+        scope
+            .spawn(move || {
+                let processor = processor_ptr.get();
+                let processor = unsafe { ComRef::from_raw(processor) }.unwrap();
+                process_blocks(processor, &sizes, &mut inputs, &output_channels)
+            })
+            .join()
+            .unwrap_or_else(|_| Err("audio thread panicked".to_string()))
+    });
+
+    unsafe { instance.component.setActive(0) };
+
+    result
+}
+
+fn process_blocks(
+    processor: ComRef<IAudioProcessor>,
+    sizes: &[usize],
+    inputs: &mut [Vec<Vec<f32>>],
+    output_channels: &[usize],
+) -> Result<(), String> {
+    let result = unsafe { processor.setProcessing(1) };
+    if result != kResultOk && result != kNotImplemented {
+        return Err(format!("setProcessing returned {result}"));
+    }
+
+    let mut outputs: Vec<Vec<Vec<f32>>> = output_channels
+        .iter()
+        .map(|&count| vec![vec![0.0; MAX_BUFFER_SIZE]; count])
+        .collect();
+
+    let mut input_ptrs: Vec<Vec<*mut f32>> = inputs
+        .iter_mut()
+        .map(|bus| bus.iter_mut().map(|channel| channel.as_mut_ptr()).collect())
+        .collect();
+    let mut output_ptrs: Vec<Vec<*mut f32>> = outputs
+        .iter_mut()
+        .map(|bus| bus.iter_mut().map(|channel| channel.as_mut_ptr()).collect())
+        .collect();
+
+    let audio_bus_buffers = |ptrs: &mut Vec<*mut f32>| AudioBusBuffers {
+        numChannels: ptrs.len() as int32,
+        silenceFlags: 0,
+        __field0: AudioBusBuffers__type0 {
+            channelBuffers32: ptrs.as_mut_ptr(),
+        },
+    };
+    let mut audio_inputs: Vec<AudioBusBuffers> =
+        input_ptrs.iter_mut().map(audio_bus_buffers).collect();
+    let mut audio_outputs: Vec<AudioBusBuffers> =
+        output_ptrs.iter_mut().map(audio_bus_buffers).collect();
+
+    let mut result = Ok(());
+    for &size in sizes {
+        let mut data = ProcessData {
+            processMode: ProcessModes_::kRealtime as int32,
+            symbolicSampleSize: SymbolicSampleSizes_::kSample32 as int32,
+            numSamples: size as int32,
+            numInputs: audio_inputs.len() as int32,
+            numOutputs: audio_outputs.len() as int32,
+            inputs: audio_inputs.as_mut_ptr(),
+            outputs: audio_outputs.as_mut_ptr(),
+            inputParameterChanges: ptr::null_mut(),
+            outputParameterChanges: ptr::null_mut(),
+            inputEvents: ptr::null_mut(),
+            outputEvents: ptr::null_mut(),
+            processContext: ptr::null_mut(),
+        };
+
+        let status = unsafe { processor.process(&mut data) };
+        if status != kResultOk {
+            result = Err(format!(
+                "process returned {status} for a block of {size} frames"
+            ));
+            break;
+        }
+
+        for bus in &output_ptrs {
+            for &channel in bus {
+                let samples = unsafe { slice::from_raw_parts(channel, size) };
+                if samples.iter().any(|sample| !sample.is_finite()) {
+                    result = Err(format!("non-finite output for a block of {size} frames"));
+                }
+            }
+        }
+        if result.is_err() {
+            break;
+        }
+    }
+
+    unsafe { processor.setProcessing(0) };
+
+    result
+}
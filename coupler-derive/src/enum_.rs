@@ -119,6 +119,10 @@ pub fn expand_enum(input: &DeriveInput) -> Result<TokenStream, Error> {
                     _ => #ident::#last_variant,
                 }
             }
+
+            fn value_names() -> ::std::option::Option<&'static [&'static ::std::primitive::str]> {
+                ::std::option::Option::Some(&[#(#names),*])
+            }
         }
 
         impl #impl_generics ::std::str::FromStr for #ident #ty_generics #where_clause {
@@ -1,6 +1,8 @@
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::{Data, DeriveInput, Error, Expr, Field, Fields, LitInt, LitStr};
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use syn::{Data, DeriveInput, Error, Expr, Field, Fields, LitInt, LitStr, Type};
 
 struct Reserved {
     key: LitStr,
@@ -60,12 +62,50 @@ fn parse_reserved(input: &DeriveInput) -> Result<Vec<Reserved>, Error> {
     Ok(reserved)
 }
 
+// Whether the struct has a `#[params(default)]` attribute.
+fn parse_derive_default(input: &DeriveInput) -> Result<bool, Error> {
+    let mut derive_default = false;
+
+    for attr in &input.attrs {
+        if !attr.path().is_ident("params") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            let ident = meta.path.get_ident().ok_or_else(|| {
+                Error::new_spanned(&meta.path, "expected this path to be an identifier")
+            })?;
+
+            if ident == "default" {
+                if derive_default {
+                    return Err(Error::new_spanned(
+                        &meta.path,
+                        "duplicate params attribute `default`",
+                    ));
+                }
+
+                derive_default = true;
+            } else {
+                return Err(Error::new_spanned(
+                    &meta.path,
+                    format!("unknown params attribute `{}`", ident),
+                ));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(derive_default)
+}
+
 struct ParamAttrs {
     key: LitStr,
     generation: Option<LitInt>,
     name: LitStr,
     range: TokenStream,
     format: TokenStream,
+    default: Option<TokenStream>,
 }
 
 fn parse_param(field: &Field) -> Result<Option<ParamAttrs>, Error> {
@@ -76,6 +116,7 @@ fn parse_param(field: &Field) -> Result<Option<ParamAttrs>, Error> {
     let mut name = None;
     let mut range = None;
     let mut format = None;
+    let mut default = None;
 
     for attr in &field.attrs {
         if !attr.path().is_ident("param") {
@@ -134,6 +175,15 @@ fn parse_param(field: &Field) -> Result<Option<ParamAttrs>, Error> {
                 }
 
                 format = Some(meta.value()?.parse::<Expr>()?);
+            } else if ident == "default" {
+                if default.is_some() {
+                    return Err(Error::new_spanned(
+                        &meta.path,
+                        "duplicate param attribute `default`",
+                    ));
+                }
+
+                default = Some(meta.value()?.parse::<Expr>()?);
             } else {
                 return Err(Error::new_spanned(
                     &meta.path,
@@ -175,21 +225,121 @@ fn parse_param(field: &Field) -> Result<Option<ParamAttrs>, Error> {
         quote! { ::coupler::params::DefaultFormat }
     };
 
+    let default = default.map(|default| quote! { #default });
+
     Ok(Some(ParamAttrs {
         key,
         generation,
         name,
         range,
         format,
+        default,
     }))
 }
 
+struct NestedAttrs<'a> {
+    prefix: LitStr,
+    ty: &'a Type,
+    len: Option<&'a Expr>,
+}
+
+fn parse_nested(field: &Field) -> Result<Option<NestedAttrs<'_>>, Error> {
+    let mut is_nested = false;
+
+    let mut prefix = None;
+
+    for attr in &field.attrs {
+        if !attr.path().is_ident("params") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            let ident = meta.path.get_ident().ok_or_else(|| {
+                Error::new_spanned(&meta.path, "expected this path to be an identifier")
+            })?;
+
+            if ident == "nested" {
+                if is_nested {
+                    return Err(Error::new_spanned(
+                        &meta.path,
+                        "duplicate params attribute `nested`",
+                    ));
+                }
+
+                is_nested = true;
+            } else if ident == "prefix" {
+                if prefix.is_some() {
+                    return Err(Error::new_spanned(
+                        &meta.path,
+                        "duplicate params attribute `prefix`",
+                    ));
+                }
+
+                prefix = Some(meta.value()?.parse::<LitStr>()?);
+            } else {
+                return Err(Error::new_spanned(
+                    &meta.path,
+                    format!("unknown params attribute `{}`", ident),
+                ));
+            }
+
+            Ok(())
+        })?;
+
+        if !is_nested {
+            return Err(Error::new_spanned(attr, "missing `nested` attribute"));
+        }
+    }
+
+    if !is_nested {
+        return Ok(None);
+    }
+
+    let prefix = if let Some(prefix) = prefix {
+        prefix
+    } else {
+        let ident = field.ident.as_ref().unwrap();
+        LitStr::new(&format!("{}_", ident), ident.span())
+    };
+
+    let (ty, len) = match &field.ty {
+        Type::Array(array) => (&*array.elem, Some(&array.len)),
+        ty => (ty, None),
+    };
+
+    Ok(Some(NestedAttrs { prefix, ty, len }))
+}
+
+enum FieldKind<'a> {
+    Param(ParamAttrs),
+    Nested(NestedAttrs<'a>),
+}
+
 struct ParamField<'a> {
     field: &'a Field,
-    param: ParamAttrs,
+    kind: FieldKind<'a>,
 }
 
-fn parse_fields(input: &DeriveInput) -> Result<Vec<ParamField<'_>>, Error> {
+impl<'a> ParamField<'a> {
+    // Number of parameter indices occupied by this field.
+    fn count(&self) -> TokenStream {
+        match &self.kind {
+            FieldKind::Param(_) => quote! { 1usize },
+            FieldKind::Nested(nested) => {
+                let ty = &nested.ty;
+                let count = quote! { <#ty as ::coupler::params::Params>::PARAM_COUNT };
+
+                if let Some(len) = &nested.len {
+                    quote! { (#len) * #count }
+                } else {
+                    count
+                }
+            }
+        }
+    }
+}
+
+fn all_fields(input: &DeriveInput) -> Result<&Punctuated<Field, Comma>, Error> {
     let body = match &input.data {
         Data::Struct(body) => body,
         _ => {
@@ -200,22 +350,35 @@ fn parse_fields(input: &DeriveInput) -> Result<Vec<ParamField<'_>>, Error> {
         }
     };
 
-    let fields = match &body.fields {
-        Fields::Named(fields) => fields,
-        _ => {
-            return Err(Error::new_spanned(
-                input,
-                "#[derive(Params)] can only be used on structs with named fields",
-            ));
-        }
-    };
+    match &body.fields {
+        Fields::Named(fields) => Ok(&fields.named),
+        _ => Err(Error::new_spanned(
+            input,
+            "#[derive(Params)] can only be used on structs with named fields",
+        )),
+    }
+}
 
+fn parse_fields(input: &DeriveInput) -> Result<Vec<ParamField<'_>>, Error> {
     let mut param_fields = Vec::new();
 
-    for field in &fields.named {
-        if let Some(param) = parse_param(field)? {
-            param_fields.push(ParamField { field, param });
-        }
+    for field in all_fields(input)? {
+        let param = parse_param(field)?;
+        let nested = parse_nested(field)?;
+
+        let kind = match (param, nested) {
+            (Some(param), None) => FieldKind::Param(param),
+            (None, Some(nested)) => FieldKind::Nested(nested),
+            (Some(_), Some(_)) => {
+                return Err(Error::new_spanned(
+                    field,
+                    "a field cannot have both `param` and `params` attributes",
+                ));
+            }
+            (None, None) => continue,
+        };
+
+        param_fields.push(ParamField { field, kind });
     }
 
     Ok(param_fields)
@@ -223,105 +386,347 @@ fn parse_fields(input: &DeriveInput) -> Result<Vec<ParamField<'_>>, Error> {
 
 pub fn expand_params(input: &DeriveInput) -> Result<TokenStream, Error> {
     let reserved = parse_reserved(input)?;
+    let derive_default = parse_derive_default(input)?;
     let fields = parse_fields(input)?;
 
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
     let ident = &input.ident;
 
+    // The index of the first parameter belonging to each field, followed by the total count.
+    let mut offsets = vec![quote! { 0usize }];
+    for field in &fields {
+        let prev = offsets.last().unwrap();
+        let count = field.count();
+        offsets.push(quote! { #prev + #count });
+    }
+    let param_count = offsets.last().unwrap();
+
     let reserved_keys = reserved.iter().map(|reserved| {
         let key = &reserved.key;
 
-        if let Some(generation) = &reserved.generation {
+        let key = if let Some(generation) = &reserved.generation {
             quote! { ::coupler::key::Key::new(#generation, #key) }
         } else {
             quote! { #key }
-        }
-    });
-
-    let param_keys = fields.iter().map(|field| {
-        let key = &field.param.key;
+        };
 
-        if let Some(generation) = &field.param.generation {
-            quote! { ::coupler::key::Key::new(#generation, #key) }
-        } else {
-            quote! { #key }
+        quote! {
+            __build = __build.map(|__build| __build.reserve(#key));
         }
     });
 
-    let param_infos = fields.iter().map(|field| {
+    let param_entries = fields.iter().map(|field| {
         let ident = field.field.ident.as_ref().unwrap();
-        let ty = &field.field.ty;
-        let name = &field.param.name;
-        let range = &field.param.range;
 
-        quote! {
-            ::coupler::params::ParamInfo {
-                name: #name,
-                default: ::coupler::params::Range::<#ty>::encode(&(#range), &__default.#ident),
-                steps: ::coupler::params::Range::<#ty>::steps(&(#range)),
+        match &field.kind {
+            FieldKind::Param(param) => {
+                let ty = &field.field.ty;
+                let name = &param.name;
+                let range = &param.range;
+
+                let key = if let Some(generation) = &param.generation {
+                    let key = &param.key;
+                    quote! { ::coupler::key::Key::new(#generation, #key) }
+                } else {
+                    let key = &param.key;
+                    quote! { #key }
+                };
+
+                let default = if let Some(default) = &param.default {
+                    quote! { (#default) }
+                } else {
+                    quote! { __default.#ident }
+                };
+
+                quote! {
+                    __build = __build.map(|__build| {
+                        let __range = #range;
+                        let mut __info = ::coupler::params::ParamInfo::new(
+                            #name,
+                            ::coupler::params::Range::<#ty>::encode(&__range, &#default),
+                        );
+                        __info.steps = ::coupler::params::Range::<#ty>::steps(&__range);
+                        __info.value_names = ::coupler::params::Range::<#ty>::value_names(&__range);
+                        __build.param(#key, __info)
+                    });
+                }
+            }
+            FieldKind::Nested(nested) => {
+                let prefix = &nested.prefix;
+
+                if nested.len.is_some() {
+                    quote! {
+                        for (__item_index, __item) in self.#ident.iter().enumerate() {
+                            ::coupler::params::Params::params(
+                                __item,
+                                ::coupler::params::Prefixed::indexed(
+                                    #prefix,
+                                    __item_index,
+                                    &mut __build,
+                                ),
+                            );
+                        }
+                    }
+                } else {
+                    quote! {
+                        ::coupler::params::Params::params(
+                            &self.#ident,
+                            ::coupler::params::Prefixed::new(#prefix, &mut __build),
+                        );
+                    }
+                }
             }
         }
     });
 
-    let set_cases = fields.iter().enumerate().map(|(index, field)| {
-        let ident = &field.field.ident;
-        let ty = &field.field.ty;
-        let range = &field.param.range;
+    // Only fall back to the `Default` impl if there is a parameter without an explicit default.
+    let needs_default = fields.iter().any(|field| match &field.kind {
+        FieldKind::Param(param) => param.default.is_none(),
+        FieldKind::Nested(_) => false,
+    });
 
+    let default = if needs_default {
         quote! {
-            #index => {
-                self.#ident = ::coupler::params::Range::<#ty>::decode(&(#range), __value);
-            }
+            let __default: #ident #ty_generics = ::std::default::Default::default();
         }
-    });
+    } else {
+        quote! {}
+    };
 
-    let get_cases = fields.iter().enumerate().map(|(index, field)| {
-        let ident = &field.field.ident;
+    // When the `Default` impl is used, any explicit defaults must agree with it, or a freshly
+    // constructed plugin would start out in a different state than the one reported to the host.
+    let default_checks = fields.iter().filter_map(|field| {
+        let FieldKind::Param(param) = &field.kind else {
+            return None;
+        };
+        let declared = param.default.as_ref().filter(|_| needs_default)?;
+
+        let ident = field.field.ident.as_ref().unwrap();
         let ty = &field.field.ty;
-        let range = &field.param.range;
+        let range = &param.range;
+
+        Some(quote! {
+            ::std::debug_assert!(
+                ::coupler::params::Range::<#ty>::encode(&(#range), &(#declared))
+                    == ::coupler::params::Range::<#ty>::encode(&(#range), &__default.#ident),
+                "declared default for `{}` does not match the `Default` impl",
+                ::std::stringify!(#ident),
+            );
+        })
+    });
 
-        quote! {
-            #index => ::coupler::params::Range::<#ty>::encode(&(#range), &self.#ident),
+    // With `#[params(default)]`, generate the `Default` impl from the declared defaults.
+    let default_impl = if derive_default {
+        if needs_default {
+            return Err(Error::new_spanned(
+                input,
+                "`#[params(default)]` requires every `#[param]` field to declare a `default`",
+            ));
         }
-    });
 
-    let parse_cases = fields.iter().enumerate().map(|(index, field)| {
-        let ty = &field.field.ty;
-        let range = &field.param.range;
-        let format = &field.param.format;
+        let field_defaults = all_fields(input)?.iter().map(|field| {
+            let ident = field.ident.as_ref().unwrap();
+
+            let param_field = fields
+                .iter()
+                .find(|param_field| param_field.field.ident.as_ref() == Some(ident));
+
+            match param_field.map(|param_field| &param_field.kind) {
+                Some(FieldKind::Param(param)) => {
+                    let default = param.default.as_ref().unwrap();
+                    quote! { #ident: #default }
+                }
+                Some(FieldKind::Nested(nested)) if nested.len.is_some() => {
+                    quote! {
+                        #ident: ::std::array::from_fn(|_| ::std::default::Default::default())
+                    }
+                }
+                _ => quote! { #ident: ::std::default::Default::default() },
+            }
+        });
 
         quote! {
-            #index => match ::coupler::params::Format::<#ty>::parse(&(#format), __text) {
-                ::std::option::Option::Some(__value) => ::std::option::Option::Some(
-                    ::coupler::params::Range::<#ty>::encode(&(#range), &__value),
-                ),
-                _ => ::std::option::Option::None,
+            impl #impl_generics ::std::default::Default for #ident #ty_generics #where_clause {
+                fn default() -> Self {
+                    #ident {
+                        #(#field_defaults,)*
+                    }
+                }
             }
         }
-    });
+    } else {
+        quote! {}
+    };
 
-    let display_cases = fields.iter().enumerate().map(|(index, field)| {
-        let ty = &field.field.ty;
-        let range = &field.param.range;
-        let format = &field.param.format;
+    let reset_entries = fields.iter().map(|field| {
+        let ident = field.field.ident.as_ref().unwrap();
 
-        quote! {
-            #index => ::coupler::params::Format::<#ty>::display(
-                &(#format),
-                ::coupler::params::Range::<#ty>::decode(&(#range), __value),
-                __write,
-            ),
+        match &field.kind {
+            FieldKind::Param(param) => {
+                if let Some(default) = &param.default {
+                    quote! {
+                        self.#ident = #default;
+                    }
+                } else {
+                    quote! {
+                        self.#ident = __default.#ident;
+                    }
+                }
+            }
+            FieldKind::Nested(nested) => {
+                if nested.len.is_some() {
+                    quote! {
+                        for __item in self.#ident.iter_mut() {
+                            ::coupler::params::Params::reset_to_defaults(__item);
+                        }
+                    }
+                } else {
+                    quote! {
+                        ::coupler::params::Params::reset_to_defaults(&mut self.#ident);
+                    }
+                }
+            }
         }
     });
 
+    // Generates a match arm for each field. Plain parameters are matched directly by index, while
+    // nested fields are matched by index range and forwarded to the nested `Params` impl with an
+    // index relative to the start of the field (and, for arrays, the element being addressed).
+    let cases = |param_case: &dyn Fn(&ParamField, &ParamAttrs) -> TokenStream,
+                 nested_case: &dyn Fn(TokenStream, TokenStream) -> TokenStream| {
+        fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let offset = &offsets[index];
+
+                match &field.kind {
+                    FieldKind::Param(param) => {
+                        let body = param_case(field, param);
+                        quote! {
+                            __index if __index == #offset => #body,
+                        }
+                    }
+                    FieldKind::Nested(nested) => {
+                        let ident = field.field.ident.as_ref().unwrap();
+                        let end = &offsets[index + 1];
+
+                        let body = if nested.len.is_some() {
+                            let ty = &nested.ty;
+                            let count = quote! { <#ty as ::coupler::params::Params>::PARAM_COUNT };
+                            nested_case(
+                                quote! { self.#ident[(__index - (#offset)).div_euclid(#count)] },
+                                quote! { (__index - (#offset)).rem_euclid(#count) },
+                            )
+                        } else {
+                            nested_case(quote! { self.#ident }, quote! { __index - (#offset) })
+                        };
+
+                        quote! {
+                            __index if (#offset..#end).contains(&__index) => #body,
+                        }
+                    }
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let set_cases = cases(
+        &|field, param| {
+            let ident = &field.field.ident;
+            let ty = &field.field.ty;
+            let range = &param.range;
+
+            quote! {
+                {
+                    self.#ident = ::coupler::params::Range::<#ty>::decode(&(#range), __value);
+                }
+            }
+        },
+        &|target, index| {
+            quote! {
+                ::coupler::params::Params::set_param(&mut #target, #index, __value)
+            }
+        },
+    );
+
+    let get_cases = cases(
+        &|field, param| {
+            let ident = &field.field.ident;
+            let ty = &field.field.ty;
+            let range = &param.range;
+
+            quote! {
+                ::coupler::params::Range::<#ty>::encode(&(#range), &self.#ident)
+            }
+        },
+        &|target, index| {
+            quote! {
+                ::coupler::params::Params::get_param(&#target, #index)
+            }
+        },
+    );
+
+    let parse_cases = cases(
+        &|field, param| {
+            let ty = &field.field.ty;
+            let range = &param.range;
+            let format = &param.format;
+
+            quote! {
+                match ::coupler::params::Format::<#ty>::parse(&(#format), __text) {
+                    ::std::option::Option::Some(__value) => ::std::option::Option::Some(
+                        ::coupler::params::Range::<#ty>::encode(&(#range), &__value),
+                    ),
+                    _ => ::std::option::Option::None,
+                }
+            }
+        },
+        &|target, index| {
+            quote! {
+                ::coupler::params::Params::parse_param(&#target, #index, __text)
+            }
+        },
+    );
+
+    let display_cases = cases(
+        &|field, param| {
+            let ty = &field.field.ty;
+            let range = &param.range;
+            let format = &param.format;
+
+            quote! {
+                ::coupler::params::Format::<#ty>::display(
+                    &(#format),
+                    ::coupler::params::Range::<#ty>::decode(&(#range), __value),
+                    __write,
+                )
+            }
+        },
+        &|target, index| {
+            quote! {
+                ::coupler::params::Params::display_param(&#target, #index, __value, __write)
+            }
+        },
+    );
+
     Ok(quote! {
         impl #impl_generics ::coupler::params::Params for #ident #ty_generics #where_clause {
+            const PARAM_COUNT: ::std::primitive::usize = #param_count;
+
             fn params(&self, __build: impl ::coupler::params::BuildParams) {
-                let __default: #ident #ty_generics = ::std::default::Default::default();
+                #default
+                #(#default_checks)*
 
-                __build
-                    #(.reserve(#reserved_keys))*
-                    #(.param(#param_keys, #param_infos))*;
+                let mut __build = ::std::option::Option::Some(__build);
+                #(#reserved_keys)*
+                #(#param_entries)*
+            }
+
+            fn reset_to_defaults(&mut self) {
+                #default
+
+                #(#reset_entries)*
             }
 
             fn set_param(&mut self, __index: ::std::primitive::usize, __value: ::std::primitive::f64) {
@@ -357,5 +762,7 @@ pub fn expand_params(input: &DeriveInput) -> Result<TokenStream, Error> {
                 }
             }
         }
+
+        #default_impl
     })
 }
@@ -0,0 +1,14 @@
+#![no_main]
+
+use coupler::testing::ClapTestHost;
+use coupler_fuzz::{FuzzPlugin, FuzzReader};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut host = ClapTestHost::<FuzzPlugin>::new();
+    host.load_from(FuzzReader::new(data));
+
+    for index in 0..host.param_count() {
+        assert!(host.get_param(index).is_finite());
+    }
+});
@@ -0,0 +1,22 @@
+#![no_main]
+
+use coupler::testing::ClapTestHost;
+use coupler_fuzz::FuzzPlugin;
+use libfuzzer_sys::fuzz_target;
+
+// The first byte picks the parameter and the rest is the text typed in by the user.
+fuzz_target!(|data: &[u8]| {
+    let Some((&index, text)) = data.split_first() else {
+        return;
+    };
+    let Ok(text) = std::str::from_utf8(text) else {
+        return;
+    };
+
+    let host = ClapTestHost::<FuzzPlugin>::new();
+    let index = index as usize % host.param_count();
+
+    if let Some(value) = host.parse_param(index, text) {
+        assert!((0.0..=1.0).contains(&value), "{text:?} parsed to {value}");
+    }
+});
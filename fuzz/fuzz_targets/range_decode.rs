@@ -0,0 +1,65 @@
+#![no_main]
+
+use std::fmt::Debug;
+
+use coupler::params::{Db, Encode, Log, Pow, Range, Skew, Stepped};
+use coupler_fuzz::Mode;
+use libfuzzer_sys::fuzz_target;
+
+fn check_int<T: PartialOrd + Debug>(range: impl Range<T>, value: f64, min: T, max: T) {
+    let decoded = range.decode(value);
+    assert!(
+        min <= decoded && decoded <= max,
+        "{value} decoded to {decoded:?}"
+    );
+}
+
+// Float ranges are checked with a little slack for rounding.
+fn check_float(range: impl Range<f64>, value: f64, min: f64, max: f64) {
+    let decoded = range.decode(value);
+    let slack = (max - min).abs() * 1e-9;
+    assert!(decoded.is_finite(), "{value} decoded to {decoded}");
+    assert!(
+        min - slack <= decoded && decoded <= max + slack,
+        "{value} decoded to {decoded}"
+    );
+}
+
+fn check_f32(range: impl Range<f32>, value: f64, min: f32, max: f32) {
+    let decoded = range.decode(value);
+    let slack = (max - min).abs() * 1e-5;
+    assert!(decoded.is_finite(), "{value} decoded to {decoded}");
+    assert!(
+        min - slack <= decoded && decoded <= max + slack,
+        "{value} decoded to {decoded}"
+    );
+}
+
+fuzz_target!(|data: &[u8]| {
+    for bytes in data.chunks_exact(8) {
+        let value = f64::from_le_bytes(bytes.try_into().unwrap());
+
+        check_float(-1.0..1.0, value, -1.0, 1.0);
+        check_float(-1.0..=1.0, value, -1.0, 1.0);
+        check_float(Log(20.0..20000.0), value, 20.0, 20000.0);
+        check_float(Log(20.0..=20000.0), value, 20.0, 20000.0);
+        check_float(Pow::new(0.0..=100.0, 2.0), value, 0.0, 100.0);
+        check_float(Skew::new(20.0..20000.0, 1000.0), value, 20.0, 20000.0);
+        check_float(Stepped::new(-12.0..=12.0, 0.5), value, -12.0, 12.0);
+        check_float(Db(-60.0..=12.0), value, 0.0, 10.0f64.powf(12.0 / 20.0));
+        check_f32(0.0..1.0, value, 0.0, 1.0);
+        check_f32(Db(-60.0..=12.0), value, 0.0, 10.0f32.powf(12.0 / 20.0));
+        check_f32(Stepped::new(0.0..1.0, 0.25), value, 0.0, 0.75);
+
+        check_int(0..4u8, value, 0, 3);
+        check_int(0..=u8::MAX, value, 0, u8::MAX);
+        check_int(-3..3i32, value, -3, 2);
+        check_int(-3..=3i32, value, -3, 3);
+        check_int(0..=u64::MAX, value, 0, u64::MAX);
+        check_int(i64::MIN..i64::MAX, value, i64::MIN, i64::MAX - 1);
+        check_int(5..5u16, value, 5, 5);
+
+        bool::decode(value);
+        Mode::decode(value);
+    }
+});
@@ -0,0 +1,266 @@
+//! A plugin and helpers shared between the fuzz targets, which are run with
+//! `cargo fuzz run <target>` from the repository root.
+//!
+//! libfuzzer-sys installs a panic hook which aborts the process, so panics are reported even when
+//! they are caught at the plugin boundary.
+
+use std::{fmt, io};
+
+use coupler::buffers::Buffers;
+use coupler::bus::{BuildBusConfigs, BuildBuses, BusConfig, BusDir, BusInfo, Layout};
+use coupler::editor::{EditorHost, NoEditor, ParentWindow, Size};
+use coupler::events::Events;
+use coupler::format::clap::{BuildClapInfo, ClapInfo, ClapPlugin};
+use coupler::format::vst3::{BuildVst3Info, Uuid, Vst3Info, Vst3Plugin};
+use coupler::host::Host;
+use coupler::params::{
+    BuildParams, Db, Decibels, Enum, Hertz, Log, NoteName, OnOff, Pan, Params, Percent, Seconds,
+    Semitones, Skew, Stepped,
+};
+use coupler::plugin::{BuildInfo, Plugin, PluginInfo};
+use coupler::process::{Config, Processor};
+
+#[derive(Enum, Copy, Clone)]
+pub enum Mode {
+    Clean,
+    Warm,
+    #[name("Hot!")]
+    Hot,
+}
+
+#[derive(Params, Clone)]
+pub struct VoiceParams {
+    #[param(name = "Detune", range = -100.0..=100.0)]
+    pub detune: f32,
+    #[param(name = "Octave", range = -2..=2)]
+    pub octave: i8,
+}
+
+/// Parameters covering each of the built-in ranges and formats.
+#[derive(Params, Clone)]
+pub struct FuzzParams {
+    #[param(name = "Gain", range = Db(-60.0..=12.0), format = Decibels)]
+    pub gain: f32,
+    #[param(name = "Cutoff", range = Skew::new(20.0..=20000.0, 1000.0), format = Hertz)]
+    pub cutoff: f32,
+    #[param(name = "Time", range = Log(0.001..=10.0), format = Seconds)]
+    pub time: f64,
+    #[param(name = "Mix", range = 0.0..=1.0, format = Percent)]
+    pub mix: f32,
+    #[param(name = "Pan", range = -1.0..=1.0, format = Pan)]
+    pub pan: f32,
+    #[param(name = "Transpose", range = Stepped::new(-24.0..=24.0, 0.5), format = Semitones)]
+    pub transpose: f64,
+    #[param(name = "Note", range = 0..=127, format = NoteName)]
+    pub note: u8,
+    #[param(name = "Voices", range = 1..9)]
+    pub voices: i32,
+    #[param(name = "Mode")]
+    pub mode: Mode,
+    #[param(name = "Bypass", format = OnOff)]
+    pub bypass: bool,
+    #[params(nested)]
+    pub voice: [VoiceParams; 2],
+}
+
+impl Default for VoiceParams {
+    fn default() -> VoiceParams {
+        VoiceParams {
+            detune: 0.0,
+            octave: 0,
+        }
+    }
+}
+
+impl Default for FuzzParams {
+    fn default() -> FuzzParams {
+        FuzzParams {
+            gain: 1.0,
+            cutoff: 1000.0,
+            time: 0.1,
+            mix: 1.0,
+            pan: 0.0,
+            transpose: 0.0,
+            note: 60,
+            voices: 1,
+            mode: Mode::Clean,
+            bypass: false,
+            voice: [VoiceParams::default(), VoiceParams::default()],
+        }
+    }
+}
+
+/// Stores its state as one little-endian `f64` per normalized parameter value, so that arbitrary
+/// input reaches `Range::decode` through `load`.
+pub struct FuzzPlugin {
+    params: FuzzParams,
+}
+
+impl Plugin for FuzzPlugin {
+    type Processor = FuzzProcessor;
+    type Editor = NoEditor;
+
+    fn info(build: impl BuildInfo) {
+        build.info(PluginInfo {
+            name: "Fuzz",
+            version: "0.0.0",
+            vendor: "Coupler",
+            url: "",
+            email: "",
+        })
+    }
+
+    fn new(_host: Host) -> Self {
+        FuzzPlugin {
+            params: FuzzParams::default(),
+        }
+    }
+
+    fn buses(&self, build: impl BuildBuses) {
+        build.bus(
+            "main",
+            BusInfo {
+                name: "Main",
+                dir: BusDir::InOut,
+            },
+        );
+    }
+
+    fn bus_configs(&self, build: impl BuildBusConfigs) {
+        build.config(
+            "stereo",
+            BusConfig {
+                layouts: &[Layout::Stereo],
+            },
+        );
+    }
+
+    fn params(&self, build: impl BuildParams) {
+        self.params.params(build)
+    }
+
+    fn set_param(&mut self, index: usize, value: f64) {
+        self.params.set_param(index, value);
+    }
+
+    fn get_param(&self, index: usize) -> f64 {
+        self.params.get_param(index)
+    }
+
+    fn parse_param(&self, index: usize, text: &str) -> Option<f64> {
+        self.params.parse_param(index, text)
+    }
+
+    fn display_param(
+        &self,
+        index: usize,
+        value: f64,
+        write: impl fmt::Write,
+    ) -> Result<(), fmt::Error> {
+        self.params.display_param(index, value, write)
+    }
+
+    fn save(&self, mut output: impl io::Write) -> io::Result<()> {
+        for index in 0..FuzzParams::PARAM_COUNT {
+            output.write_all(&self.params.get_param(index).to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    fn load(&mut self, mut input: impl io::Read) -> io::Result<()> {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+
+        for (index, bytes) in data.chunks_exact(8).enumerate() {
+            let value = f64::from_le_bytes(bytes.try_into().unwrap());
+            self.params.set_param(index % FuzzParams::PARAM_COUNT, value);
+        }
+
+        Ok(())
+    }
+
+    fn processor(&mut self, _config: Config) -> Self::Processor {
+        FuzzProcessor
+    }
+
+    fn has_editor(&self) -> bool {
+        false
+    }
+
+    fn editor_size(&self) -> Size {
+        Size {
+            width: 0.0,
+            height: 0.0,
+        }
+    }
+
+    fn editor(&mut self, _host: EditorHost, _parent: &ParentWindow) -> Self::Editor {
+        NoEditor
+    }
+}
+
+impl ClapPlugin for FuzzPlugin {
+    fn clap_info(build: impl BuildClapInfo) {
+        build.info(ClapInfo {
+            id: "rs.coupler.fuzz",
+        })
+    }
+}
+
+impl Vst3Plugin for FuzzPlugin {
+    fn vst3_info(build: impl BuildVst3Info) {
+        build.info(Vst3Info {
+            class_id: Uuid::from_name("rs.coupler.fuzz"),
+        })
+    }
+}
+
+pub struct FuzzProcessor;
+
+impl Processor for FuzzProcessor {
+    fn reset(&mut self) {}
+    fn set_param(&mut self, _index: usize, _value: f64) {}
+    fn process(&mut self, _buffers: Buffers, _events: Events) {}
+}
+
+/// Plays the part of a host's stream, with the fuzzer input deciding how much each read returns.
+///
+/// Each read consumes a control byte. Most control bytes give the number of bytes to copy out of
+/// the remaining input, but a few make the read fail or report a count larger than the buffer.
+pub struct FuzzReader {
+    data: Vec<u8>,
+    position: usize,
+}
+
+impl FuzzReader {
+    pub fn new(data: &[u8]) -> FuzzReader {
+        FuzzReader {
+            data: data.to_vec(),
+            position: 0,
+        }
+    }
+}
+
+impl io::Read for FuzzReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(&control) = self.data.get(self.position) else {
+            return Ok(0);
+        };
+        self.position += 1;
+
+        match control {
+            0xff => Err(io::Error::other("read error")),
+            0xfe => Ok(buf.len() + 1),
+            0xfd => Ok(usize::MAX),
+            _ => {
+                let rest = &self.data[self.position..];
+                let count = (control as usize).min(buf.len()).min(rest.len());
+                buf[..count].copy_from_slice(&rest[..count]);
+                self.position += count;
+
+                Ok(count)
+            }
+        }
+    }
+}
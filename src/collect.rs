@@ -1,6 +1,11 @@
+use std::collections::HashMap;
+
 use crate::bus::{BuildBusConfigs, BuildBuses, BusConfig, BusDir, BusInfo, Layout};
 use crate::key::{Key, KeyList};
-use crate::params::{BuildParams, ParamInfo};
+use crate::log::{self, Level};
+use crate::params::{
+    BuildParams, BuildRemoteControls, ParamInfo, REMOTE_CONTROLS_COUNT, RemoteControlsPage,
+};
 use crate::plugin::Plugin;
 
 pub struct OwnedBusInfo {
@@ -79,6 +84,7 @@ pub struct OwnedParamInfo {
     pub name: String,
     pub default: f64,
     pub steps: Option<u32>,
+    pub value_names: Option<Vec<String>>,
 }
 
 pub fn collect_params<P: Plugin>(plugin: &P) -> (Vec<u32>, Vec<OwnedParamInfo>) {
@@ -94,6 +100,9 @@ pub fn collect_params<P: Plugin>(plugin: &P) -> (Vec<u32>, Vec<OwnedParamInfo>)
                 name: param.name.to_string(),
                 default: param.default,
                 steps: param.steps,
+                value_names: param
+                    .value_names
+                    .map(|names| names.iter().map(|name| name.to_string()).collect()),
             });
             self
         }
@@ -113,3 +122,84 @@ pub fn collect_params<P: Plugin>(plugin: &P) -> (Vec<u32>, Vec<OwnedParamInfo>)
 
     (keys.into_ids(), params)
 }
+
+pub struct OwnedRemoteControlsPage {
+    pub name: String,
+    pub section: String,
+    // Indices of the parameters on this page
+    pub params: [Option<usize>; REMOTE_CONTROLS_COUNT],
+}
+
+/// Maps the key string of each parameter to its index.
+pub fn collect_param_keys<P: Plugin>(plugin: &P) -> HashMap<String, usize> {
+    struct CollectParamKeys<'a> {
+        keys: &'a mut HashMap<String, usize>,
+    }
+
+    impl<'a> BuildParams for CollectParamKeys<'a> {
+        fn param<'k>(self, key: impl Into<Key<'k>>, _param: ParamInfo) -> Self {
+            let index = self.keys.len();
+            self.keys.insert(key.into().str.to_string(), index);
+            self
+        }
+
+        fn reserve<'k>(self, _key: impl Into<Key<'k>>) -> Self {
+            self
+        }
+    }
+
+    let mut keys = HashMap::new();
+    plugin.params(CollectParamKeys { keys: &mut keys });
+
+    keys
+}
+
+pub fn collect_remote_controls<P: Plugin>(plugin: &P) -> (Vec<u32>, Vec<OwnedRemoteControlsPage>) {
+    struct CollectRemoteControls<'a> {
+        param_keys: &'a HashMap<String, usize>,
+        keys: &'a mut KeyList,
+        pages: &'a mut Vec<OwnedRemoteControlsPage>,
+    }
+
+    impl<'a> BuildRemoteControls for CollectRemoteControls<'a> {
+        fn page<'k>(self, key: impl Into<Key<'k>>, page: RemoteControlsPage) -> Self {
+            self.keys.key(key);
+            self.pages.push(OwnedRemoteControlsPage {
+                name: page.name.to_string(),
+                section: page.section.to_string(),
+                params: page.params.map(|key| {
+                    let key = key?;
+                    let index = self.param_keys.get(key.str).copied();
+                    if index.is_none() {
+                        log::log(
+                            Level::Warning,
+                            format_args!(
+                                "remote controls page \"{}\" refers to unknown parameter \"{}\"",
+                                page.name, key.str
+                            ),
+                        );
+                    }
+                    index
+                }),
+            });
+            self
+        }
+
+        fn reserve<'k>(self, key: impl Into<Key<'k>>) -> Self {
+            self.keys.reserve(key);
+            self
+        }
+    }
+
+    let param_keys = collect_param_keys(plugin);
+
+    let mut keys = KeyList::new();
+    let mut pages = Vec::new();
+    plugin.remote_controls(CollectRemoteControls {
+        param_keys: &param_keys,
+        keys: &mut keys,
+        pages: &mut pages,
+    });
+
+    (keys.into_ids(), pages)
+}
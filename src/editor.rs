@@ -1,11 +1,21 @@
+use std::any::Any;
 use std::ffi::{c_ulong, c_void};
 use std::marker::PhantomData;
 use std::rc::Rc;
 
+#[cfg(target_os = "linux")]
+use std::os::fd::RawFd;
+
+/// Interval at which format wrappers ask the host to call [`Editor::idle`], where supported.
+pub(crate) const IDLE_INTERVAL_MS: u32 = 16;
+
 pub trait EditorHostInner {
     fn begin_gesture(&self, index: usize);
     fn end_gesture(&self, index: usize);
     fn set_param(&self, index: usize, value: f64);
+    fn request_resize(&self, size: Size) -> bool;
+    fn send_message(&self, message: Box<dyn Any + Send>);
+    fn show_context_menu(&self, param: Option<usize>, x: f64, y: f64, items: &[MenuItem]) -> bool;
 }
 
 #[derive(Clone)]
@@ -34,6 +44,43 @@ impl EditorHost {
     pub fn set_param(&self, index: usize, value: f64) {
         self.inner.set_param(index, value);
     }
+
+    /// Asks the host to resize the editor window. Returns `true` if the host accepted the new size,
+    /// in which case the editor should resize itself accordingly.
+    ///
+    /// Some hosts respond by resizing the editor before this method returns. If that happens while
+    /// an `Editor` method is running, [`Editor::set_size`] is called on the next idle tick instead.
+    pub fn request_resize(&self, size: Size) -> bool {
+        self.inner.request_resize(size)
+    }
+
+    /// Sends a message to the plugin, to be handled by
+    /// [`Plugin::message`](crate::plugin::Plugin::message) on the main thread.
+    ///
+    /// The message is queued and delivered on the next idle tick, after the current `Editor` method
+    /// has returned.
+    pub fn send_message<M: Any + Send>(&self, message: M) {
+        self.inner.send_message(Box::new(message));
+    }
+
+    /// Shows the host's context menu at the given position, relative to the editor's window, with
+    /// `items` added to it. If `param` is provided, the menu contains the host's entries for that
+    /// parameter, such as automation and MIDI learn. Returns `false` if the host does not support
+    /// context menus.
+    ///
+    /// When the user selects one of `items`, [`Editor::context_menu_action`] is called with its
+    /// `id`. Some hosts show the menu before this method returns; if the selection is made while an
+    /// `Editor` method is running, the action is delivered on the next idle tick instead. Also
+    /// returns `false` if `param` is not a valid parameter index.
+    pub fn show_context_menu(
+        &self,
+        param: Option<usize>,
+        x: f64,
+        y: f64,
+        items: &[MenuItem],
+    ) -> bool {
+        self.inner.show_context_menu(param, x, y, items)
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -45,26 +92,237 @@ pub enum RawParent {
 
 pub struct ParentWindow {
     parent: RawParent,
+    scale: f64,
 }
 
 impl ParentWindow {
     pub unsafe fn from_raw(parent: RawParent) -> ParentWindow {
-        ParentWindow { parent }
+        ParentWindow { parent, scale: 1.0 }
+    }
+
+    pub fn with_scale(self, scale: f64) -> ParentWindow {
+        ParentWindow { scale, ..self }
     }
 
     pub fn as_raw(&self) -> RawParent {
         self.parent
     }
+
+    /// The content scale factor provided by the host at the time the editor is created.
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+}
+
+/// A plugin-provided item in a host context menu.
+#[derive(Clone, PartialEq, Debug)]
+pub enum MenuItem {
+    Entry {
+        label: String,
+        id: u32,
+        enabled: bool,
+        checked: bool,
+    },
+    Separator,
+    BeginSubmenu {
+        label: String,
+    },
+    EndSubmenu,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+/// A mapping of a parameter to a hardware controller or other host control, such as a MIDI learn
+/// assignment.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ParamMapping {
+    pub color: Option<Color>,
+    pub label: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AutomationState {
+    /// The host has no automation for the parameter.
+    None,
+    /// The host has automation for the parameter, but it isn't playing.
+    Present,
+    Playing,
+    Recording,
+    /// The host is playing automation, but the parameter is being overridden by the user.
+    Overriding,
 }
 
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub struct Size {
     pub width: f64,
     pub height: f64,
 }
 
+/// Constraints on the size of a resizable editor.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct ResizeHints {
+    pub min_size: Option<Size>,
+    pub max_size: Option<Size>,
+    /// Ratio of width to height which the editor must preserve.
+    pub aspect_ratio: Option<f64>,
+}
+
+impl ResizeHints {
+    /// Returns the size closest to `size` which satisfies these constraints. If the aspect ratio
+    /// can't be satisfied within the minimum and maximum sizes, the minimum and maximum sizes take
+    /// priority.
+    pub fn constrain(&self, size: Size) -> Size {
+        let (min_width, min_height) = self.min_size.map_or((0.0, 0.0), |s| (s.width, s.height));
+        let (max_width, max_height) =
+            self.max_size.map_or((f64::INFINITY, f64::INFINITY), |s| (s.width, s.height));
+
+        let mut width = size.width.max(min_width).min(max_width);
+        let mut height = size.height.max(min_height).min(max_height);
+
+        if let Some(aspect_ratio) = self.aspect_ratio
+            && aspect_ratio > 0.0
+        {
+            // Fit the largest rectangle with the given aspect ratio inside the requested size, then
+            // keep its width within the range for which both dimensions fit the min/max box.
+            let lowest = min_width.max(min_height * aspect_ratio);
+            let highest = max_width.min(max_height * aspect_ratio);
+            width = width.min(height * aspect_ratio).max(lowest).min(highest);
+            height = width / aspect_ratio;
+
+            width = width.max(min_width).min(max_width);
+            height = height.max(min_height).min(max_height);
+        }
+
+        Size { width, height }
+    }
+}
+
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub alt: bool,
+    pub control: bool,
+    /// The command key on macOS.
+    pub command: bool,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum KeyCode {
+    Backspace,
+    Tab,
+    Enter,
+    Escape,
+    Space,
+    Insert,
+    Delete,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Left,
+    Right,
+    Up,
+    Down,
+    /// A key which is identified by the character it produces, if any.
+    Other,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct KeyEvent {
+    /// The character produced by the key, if any.
+    pub char: Option<char>,
+    pub code: KeyCode,
+    pub modifiers: Modifiers,
+}
+
 pub trait Editor: Sized + 'static {
     fn size(&self) -> Size;
     fn param_changed(&mut self, index: usize, value: f64);
+
+    /// Returns the constraints on the editor's size, or `None` if the editor cannot be resized.
+    fn resize_hints(&self) -> Option<ResizeHints> {
+        None
+    }
+
+    /// Called when the host resizes the editor window. Returns `false` if the new size was
+    /// rejected.
+    fn set_size(&mut self, _size: Size) -> bool {
+        false
+    }
+
+    /// Called when the host changes the content scale factor, e.g. when the editor window is moved
+    /// to a display with a different pixel density.
+    ///
+    /// Sizes exchanged with the host are not affected; the editor is responsible for applying the
+    /// scale factor to its contents.
+    fn set_scale(&mut self, _scale: f64) {}
+
+    /// Called for key presses which the host routes through the plugin view rather than
+    /// delivering directly to the editor's window. Returns `true` if the event was handled.
+    fn key_down(&mut self, _event: KeyEvent) -> bool {
+        false
+    }
+
+    /// Called for key releases routed through the plugin view. Returns `true` if the event was
+    /// handled.
+    fn key_up(&mut self, _event: KeyEvent) -> bool {
+        false
+    }
+
+    /// Called for mouse wheel movement routed through the plugin view. Returns `true` if the event
+    /// was handled.
+    fn wheel(&mut self, _distance: f64) -> bool {
+        false
+    }
+
+    /// Called when the plugin view gains or loses keyboard focus. Returns `true` if the event was
+    /// handled.
+    fn focus_changed(&mut self, _focused: bool) -> bool {
+        false
+    }
+
+    /// Called periodically on the main thread while the editor is open. This can be used to drive
+    /// animations or to drain a [`channel`](crate::channel) from the processor.
+    ///
+    /// The wrappers use host-provided timers where available (CLAP `timer-support`, VST3 `IRunLoop`
+    /// on Linux), and the VST3 wrapper uses a platform timer on Windows and macOS. Under CLAP hosts
+    /// without `timer-support`, `idle` is not called periodically, only whenever the plugin is
+    /// woken on the main thread for some other reason, such as a parameter change. Editors which
+    /// need regular updates under such hosts should use the platform's own timers.
+    fn idle(&mut self) {}
+
+    /// Called when the user selects an item added with [`EditorHost::show_context_menu`].
+    fn context_menu_action(&mut self, _id: u32) {}
+
+    /// Called when the host maps a parameter to a controller or removes such a mapping. `mapping`
+    /// is `None` if the parameter is no longer mapped.
+    fn param_mapping_changed(&mut self, _index: usize, _mapping: Option<ParamMapping>) {}
+
+    /// Called when the host's automation state for a parameter changes. `color` is the color the
+    /// host uses to display the automation, if any.
+    fn param_automation_changed(
+        &mut self,
+        _index: usize,
+        _state: AutomationState,
+        _color: Option<Color>,
+    ) {
+    }
+
+    /// Returns a file descriptor, such as the editor's X11 connection, which the host should poll on
+    /// the editor's behalf. [`Editor::idle`] is called whenever the descriptor becomes readable.
+    ///
+    /// This is queried once, after the editor is created.
+    #[cfg(target_os = "linux")]
+    fn poll_fd(&self) -> Option<RawFd> {
+        None
+    }
 }
 
 pub struct NoEditor;
@@ -79,3 +337,110 @@ impl Editor for NoEditor {
 
     fn param_changed(&mut self, _index: usize, _value: f64) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constrain_min_max() {
+        let hints = ResizeHints {
+            min_size: Some(Size {
+                width: 100.0,
+                height: 100.0,
+            }),
+            max_size: Some(Size {
+                width: 400.0,
+                height: 300.0,
+            }),
+            aspect_ratio: None,
+        };
+
+        assert_eq!(
+            hints.constrain(Size {
+                width: 50.0,
+                height: 500.0,
+            }),
+            Size {
+                width: 100.0,
+                height: 300.0,
+            }
+        );
+    }
+
+    #[test]
+    fn constrain_aspect_ratio() {
+        let hints = ResizeHints {
+            min_size: None,
+            max_size: None,
+            aspect_ratio: Some(2.0),
+        };
+
+        assert_eq!(
+            hints.constrain(Size {
+                width: 300.0,
+                height: 100.0,
+            }),
+            Size {
+                width: 200.0,
+                height: 100.0,
+            }
+        );
+        assert_eq!(
+            hints.constrain(Size {
+                width: 300.0,
+                height: 300.0,
+            }),
+            Size {
+                width: 300.0,
+                height: 150.0,
+            }
+        );
+    }
+
+    #[test]
+    fn constrain_conflicting() {
+        let hints = ResizeHints {
+            min_size: Some(Size {
+                width: 100.0,
+                height: 100.0,
+            }),
+            max_size: Some(Size {
+                width: 400.0,
+                height: 150.0,
+            }),
+            aspect_ratio: Some(4.0),
+        };
+
+        // Only 400x100 fits the box with the aspect ratio, even though it's far from the request.
+        assert_eq!(
+            hints.constrain(Size {
+                width: 200.0,
+                height: 200.0,
+            }),
+            Size {
+                width: 400.0,
+                height: 100.0,
+            }
+        );
+
+        // No size fits both the box and the aspect ratio, so the box wins.
+        let hints = ResizeHints {
+            max_size: Some(Size {
+                width: 200.0,
+                height: 200.0,
+            }),
+            ..hints
+        };
+        assert_eq!(
+            hints.constrain(Size {
+                width: 300.0,
+                height: 300.0,
+            }),
+            Size {
+                width: 200.0,
+                height: 100.0,
+            }
+        );
+    }
+}
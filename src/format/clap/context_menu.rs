@@ -0,0 +1,148 @@
+use std::ffi::{CString, c_void};
+use std::ptr;
+
+use clap_sys::ext::draft::context_menu::*;
+use clap_sys::id::*;
+use clap_sys::plugin::*;
+
+use super::instance::Instance;
+use crate::editor::{Editor, MenuItem};
+use crate::plugin::Plugin;
+
+/// Items to be added to the host context menu for a target, stored between the call to the host's
+/// `popup` and the host calling back into `populate`.
+pub struct ContextMenu {
+    pub kind: u32,
+    pub id: clap_id,
+    pub items: Vec<MenuItem>,
+}
+
+unsafe fn add_items(builder: *const clap_context_menu_builder, items: &[MenuItem]) {
+    let add_item = unsafe { (*builder).add_item.unwrap() };
+
+    for item in items {
+        match item {
+            MenuItem::Entry {
+                label,
+                id,
+                enabled,
+                checked,
+            } => {
+                let label = CString::new(label.as_str()).unwrap_or_default();
+
+                if *checked {
+                    let entry = clap_context_menu_check_entry {
+                        label: label.as_ptr(),
+                        is_enabled: *enabled,
+                        is_checked: true,
+                        action_id: *id,
+                    };
+                    unsafe {
+                        add_item(
+                            builder,
+                            CLAP_CONTEXT_MENU_ITEM_CHECK_ENTRY,
+                            &entry as *const _ as *const c_void,
+                        )
+                    };
+                } else {
+                    let entry = clap_context_menu_entry {
+                        label: label.as_ptr(),
+                        is_enabled: *enabled,
+                        action_id: *id,
+                    };
+                    unsafe {
+                        add_item(
+                            builder,
+                            CLAP_CONTEXT_MENU_ITEM_ENTRY,
+                            &entry as *const _ as *const c_void,
+                        )
+                    };
+                }
+            }
+            MenuItem::Separator => {
+                unsafe { add_item(builder, CLAP_CONTEXT_MENU_ITEM_SEPARATOR, ptr::null()) };
+            }
+            MenuItem::BeginSubmenu { label } => {
+                let label = CString::new(label.as_str()).unwrap_or_default();
+                let submenu = clap_context_menu_submenu {
+                    label: label.as_ptr(),
+                    is_enabled: true,
+                };
+                unsafe {
+                    add_item(
+                        builder,
+                        CLAP_CONTEXT_MENU_ITEM_BEGIN_SUBMENU,
+                        &submenu as *const _ as *const c_void,
+                    )
+                };
+            }
+            MenuItem::EndSubmenu => {
+                unsafe { add_item(builder, CLAP_CONTEXT_MENU_ITEM_END_SUBMENU, ptr::null()) };
+            }
+        }
+    }
+}
+
+impl<P: Plugin> Instance<P> {
+    pub(super) const CONTEXT_MENU: clap_plugin_context_menu = clap_plugin_context_menu {
+        populate: Some(Self::context_menu_populate),
+        perform: Some(Self::context_menu_perform),
+    };
+
+    unsafe extern "C" fn context_menu_populate(
+        plugin: *const clap_plugin,
+        target: *const clap_context_menu_target,
+        builder: *const clap_context_menu_builder,
+    ) -> bool {
+        let instance = unsafe { &*(plugin as *const Self) };
+
+        instance
+            .guard("clap_plugin_context_menu.populate", || {
+                let context_menu = instance.context_menu.borrow();
+
+                if let Some(context_menu) = &*context_menu {
+                    let (kind, id) = if target.is_null() {
+                        (CLAP_CONTEXT_MENU_TARGET_KIND_GLOBAL, CLAP_INVALID_ID)
+                    } else {
+                        let target = unsafe { &*target };
+                        (target.kind, target.id)
+                    };
+
+                    if context_menu.kind == kind && context_menu.id == id {
+                        unsafe { add_items(builder, &context_menu.items) };
+                    }
+                }
+
+                true
+            })
+            .unwrap_or(false)
+    }
+
+    unsafe extern "C" fn context_menu_perform(
+        plugin: *const clap_plugin,
+        _target: *const clap_context_menu_target,
+        action_id: clap_id,
+    ) -> bool {
+        let instance = unsafe { &*(plugin as *const Self) };
+
+        instance
+            .guard("clap_plugin_context_menu.perform", || {
+                // Hosts may perform actions from within the call to `popup`, in which case the main
+                // thread state is still borrowed by an editor callback. Apply the action from
+                // `on_main_thread` instead.
+                let Ok(mut main_thread_state) = instance.main_thread_state.try_borrow() else {
+                    instance.deferred.borrow().context_menu_action(action_id);
+                    unsafe { (*instance.host.0).request_callback.unwrap()(instance.host.0) };
+                    return true;
+                };
+
+                if let Some(editor) = &mut main_thread_state.editor {
+                    editor.context_menu_action(action_id);
+                    return true;
+                }
+
+                false
+            })
+            .unwrap_or(false)
+    }
+}
@@ -8,6 +8,7 @@ use clap_sys::{host::*, plugin::*, plugin_factory::*, version::*};
 
 use super::instance::Instance;
 use super::{ClapPlugin, with_clap_info};
+use crate::panic;
 use crate::plugin::{Plugin, with_info};
 
 struct FactoryState {
@@ -145,18 +146,20 @@ impl<P: Plugin + ClapPlugin> Factory<P> {
         host: *const clap_host,
         plugin_id: *const c_char,
     ) -> *const clap_plugin {
-        let factory = unsafe { &*(factory as *const Self) };
-        let state = unsafe { &*factory.state.get() };
+        panic::catch("clap_plugin_factory.create_plugin", || {
+            let factory = unsafe { &*(factory as *const Self) };
+            let state = unsafe { &*factory.state.get() };
 
-        if let Some(state) = state {
-            if unsafe { CStr::from_ptr(plugin_id) }
-                == unsafe { CStr::from_ptr(state.descriptor.id) }
+            if let Some(state) = state
+                && unsafe { CStr::from_ptr(plugin_id) }
+                    == unsafe { CStr::from_ptr(state.descriptor.id) }
             {
                 let instance = Box::new(Instance::<P>::new(&state.descriptor, host));
                 return Box::into_raw(instance) as *const clap_plugin;
             }
-        }
 
-        ptr::null()
+            ptr::null()
+        })
+        .unwrap_or(ptr::null())
     }
 }
@@ -1,20 +1,33 @@
+use std::any::Any;
 use std::ffi::{CStr, c_char};
 use std::rc::Rc;
 use std::sync::Arc;
 
-use clap_sys::ext::gui::*;
-use clap_sys::plugin::*;
+use clap_sys::ext::draft::context_menu::*;
+use clap_sys::ext::{gui::*, timer_support::*};
+use clap_sys::{id::*, plugin::*};
 
-use super::instance::{Extensions, HostPtr, Instance};
-use crate::editor::{Editor, EditorHost, EditorHostInner, ParentWindow, RawParent};
+#[cfg(target_os = "linux")]
+use clap_sys::ext::posix_fd_support::*;
+
+use super::context_menu::ContextMenu;
+use super::instance::{Extensions, HostPtr, Instance, MainThreadState};
+use crate::editor::{
+    Editor, EditorHost, EditorHostInner, IDLE_INTERVAL_MS, MenuItem, ParentWindow, RawParent, Size,
+};
+use crate::format::deferred::Deferred;
 use crate::plugin::Plugin;
 use crate::sync::param_gestures::ParamGestures;
+use crate::sync::sync_cell::SyncCell;
 use crate::sync::thread_cell::ThreadCell;
 
 struct ClapEditorHost {
     host: HostPtr,
     extensions: Extensions,
+    param_ids: Arc<Vec<u32>>,
     param_gestures: Arc<ParamGestures>,
+    deferred: Arc<SyncCell<Deferred>>,
+    context_menu: Arc<SyncCell<Option<ContextMenu>>>,
 }
 
 impl EditorHostInner for ClapEditorHost {
@@ -41,6 +54,69 @@ impl EditorHostInner for ClapEditorHost {
             unsafe { host_params.as_ref().request_flush.unwrap()(self.host.0) };
         }
     }
+
+    fn request_resize(&self, size: Size) -> bool {
+        if let Some(host_gui) = self.extensions.host_gui {
+            let width = size.width.round() as u32;
+            let height = size.height.round() as u32;
+            return unsafe {
+                host_gui.as_ref().request_resize.unwrap()(self.host.0, width, height)
+            };
+        }
+
+        false
+    }
+
+    fn send_message(&self, message: Box<dyn Any + Send>) {
+        self.deferred.borrow().send_message(message);
+
+        // Messages are delivered from `on_main_thread`.
+        unsafe { (*self.host.0).request_callback.unwrap()(self.host.0) };
+    }
+
+    fn show_context_menu(&self, param: Option<usize>, x: f64, y: f64, items: &[MenuItem]) -> bool {
+        let Some(host_context_menu) = self.extensions.host_context_menu else {
+            return false;
+        };
+        let host_context_menu = unsafe { host_context_menu.as_ref() };
+
+        if !unsafe { host_context_menu.can_popup.unwrap()(self.host.0) } {
+            return false;
+        }
+
+        let target = if let Some(index) = param {
+            let Some(&id) = self.param_ids.get(index) else {
+                return false;
+            };
+
+            clap_context_menu_target {
+                kind: CLAP_CONTEXT_MENU_TARGET_KIND_PARAM,
+                id,
+            }
+        } else {
+            clap_context_menu_target {
+                kind: CLAP_CONTEXT_MENU_TARGET_KIND_GLOBAL,
+                id: CLAP_INVALID_ID,
+            }
+        };
+
+        // The host calls back into `context_menu_populate` to retrieve the items.
+        *self.context_menu.borrow() = Some(ContextMenu {
+            kind: target.kind,
+            id: target.id,
+            items: items.to_vec(),
+        });
+
+        unsafe {
+            host_context_menu.popup.unwrap()(
+                self.host.0,
+                &target,
+                0,
+                x.round() as i32,
+                y.round() as i32,
+            )
+        }
+    }
 }
 
 impl<P: Plugin> Instance<P> {
@@ -112,13 +188,30 @@ impl<P: Plugin> Instance<P> {
 
     unsafe extern "C" fn gui_destroy(plugin: *const clap_plugin) {
         let instance = unsafe { &*(plugin as *const Self) };
-        let mut main_thread_state = instance.main_thread_state.borrow();
 
-        main_thread_state.editor = None;
+        instance.guard("clap_plugin_gui.destroy", || {
+            let mut main_thread_state = instance.main_thread_state.borrow();
+
+            instance.unregister_idle(&mut main_thread_state);
+            main_thread_state.editor = None;
+        });
     }
 
-    unsafe extern "C" fn gui_set_scale(_plugin: *const clap_plugin, _scale: f64) -> bool {
-        false
+    unsafe extern "C" fn gui_set_scale(plugin: *const clap_plugin, scale: f64) -> bool {
+        let instance = unsafe { &*(plugin as *const Self) };
+
+        instance
+            .guard("clap_plugin_gui.set_scale", || {
+                let mut main_thread_state = instance.main_thread_state.borrow();
+
+                main_thread_state.scale = scale;
+                if let Some(editor) = &mut main_thread_state.editor {
+                    editor.set_scale(scale);
+                }
+
+                true
+            })
+            .unwrap_or(false)
     }
 
     unsafe extern "C" fn gui_get_size(
@@ -127,82 +220,204 @@ impl<P: Plugin> Instance<P> {
         height: *mut u32,
     ) -> bool {
         let instance = unsafe { &*(plugin as *const Self) };
-        let main_thread_state = instance.main_thread_state.borrow();
 
-        let size = if let Some(editor) = &main_thread_state.editor {
-            editor.size()
-        } else {
-            main_thread_state.plugin.editor_size()
-        };
+        instance
+            .guard("clap_plugin_gui.get_size", || {
+                let Ok(main_thread_state) = instance.main_thread_state.try_borrow() else {
+                    return false;
+                };
 
-        let width = unsafe { &mut *width };
-        *width = size.width.round() as u32;
+                let size = if let Some(editor) = &main_thread_state.editor {
+                    editor.size()
+                } else {
+                    main_thread_state.plugin.editor_size()
+                };
 
-        let height = unsafe { &mut *height };
-        *height = size.height.round() as u32;
+                let width = unsafe { &mut *width };
+                *width = size.width.round() as u32;
 
-        true
+                let height = unsafe { &mut *height };
+                *height = size.height.round() as u32;
+
+                true
+            })
+            .unwrap_or(false)
     }
 
-    unsafe extern "C" fn gui_can_resize(_plugin: *const clap_plugin) -> bool {
-        false
+    unsafe extern "C" fn gui_can_resize(plugin: *const clap_plugin) -> bool {
+        let instance = unsafe { &*(plugin as *const Self) };
+
+        instance
+            .guard("clap_plugin_gui.can_resize", || {
+                let Ok(main_thread_state) = instance.main_thread_state.try_borrow() else {
+                    return false;
+                };
+
+                if let Some(editor) = &main_thread_state.editor {
+                    return editor.resize_hints().is_some();
+                }
+
+                false
+            })
+            .unwrap_or(false)
     }
 
     unsafe extern "C" fn gui_get_resize_hints(
-        _plugin: *const clap_plugin,
-        _hints: *mut clap_gui_resize_hints,
+        plugin: *const clap_plugin,
+        hints: *mut clap_gui_resize_hints,
     ) -> bool {
-        false
+        let instance = unsafe { &*(plugin as *const Self) };
+
+        instance
+            .guard("clap_plugin_gui.get_resize_hints", || {
+                let Ok(main_thread_state) = instance.main_thread_state.try_borrow() else {
+                    return false;
+                };
+
+                let Some(editor) = &main_thread_state.editor else {
+                    return false;
+                };
+
+                let Some(resize_hints) = editor.resize_hints() else {
+                    return false;
+                };
+
+                let fixed = |min: Option<f64>, max: Option<f64>| match (min, max) {
+                    (Some(min), Some(max)) => min >= max,
+                    _ => false,
+                };
+
+                let hints = unsafe { &mut *hints };
+                hints.can_resize_horizontally = !fixed(
+                    resize_hints.min_size.map(|size| size.width),
+                    resize_hints.max_size.map(|size| size.width),
+                );
+                hints.can_resize_vertically = !fixed(
+                    resize_hints.min_size.map(|size| size.height),
+                    resize_hints.max_size.map(|size| size.height),
+                );
+
+                if let Some(aspect_ratio) = resize_hints.aspect_ratio {
+                    hints.preserve_aspect_ratio = true;
+                    hints.aspect_ratio_width = (aspect_ratio * 1000.0).round() as u32;
+                    hints.aspect_ratio_height = 1000;
+                } else {
+                    hints.preserve_aspect_ratio = false;
+                    hints.aspect_ratio_width = 1;
+                    hints.aspect_ratio_height = 1;
+                }
+
+                true
+            })
+            .unwrap_or(false)
     }
 
     unsafe extern "C" fn gui_adjust_size(
-        _plugin: *const clap_plugin,
-        _width: *mut u32,
-        _height: *mut u32,
+        plugin: *const clap_plugin,
+        width: *mut u32,
+        height: *mut u32,
     ) -> bool {
-        false
+        let instance = unsafe { &*(plugin as *const Self) };
+
+        instance
+            .guard("clap_plugin_gui.adjust_size", || {
+                let Ok(main_thread_state) = instance.main_thread_state.try_borrow() else {
+                    return false;
+                };
+
+                let Some(editor) = &main_thread_state.editor else {
+                    return false;
+                };
+
+                let Some(resize_hints) = editor.resize_hints() else {
+                    return false;
+                };
+
+                let width = unsafe { &mut *width };
+                let height = unsafe { &mut *height };
+
+                let size = resize_hints.constrain(Size {
+                    width: *width as f64,
+                    height: *height as f64,
+                });
+                *width = size.width.round() as u32;
+                *height = size.height.round() as u32;
+
+                true
+            })
+            .unwrap_or(false)
     }
 
-    unsafe extern "C" fn gui_set_size(
-        _plugin: *const clap_plugin,
-        _width: u32,
-        _height: u32,
-    ) -> bool {
-        false
+    unsafe extern "C" fn gui_set_size(plugin: *const clap_plugin, width: u32, height: u32) -> bool {
+        let instance = unsafe { &*(plugin as *const Self) };
+
+        instance
+            .guard("clap_plugin_gui.set_size", || {
+                let size = Size {
+                    width: width as f64,
+                    height: height as f64,
+                };
+
+                // The host may resize the editor from within `request_resize`, while the editor is
+                // still running. Apply the size from `on_main_thread` in that case.
+                let Ok(mut main_thread_state) = instance.main_thread_state.try_borrow() else {
+                    instance.deferred.borrow().set_size(size);
+                    unsafe { (*instance.host.0).request_callback.unwrap()(instance.host.0) };
+                    return true;
+                };
+
+                if let Some(editor) = &mut main_thread_state.editor {
+                    return editor.set_size(size);
+                }
+
+                false
+            })
+            .unwrap_or(false)
     }
 
     unsafe extern "C" fn gui_set_parent(
         plugin: *const clap_plugin,
         window: *const clap_window,
     ) -> bool {
-        let window = unsafe { &*window };
-
-        if unsafe { CStr::from_ptr(window.api) } != Self::API {
-            return false;
-        }
-
-        #[cfg(target_os = "windows")]
-        let raw_parent = { RawParent::Win32(unsafe { window.specific.win32 }) };
-
-        #[cfg(target_os = "macos")]
-        let raw_parent = { RawParent::Cocoa(unsafe { window.specific.cocoa }) };
-
-        #[cfg(target_os = "linux")]
-        let raw_parent = { RawParent::X11(unsafe { window.specific.x11 }) };
-
         let instance = unsafe { &*(plugin as *const Self) };
-        let mut main_thread_state = instance.main_thread_state.borrow();
-
-        let host = EditorHost::from_inner(Rc::new(ClapEditorHost {
-            host: instance.host,
-            extensions: main_thread_state.extensions,
-            param_gestures: Arc::clone(&instance.param_gestures),
-        }));
-        let parent = unsafe { ParentWindow::from_raw(raw_parent) };
-        let editor = main_thread_state.plugin.editor(host, &parent);
-        main_thread_state.editor = Some(ThreadCell::new(editor));
 
-        true
+        instance
+            .guard("clap_plugin_gui.set_parent", || {
+                let window = unsafe { &*window };
+
+                if unsafe { CStr::from_ptr(window.api) } != Self::API {
+                    return false;
+                }
+
+                #[cfg(target_os = "windows")]
+                let raw_parent = { RawParent::Win32(unsafe { window.specific.win32 }) };
+
+                #[cfg(target_os = "macos")]
+                let raw_parent = { RawParent::Cocoa(unsafe { window.specific.cocoa }) };
+
+                #[cfg(target_os = "linux")]
+                let raw_parent = { RawParent::X11(unsafe { window.specific.x11 }) };
+
+                let mut main_thread_state = instance.main_thread_state.borrow();
+
+                let host = EditorHost::from_inner(Rc::new(ClapEditorHost {
+                    host: instance.host,
+                    extensions: main_thread_state.extensions,
+                    param_ids: Arc::clone(&instance.param_ids),
+                    param_gestures: Arc::clone(&instance.param_gestures),
+                    deferred: Arc::clone(&instance.deferred),
+                    context_menu: Arc::clone(&instance.context_menu),
+                }));
+                let parent = unsafe { ParentWindow::from_raw(raw_parent) }
+                    .with_scale(main_thread_state.scale);
+                instance.unregister_idle(&mut main_thread_state);
+                let editor = main_thread_state.plugin.editor(host, &parent);
+                main_thread_state.editor = Some(ThreadCell::new(editor));
+                instance.register_idle(&mut main_thread_state);
+
+                true
+            })
+            .unwrap_or(false)
     }
 
     unsafe extern "C" fn gui_set_transient(
@@ -222,3 +437,105 @@ impl<P: Plugin> Instance<P> {
         false
     }
 }
+
+impl<P: Plugin> Instance<P> {
+    pub(super) const TIMER_SUPPORT: clap_plugin_timer_support = clap_plugin_timer_support {
+        on_timer: Some(Self::timer_support_on_timer),
+    };
+
+    #[cfg(target_os = "linux")]
+    pub(super) const POSIX_FD_SUPPORT: clap_plugin_posix_fd_support =
+        clap_plugin_posix_fd_support {
+            on_fd: Some(Self::posix_fd_support_on_fd),
+        };
+
+    fn register_idle(&self, main_thread_state: &mut MainThreadState<P>) {
+        if let Some(host_timer_support) = main_thread_state.extensions.host_timer_support {
+            let mut timer_id = CLAP_INVALID_ID;
+            let registered = unsafe {
+                host_timer_support.as_ref().register_timer.unwrap()(
+                    self.host.0,
+                    IDLE_INTERVAL_MS,
+                    &mut timer_id,
+                )
+            };
+            if registered {
+                main_thread_state.timer_id = Some(timer_id);
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        if let Some(host_posix_fd_support) = main_thread_state.extensions.host_posix_fd_support
+            && let Some(editor) = &main_thread_state.editor
+            && let Some(fd) = editor.poll_fd()
+        {
+            let registered = unsafe {
+                host_posix_fd_support.as_ref().register_fd.unwrap()(
+                    self.host.0,
+                    fd,
+                    CLAP_POSIX_FD_READ,
+                )
+            };
+            if registered {
+                main_thread_state.poll_fd = Some(fd);
+            }
+        }
+    }
+
+    fn unregister_idle(&self, main_thread_state: &mut MainThreadState<P>) {
+        if let Some(timer_id) = main_thread_state.timer_id.take()
+            && let Some(host_timer_support) = main_thread_state.extensions.host_timer_support
+        {
+            unsafe { host_timer_support.as_ref().unregister_timer.unwrap()(self.host.0, timer_id) };
+        }
+
+        if let Some(fd) = main_thread_state.poll_fd.take()
+            && let Some(host_posix_fd_support) = main_thread_state.extensions.host_posix_fd_support
+        {
+            unsafe { host_posix_fd_support.as_ref().unregister_fd.unwrap()(self.host.0, fd) };
+        }
+    }
+
+    unsafe extern "C" fn timer_support_on_timer(plugin: *const clap_plugin, timer_id: clap_id) {
+        let instance = unsafe { &*(plugin as *const Self) };
+
+        instance.guard("clap_plugin_timer_support.on_timer", || {
+            // Hosts may run their event loop from within a call made by the editor, such as the
+            // context menu's popup, in which case this tick is skipped.
+            let Ok(mut main_thread_state) = instance.main_thread_state.try_borrow() else {
+                return;
+            };
+
+            if main_thread_state.timer_id == Some(timer_id) {
+                instance.run_deferred(&mut main_thread_state);
+
+                if let Some(editor) = &mut main_thread_state.editor {
+                    editor.idle();
+                }
+            }
+        });
+    }
+
+    #[cfg(target_os = "linux")]
+    unsafe extern "C" fn posix_fd_support_on_fd(
+        plugin: *const clap_plugin,
+        fd: std::ffi::c_int,
+        _flags: clap_posix_fd_flags,
+    ) {
+        let instance = unsafe { &*(plugin as *const Self) };
+
+        instance.guard("clap_plugin_posix_fd_support.on_fd", || {
+            // Hosts may run their event loop from within a call made by the editor, such as the
+            // context menu's popup, in which case this tick is skipped.
+            let Ok(mut main_thread_state) = instance.main_thread_state.try_borrow() else {
+                return;
+            };
+
+            if main_thread_state.poll_fd == Some(fd)
+                && let Some(editor) = &mut main_thread_state.editor
+            {
+                editor.idle();
+            }
+        });
+    }
+}
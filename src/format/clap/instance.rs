@@ -1,27 +1,38 @@
 use std::collections::HashMap;
-use std::ffi::{CStr, c_char, c_void};
+use std::ffi::{CStr, CString, c_char, c_int, c_void};
 use std::iter::zip;
 use std::ptr::NonNull;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::{io, mem, ptr, slice};
 
-use clap_sys::ext::{audio_ports::*, audio_ports_config::*, gui::*, params::*, state::*};
+use clap_sys::ext::draft::{context_menu::*, param_indication::*, remote_controls::*};
+use clap_sys::ext::{
+    audio_ports::*, audio_ports_config::*, gui::*, log::*, params::*, posix_fd_support::*,
+    state::*, thread_check::*, timer_support::*,
+};
 use clap_sys::{events::*, host::*, id::*, plugin::*, process::*, stream::*};
 
+use super::context_menu::ContextMenu;
 use super::host::ClapHost;
 use crate::buffers::{BufferData, BufferType, Buffers};
 use crate::bus::{BusDir, Layout};
 use crate::collect::{
-    OwnedBusConfig, OwnedBusInfo, OwnedParamInfo, collect_bus_configs, collect_buses,
-    collect_params,
+    OwnedBusConfig, OwnedBusInfo, OwnedParamInfo, OwnedRemoteControlsPage, collect_bus_configs,
+    collect_buses, collect_params, collect_remote_controls,
 };
 use crate::editor::Editor;
 use crate::events::{Data, Event, Events};
+use crate::format::deferred::Deferred;
 use crate::host::Host;
+use crate::log::{self, Level, Logger};
+use crate::panic;
 use crate::plugin::Plugin;
 use crate::process::{Config, Processor};
+use crate::rt_check;
 use crate::sync::param_gestures::{GestureStates, GestureUpdate, ParamGestures};
 use crate::sync::params::ParamValues;
+use crate::sync::thread_check::{HostThreadCheck, MainThreadCell};
 use crate::sync::{sync_cell::SyncCell, thread_cell::ThreadCell};
 use crate::util::{RequireSendSync, copy_cstring, slice_from_raw_parts_checked};
 
@@ -42,7 +53,7 @@ fn map_param_in(param: &OwnedParamInfo, value: f64) -> f64 {
 
 fn map_param_out(param: &OwnedParamInfo, value: f64) -> f64 {
     if let Some(steps) = param.steps {
-        (value * steps as f64).floor()
+        (value * steps as f64).floor().min(steps.saturating_sub(1) as f64)
     } else {
         value
     }
@@ -54,9 +65,59 @@ pub struct HostPtr(pub *const clap_host);
 unsafe impl Send for HostPtr {}
 unsafe impl Sync for HostPtr {}
 
+#[derive(Copy, Clone)]
+struct HostLog {
+    host: HostPtr,
+    host_log: NonNull<clap_host_log>,
+}
+
+unsafe impl Send for HostLog {}
+unsafe impl Sync for HostLog {}
+
+impl HostLog {
+    fn log(&self, level: Level, message: &str) {
+        let severity = match level {
+            Level::Error => CLAP_LOG_ERROR,
+            Level::Warning => CLAP_LOG_WARNING,
+            Level::Info => CLAP_LOG_INFO,
+            Level::Debug => CLAP_LOG_DEBUG,
+        };
+        let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+
+        unsafe { (*self.host_log.as_ptr()).log.unwrap()(self.host.0, severity, message.as_ptr()) };
+    }
+}
+
+struct ClapThreadCheck {
+    host: HostPtr,
+    host_thread_check: NonNull<clap_host_thread_check>,
+}
+
+unsafe impl Send for ClapThreadCheck {}
+unsafe impl Sync for ClapThreadCheck {}
+
+impl HostThreadCheck for ClapThreadCheck {
+    fn is_main_thread(&self) -> bool {
+        let host_thread_check = unsafe { self.host_thread_check.as_ref() };
+        host_thread_check.is_main_thread.is_none_or(|f| unsafe { f(self.host.0) })
+    }
+
+    fn is_audio_thread(&self) -> bool {
+        let host_thread_check = unsafe { self.host_thread_check.as_ref() };
+        host_thread_check.is_audio_thread.is_none_or(|f| unsafe { f(self.host.0) })
+    }
+}
+
 #[derive(Copy, Clone)]
 pub struct Extensions {
     pub host_params: Option<NonNull<clap_host_params>>,
+    pub host_gui: Option<NonNull<clap_host_gui>>,
+    pub host_state: Option<NonNull<clap_host_state>>,
+    pub host_context_menu: Option<NonNull<clap_host_context_menu>>,
+    pub host_timer_support: Option<NonNull<clap_host_timer_support>>,
+    pub host_posix_fd_support: Option<NonNull<clap_host_posix_fd_support>>,
+    pub host_log: Option<NonNull<clap_host_log>>,
+    pub host_thread_check: Option<NonNull<clap_host_thread_check>>,
 }
 
 unsafe impl Send for Extensions {}
@@ -67,6 +128,9 @@ pub struct MainThreadState<P: Plugin> {
     pub bus_config_index: usize,
     pub plugin: P,
     pub editor: Option<ThreadCell<P::Editor>>,
+    pub scale: f64,
+    pub timer_id: Option<clap_id>,
+    pub poll_fd: Option<c_int>,
 }
 
 struct RawBuffers {
@@ -94,16 +158,26 @@ pub struct Instance<P: Plugin> {
     pub bus_configs: Vec<OwnedBusConfig>,
     pub input_bus_map: Vec<usize>,
     pub output_bus_map: Vec<usize>,
-    pub param_ids: Vec<u32>,
+    pub param_ids: Arc<Vec<u32>>,
     pub params: Vec<OwnedParamInfo>,
     pub param_map: HashMap<u32, usize>,
+    pub remote_controls_ids: Vec<u32>,
+    pub remote_controls: Vec<OwnedRemoteControlsPage>,
     // Processor -> plugin parameter changes
     pub plugin_params: ParamValues,
     // Plugin -> processor parameter changes
     pub processor_params: ParamValues,
     pub param_gestures: Arc<ParamGestures>,
+    pub deferred: Arc<SyncCell<Deferred>>,
+    pub context_menu: Arc<SyncCell<Option<ContextMenu>>>,
     pub has_editor: bool,
-    pub main_thread_state: SyncCell<MainThreadState<P>>,
+    // Set between `activate` and `deactivate`, during which `params.flush` is called on the audio
+    // thread.
+    pub active: AtomicBool,
+    // Set once plugin code has panicked, after which the instance only outputs silence.
+    pub failed: AtomicBool,
+    pub logger: Logger,
+    pub main_thread_state: MainThreadCell<MainThreadState<P>>,
     pub process_state: SyncCell<ProcessState<P>>,
 }
 
@@ -137,6 +211,8 @@ impl<P: Plugin> Instance<P> {
             param_map.insert(id, index);
         }
 
+        let (remote_controls_ids, remote_controls) = collect_remote_controls(&plugin);
+
         let has_editor = plugin.has_editor();
 
         Instance {
@@ -161,17 +237,36 @@ impl<P: Plugin> Instance<P> {
             input_bus_map,
             output_bus_map,
             params,
-            param_ids,
+            param_ids: Arc::new(param_ids),
             param_map,
+            remote_controls_ids,
+            remote_controls,
             plugin_params: ParamValues::with_count(param_count),
             processor_params: ParamValues::with_count(param_count),
             param_gestures: Arc::new(ParamGestures::with_count(param_count)),
+            deferred: Arc::new(SyncCell::new(Deferred::new())),
+            context_menu: Arc::new(SyncCell::new(None)),
             has_editor,
-            main_thread_state: SyncCell::new(MainThreadState {
-                extensions: Extensions { host_params: None },
+            active: AtomicBool::new(false),
+            failed: AtomicBool::new(false),
+            logger: Logger::new(),
+            main_thread_state: MainThreadCell::new(MainThreadState {
+                extensions: Extensions {
+                    host_params: None,
+                    host_gui: None,
+                    host_state: None,
+                    host_context_menu: None,
+                    host_timer_support: None,
+                    host_posix_fd_support: None,
+                    host_log: None,
+                    host_thread_check: None,
+                },
                 bus_config_index: 0,
                 plugin,
                 editor: None,
+                scale: 1.0,
+                timer_id: None,
+                poll_fd: None,
             }),
             process_state: SyncCell::new(ProcessState {
                 gesture_states: GestureStates::with_count(param_count),
@@ -185,6 +280,12 @@ impl<P: Plugin> Instance<P> {
         }
     }
 
+    /// Runs `f`, marking the instance as failed if it panics. Also writes out any log messages
+    /// queued from the audio thread.
+    pub fn guard<R>(&self, context: &'static str, f: impl FnOnce() -> R) -> Option<R> {
+        panic::guard(&self.failed, &self.logger, context, f)
+    }
+
     fn sync_plugin(&self, main_thread_state: &mut MainThreadState<P>) {
         for (index, value) in self.plugin_params.poll() {
             main_thread_state.plugin.set_param(index, value);
@@ -195,9 +296,21 @@ impl<P: Plugin> Instance<P> {
         }
     }
 
+    pub(super) fn run_deferred(&self, main_thread_state: &mut MainThreadState<P>) {
+        let delivered = Deferred::apply(
+            &self.deferred,
+            &mut main_thread_state.plugin,
+            main_thread_state.editor.as_deref_mut(),
+        );
+
+        if delivered && let Some(host_state) = main_thread_state.extensions.host_state {
+            unsafe { host_state.as_ref().mark_dirty.unwrap()(self.host.0) };
+        }
+    }
+
     fn sync_processor(&self, processor: &mut P::Processor) {
         for (index, value) in self.processor_params.poll() {
-            processor.set_param(index, value);
+            rt_check::check("Processor::set_param", || processor.set_param(index, value));
         }
     }
 
@@ -210,7 +323,9 @@ impl<P: Plugin> Instance<P> {
     ) {
         for update in self.param_gestures.poll(gesture_states) {
             if let Some(value) = update.set_value {
-                processor.set_param(update.index, value);
+                rt_check::check("Processor::set_param", || {
+                    processor.set_param(update.index, value)
+                });
 
                 self.plugin_params.set(update.index, value);
             }
@@ -299,15 +414,101 @@ impl<P: Plugin> Instance<P> {
 impl<P: Plugin> Instance<P> {
     unsafe extern "C" fn init(plugin: *const clap_plugin) -> bool {
         let instance = unsafe { &*(plugin as *const Self) };
-        let mut main_thread_state = instance.main_thread_state.borrow();
 
-        let host_params = unsafe {
-            (*instance.host.0).get_extension.unwrap()(instance.host.0, CLAP_EXT_PARAMS.as_ptr())
-        };
-        main_thread_state.extensions.host_params =
-            NonNull::new(host_params as *mut clap_host_params);
+        instance
+            .guard("clap_plugin.init", || {
+                let mut main_thread_state = instance.main_thread_state.borrow();
 
-        true
+                let host_params = unsafe {
+                    (*instance.host.0).get_extension.unwrap()(
+                        instance.host.0,
+                        CLAP_EXT_PARAMS.as_ptr(),
+                    )
+                };
+                main_thread_state.extensions.host_params =
+                    NonNull::new(host_params as *mut clap_host_params);
+
+                let host_gui = unsafe {
+                    (*instance.host.0).get_extension.unwrap()(
+                        instance.host.0,
+                        CLAP_EXT_GUI.as_ptr(),
+                    )
+                };
+                main_thread_state.extensions.host_gui =
+                    NonNull::new(host_gui as *mut clap_host_gui);
+
+                let host_state = unsafe {
+                    (*instance.host.0).get_extension.unwrap()(
+                        instance.host.0,
+                        CLAP_EXT_STATE.as_ptr(),
+                    )
+                };
+                main_thread_state.extensions.host_state =
+                    NonNull::new(host_state as *mut clap_host_state);
+
+                let host_context_menu = unsafe {
+                    (*instance.host.0).get_extension.unwrap()(
+                        instance.host.0,
+                        CLAP_EXT_CONTEXT_MENU.as_ptr(),
+                    )
+                };
+                main_thread_state.extensions.host_context_menu =
+                    NonNull::new(host_context_menu as *mut clap_host_context_menu);
+
+                let host_timer_support = unsafe {
+                    (*instance.host.0).get_extension.unwrap()(
+                        instance.host.0,
+                        CLAP_EXT_TIMER_SUPPORT.as_ptr(),
+                    )
+                };
+                main_thread_state.extensions.host_timer_support =
+                    NonNull::new(host_timer_support as *mut clap_host_timer_support);
+
+                let host_posix_fd_support = unsafe {
+                    (*instance.host.0).get_extension.unwrap()(
+                        instance.host.0,
+                        CLAP_EXT_POSIX_FD_SUPPORT.as_ptr(),
+                    )
+                };
+                main_thread_state.extensions.host_posix_fd_support =
+                    NonNull::new(host_posix_fd_support as *mut clap_host_posix_fd_support);
+
+                let host_log = unsafe {
+                    (*instance.host.0).get_extension.unwrap()(
+                        instance.host.0,
+                        CLAP_EXT_LOG.as_ptr(),
+                    )
+                };
+                main_thread_state.extensions.host_log =
+                    NonNull::new(host_log as *mut clap_host_log);
+
+                if let Some(host_log) = main_thread_state.extensions.host_log {
+                    let host_log = HostLog {
+                        host: instance.host,
+                        host_log,
+                    };
+                    instance.logger.set_sink(move |level, message| host_log.log(level, message));
+                }
+
+                let host_thread_check = unsafe {
+                    (*instance.host.0).get_extension.unwrap()(
+                        instance.host.0,
+                        CLAP_EXT_THREAD_CHECK.as_ptr(),
+                    )
+                };
+                main_thread_state.extensions.host_thread_check =
+                    NonNull::new(host_thread_check as *mut clap_host_thread_check);
+
+                if let Some(host_thread_check) = main_thread_state.extensions.host_thread_check {
+                    instance.main_thread_state.thread_check().set_host(ClapThreadCheck {
+                        host: instance.host,
+                        host_thread_check,
+                    });
+                }
+
+                true
+            })
+            .unwrap_or(false)
     }
 
     unsafe extern "C" fn destroy(plugin: *const clap_plugin) {
@@ -321,56 +522,66 @@ impl<P: Plugin> Instance<P> {
         max_frames_count: u32,
     ) -> bool {
         let instance = unsafe { &*(plugin as *const Self) };
-        let mut main_thread_state = instance.main_thread_state.borrow();
-        let mut process_state = instance.process_state.borrow();
 
-        let bus_config = &instance.bus_configs[main_thread_state.bus_config_index];
+        instance
+            .guard("clap_plugin.activate", || {
+                let mut main_thread_state = instance.main_thread_state.borrow();
+                let mut process_state = instance.process_state.borrow();
 
-        process_state.buffers.data.clear();
-        let mut total_channels = 0;
-        for (info, layout) in zip(&instance.buses, &bus_config.layouts) {
-            let buffer_type = match info.dir {
-                BusDir::In => BufferType::Const,
-                BusDir::Out | BusDir::InOut => BufferType::Mut,
-            };
-            let channel_count = layout.channel_count();
+                let bus_config = &instance.bus_configs[main_thread_state.bus_config_index];
 
-            process_state.buffers.data.push(BufferData {
-                buffer_type,
-                start: total_channels,
-                end: total_channels + channel_count,
-            });
+                process_state.buffers.data.clear();
+                let mut total_channels = 0;
+                for (info, layout) in zip(&instance.buses, &bus_config.layouts) {
+                    let buffer_type = match info.dir {
+                        BusDir::In => BufferType::Const,
+                        BusDir::Out | BusDir::InOut => BufferType::Mut,
+                    };
+                    let channel_count = layout.channel_count();
 
-            total_channels += channel_count;
-        }
+                    process_state.buffers.data.push(BufferData {
+                        buffer_type,
+                        start: total_channels,
+                        end: total_channels + channel_count,
+                    });
+
+                    total_channels += channel_count;
+                }
 
-        process_state.buffers.ptrs.resize(total_channels, NonNull::dangling().as_ptr());
+                process_state.buffers.ptrs.resize(total_channels, NonNull::dangling().as_ptr());
 
-        let config = Config {
-            layouts: &bus_config.layouts,
-            sample_rate,
-            max_buffer_size: max_frames_count as usize,
-        };
+                let config = Config {
+                    layouts: &bus_config.layouts,
+                    sample_rate,
+                    max_buffer_size: max_frames_count as usize,
+                };
 
-        // Discard any pending plugin -> processor parameter changes, since they will already be
-        // reflected in the initial state of the processor.
-        for _ in instance.processor_params.poll() {}
+                // Discard any pending plugin -> processor parameter changes, since they will
+                // already be reflected in the initial state of the processor.
+                for _ in instance.processor_params.poll() {}
 
-        process_state.processor = Some(main_thread_state.plugin.processor(config));
+                process_state.processor = Some(main_thread_state.plugin.processor(config));
+                instance.active.store(true, Ordering::Release);
 
-        true
+                true
+            })
+            .unwrap_or(false)
     }
 
     unsafe extern "C" fn deactivate(plugin: *const clap_plugin) {
         let instance = unsafe { &*(plugin as *const Self) };
-        let mut main_thread_state = instance.main_thread_state.borrow();
-        let mut process_state = instance.process_state.borrow();
 
-        // Apply any remaining processor -> plugin parameter changes. There won't be any more until
-        // the next call to `activate`.
-        instance.sync_plugin(&mut *main_thread_state);
+        instance.guard("clap_plugin.deactivate", || {
+            let mut main_thread_state = instance.main_thread_state.borrow();
+            let mut process_state = instance.process_state.borrow();
+
+            // Apply any remaining processor -> plugin parameter changes. There won't be any more
+            // until the next call to `activate`.
+            instance.sync_plugin(&mut *main_thread_state);
 
-        process_state.processor = None;
+            process_state.processor = None;
+            instance.active.store(false, Ordering::Release);
+        });
     }
 
     unsafe extern "C" fn start_processing(_plugin: *const clap_plugin) -> bool {
@@ -381,13 +592,23 @@ impl<P: Plugin> Instance<P> {
 
     unsafe extern "C" fn reset(plugin: *const clap_plugin) {
         let instance = unsafe { &*(plugin as *const Self) };
-        let mut process_state_guard = instance.process_state.borrow();
-        let process_state = &mut *process_state_guard;
 
-        if let Some(processor) = &mut process_state.processor {
-            instance.sync_processor(processor);
-            processor.reset();
+        // Once the plugin has panicked, the processor is no longer called.
+        if instance.failed.load(Ordering::Acquire) {
+            return;
         }
+
+        log::audio_thread(|| {
+            instance.guard("clap_plugin.reset", || {
+                let mut process_state_guard = instance.process_state.borrow();
+                let process_state = &mut *process_state_guard;
+
+                if let Some(processor) = &mut process_state.processor {
+                    instance.sync_processor(processor);
+                    rt_check::check("Processor::reset", || processor.reset());
+                }
+            })
+        });
     }
 
     unsafe extern "C" fn process(
@@ -395,6 +616,56 @@ impl<P: Plugin> Instance<P> {
         process: *const clap_process,
     ) -> clap_process_status {
         let instance = unsafe { &*(plugin as *const Self) };
+
+        if !instance.failed.load(Ordering::Acquire) {
+            let status = log::audio_thread(|| {
+                instance.guard("clap_plugin.process", || unsafe {
+                    Self::process_inner(instance, process)
+                })
+            });
+
+            // Make sure messages logged during processing are written out promptly.
+            if instance.logger.pending() {
+                unsafe { (*instance.host.0).request_callback.unwrap()(instance.host.0) };
+            }
+
+            if let Some(status) = status {
+                return status;
+            }
+        }
+
+        // Once the plugin has panicked, output silence.
+        let process = unsafe { &*process };
+        let len = process.frames_count as usize;
+        let outputs = unsafe {
+            slice_from_raw_parts_checked(
+                process.audio_outputs,
+                process.audio_outputs_count as usize,
+            )
+        };
+        for output in outputs {
+            let channels = unsafe {
+                slice_from_raw_parts_checked(
+                    output.data32 as *const *mut f32,
+                    output.channel_count as usize,
+                )
+            };
+            for &channel in channels {
+                if !channel.is_null() {
+                    unsafe { slice::from_raw_parts_mut(channel, len) }.fill(0.0);
+                }
+            }
+        }
+
+        CLAP_PROCESS_ERROR
+    }
+
+    unsafe fn process_inner(instance: &Self, process: *const clap_process) -> clap_process_status {
+        instance
+            .main_thread_state
+            .thread_check()
+            .check_audio_thread("clap_plugin.process");
+
         let mut process_state_guard = instance.process_state.borrow();
         let process_state = &mut *process_state_guard;
 
@@ -503,7 +774,8 @@ impl<P: Plugin> Instance<P> {
                 len,
             )
         };
-        processor.process(buffers, Events::new(&process_state.events));
+        let events = Events::new(&process_state.events);
+        rt_check::check("Processor::process", || processor.process(buffers, events));
 
         let last_sample = process.frames_count.saturating_sub(1);
         unsafe {
@@ -540,6 +812,10 @@ impl<P: Plugin> Instance<P> {
             return &Self::STATE as *const _ as *const c_void;
         }
 
+        if id == CLAP_EXT_REMOTE_CONTROLS {
+            return &Self::REMOTE_CONTROLS as *const _ as *const c_void;
+        }
+
         if id == CLAP_EXT_GUI {
             let instance = unsafe { &*(plugin as *const Self) };
             if instance.has_editor {
@@ -547,14 +823,59 @@ impl<P: Plugin> Instance<P> {
             }
         }
 
+        if id == CLAP_EXT_CONTEXT_MENU {
+            let instance = unsafe { &*(plugin as *const Self) };
+            if instance.has_editor {
+                return &Self::CONTEXT_MENU as *const _ as *const c_void;
+            }
+        }
+
+        if id == CLAP_EXT_PARAM_INDICATION {
+            let instance = unsafe { &*(plugin as *const Self) };
+            if instance.has_editor {
+                return &Self::PARAM_INDICATION as *const _ as *const c_void;
+            }
+        }
+
+        if id == CLAP_EXT_TIMER_SUPPORT {
+            let instance = unsafe { &*(plugin as *const Self) };
+            if instance.has_editor {
+                return &Self::TIMER_SUPPORT as *const _ as *const c_void;
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        if id == CLAP_EXT_POSIX_FD_SUPPORT {
+            let instance = unsafe { &*(plugin as *const Self) };
+            if instance.has_editor {
+                return &Self::POSIX_FD_SUPPORT as *const _ as *const c_void;
+            }
+        }
+
         ptr::null()
     }
 
     unsafe extern "C" fn on_main_thread(plugin: *const clap_plugin) {
         let instance = unsafe { &*(plugin as *const Self) };
-        let mut main_thread_state = instance.main_thread_state.borrow();
 
-        instance.sync_plugin(&mut *main_thread_state);
+        instance.guard("clap_plugin.on_main_thread", || {
+            // Hosts may run their event loop from within a call made by the editor, such as the
+            // context menu's popup. Anything pending is handled on a later callback.
+            let Ok(mut main_thread_state) = instance.main_thread_state.try_borrow() else {
+                return;
+            };
+
+            instance.sync_plugin(&mut *main_thread_state);
+            instance.run_deferred(&mut main_thread_state);
+
+            // Fall back to idling the editor whenever we're woken up if the host doesn't provide
+            // timers. This isn't periodic; see `Editor::idle`.
+            if main_thread_state.timer_id.is_none()
+                && let Some(editor) = &mut main_thread_state.editor
+            {
+                editor.idle();
+            }
+        });
     }
 }
 
@@ -567,11 +888,15 @@ impl<P: Plugin> Instance<P> {
     unsafe extern "C" fn audio_ports_count(plugin: *const clap_plugin, is_input: bool) -> u32 {
         let instance = unsafe { &*(plugin as *const Self) };
 
-        if is_input {
-            instance.input_bus_map.len() as u32
-        } else {
-            instance.output_bus_map.len() as u32
-        }
+        instance
+            .guard("clap_plugin_audio_ports.count", || {
+                if is_input {
+                    instance.input_bus_map.len() as u32
+                } else {
+                    instance.output_bus_map.len() as u32
+                }
+            })
+            .unwrap_or(0)
     }
 
     unsafe extern "C" fn audio_ports_get(
@@ -581,50 +906,55 @@ impl<P: Plugin> Instance<P> {
         info: *mut clap_audio_port_info,
     ) -> bool {
         let instance = unsafe { &*(plugin as *const Self) };
-        let main_thread_state = instance.main_thread_state.borrow();
-
-        let bus_index = if is_input {
-            instance.input_bus_map.get(index as usize)
-        } else {
-            instance.output_bus_map.get(index as usize)
-        };
-
-        if let Some(&bus_index) = bus_index {
-            let bus_info = instance.buses.get(bus_index);
-
-            let bus_config = &instance.bus_configs[main_thread_state.bus_config_index];
-            let layout = bus_config.layouts.get(bus_index);
-
-            if let (Some(bus_info), Some(layout)) = (bus_info, layout) {
-                let port_info = unsafe { &mut *info };
 
-                port_info.id = instance.bus_ids[bus_index];
-                copy_cstring(&bus_info.name, &mut port_info.name);
-                port_info.flags = if index == 0 {
-                    CLAP_AUDIO_PORT_IS_MAIN
-                } else {
-                    0
-                };
-                port_info.channel_count = layout.channel_count() as u32;
-                port_info.port_type = port_type_from_layout(layout).as_ptr();
-                port_info.in_place_pair = if bus_info.dir == BusDir::InOut {
-                    // Find the other half of this input-output pair
-                    let bus_map = if is_input {
-                        &instance.output_bus_map
-                    } else {
-                        &instance.input_bus_map
-                    };
+        instance
+            .guard("clap_plugin_audio_ports.get", || {
+                let main_thread_state = instance.main_thread_state.borrow();
 
-                    bus_map.iter().position(|&i| i == bus_index).unwrap() as clap_id
+                let bus_index = if is_input {
+                    instance.input_bus_map.get(index as usize)
                 } else {
-                    CLAP_INVALID_ID
+                    instance.output_bus_map.get(index as usize)
                 };
 
-                return true;
-            }
-        }
+                if let Some(&bus_index) = bus_index {
+                    let bus_info = instance.buses.get(bus_index);
+
+                    let bus_config = &instance.bus_configs[main_thread_state.bus_config_index];
+                    let layout = bus_config.layouts.get(bus_index);
+
+                    if let (Some(bus_info), Some(layout)) = (bus_info, layout) {
+                        let port_info = unsafe { &mut *info };
+
+                        port_info.id = instance.bus_ids[bus_index];
+                        copy_cstring(&bus_info.name, &mut port_info.name);
+                        port_info.flags = if index == 0 {
+                            CLAP_AUDIO_PORT_IS_MAIN
+                        } else {
+                            0
+                        };
+                        port_info.channel_count = layout.channel_count() as u32;
+                        port_info.port_type = port_type_from_layout(layout).as_ptr();
+                        port_info.in_place_pair = if bus_info.dir == BusDir::InOut {
+                            // Find the other half of this input-output pair
+                            let bus_map = if is_input {
+                                &instance.output_bus_map
+                            } else {
+                                &instance.input_bus_map
+                            };
+
+                            bus_map.iter().position(|&i| i == bus_index).unwrap() as clap_id
+                        } else {
+                            CLAP_INVALID_ID
+                        };
+
+                        return true;
+                    }
+                }
 
-        false
+                false
+            })
+            .unwrap_or(false)
     }
 }
 
@@ -638,7 +968,11 @@ impl<P: Plugin> Instance<P> {
     unsafe extern "C" fn audio_ports_config_count(plugin: *const clap_plugin) -> u32 {
         let instance = unsafe { &*(plugin as *const Self) };
 
-        instance.bus_configs.len() as u32
+        instance
+            .guard("clap_plugin_audio_ports_config.count", || {
+                instance.bus_configs.len() as u32
+            })
+            .unwrap_or(0)
     }
 
     unsafe extern "C" fn audio_ports_config_get(
@@ -648,42 +982,46 @@ impl<P: Plugin> Instance<P> {
     ) -> bool {
         let instance = unsafe { &*(plugin as *const Self) };
 
-        if let Some(bus_config) = instance.bus_configs.get(index as usize) {
-            let config = unsafe { &mut *config };
+        instance
+            .guard("clap_plugin_audio_ports_config.get", || {
+                if let Some(bus_config) = instance.bus_configs.get(index as usize) {
+                    let config = unsafe { &mut *config };
 
-            config.id = index;
-            copy_cstring("", &mut config.name);
-            config.input_port_count = instance.input_bus_map.len() as u32;
-            config.output_port_count = instance.output_bus_map.len() as u32;
+                    config.id = index;
+                    copy_cstring("", &mut config.name);
+                    config.input_port_count = instance.input_bus_map.len() as u32;
+                    config.output_port_count = instance.output_bus_map.len() as u32;
 
-            if let Some(&bus_index) = instance.input_bus_map.first() {
-                config.has_main_input = true;
+                    if let Some(&bus_index) = instance.input_bus_map.first() {
+                        config.has_main_input = true;
 
-                let layout = &bus_config.layouts[bus_index];
-                config.main_input_channel_count = layout.channel_count() as u32;
-                config.main_input_port_type = port_type_from_layout(layout).as_ptr();
-            } else {
-                config.has_main_input = false;
-                config.main_input_channel_count = 0;
-                config.main_input_port_type = ptr::null();
-            }
+                        let layout = &bus_config.layouts[bus_index];
+                        config.main_input_channel_count = layout.channel_count() as u32;
+                        config.main_input_port_type = port_type_from_layout(layout).as_ptr();
+                    } else {
+                        config.has_main_input = false;
+                        config.main_input_channel_count = 0;
+                        config.main_input_port_type = ptr::null();
+                    }
 
-            if let Some(&bus_index) = instance.output_bus_map.first() {
-                config.has_main_output = true;
+                    if let Some(&bus_index) = instance.output_bus_map.first() {
+                        config.has_main_output = true;
 
-                let layout = &bus_config.layouts[bus_index];
-                config.main_output_channel_count = layout.channel_count() as u32;
-                config.main_output_port_type = port_type_from_layout(layout).as_ptr();
-            } else {
-                config.has_main_output = false;
-                config.main_output_channel_count = 0;
-                config.main_output_port_type = ptr::null();
-            }
+                        let layout = &bus_config.layouts[bus_index];
+                        config.main_output_channel_count = layout.channel_count() as u32;
+                        config.main_output_port_type = port_type_from_layout(layout).as_ptr();
+                    } else {
+                        config.has_main_output = false;
+                        config.main_output_channel_count = 0;
+                        config.main_output_port_type = ptr::null();
+                    }
 
-            return true;
-        }
+                    return true;
+                }
 
-        false
+                false
+            })
+            .unwrap_or(false)
     }
 
     unsafe extern "C" fn audio_ports_config_select(
@@ -691,14 +1029,19 @@ impl<P: Plugin> Instance<P> {
         config_id: clap_id,
     ) -> bool {
         let instance = unsafe { &*(plugin as *const Self) };
-        let mut main_thread_state = instance.main_thread_state.borrow();
 
-        if instance.bus_configs.get(config_id as usize).is_some() {
-            main_thread_state.bus_config_index = config_id as usize;
-            return true;
-        }
+        instance
+            .guard("clap_plugin_audio_ports_config.select", || {
+                let mut main_thread_state = instance.main_thread_state.borrow();
+
+                if instance.bus_configs.get(config_id as usize).is_some() {
+                    main_thread_state.bus_config_index = config_id as usize;
+                    return true;
+                }
 
-        false
+                false
+            })
+            .unwrap_or(false)
     }
 }
 
@@ -715,7 +1058,9 @@ impl<P: Plugin> Instance<P> {
     unsafe extern "C" fn params_count(plugin: *const clap_plugin) -> u32 {
         let instance = unsafe { &*(plugin as *const Self) };
 
-        instance.params.len() as u32
+        instance
+            .guard("clap_plugin_params.count", || instance.params.len() as u32)
+            .unwrap_or(0)
     }
 
     unsafe extern "C" fn params_get_info(
@@ -725,28 +1070,35 @@ impl<P: Plugin> Instance<P> {
     ) -> bool {
         let instance = unsafe { &*(plugin as *const Self) };
 
-        if let Some(param) = instance.params.get(param_index as usize) {
-            let param_info = unsafe { &mut *param_info };
-
-            param_info.id = instance.param_ids[param_index as usize];
-            param_info.flags = CLAP_PARAM_IS_AUTOMATABLE;
-            param_info.cookie = ptr::null_mut();
-            copy_cstring(&param.name, &mut param_info.name);
-            copy_cstring("", &mut param_info.module);
-            if let Some(steps) = param.steps {
-                param_info.flags |= CLAP_PARAM_IS_STEPPED;
-                param_info.min_value = 0.0;
-                param_info.max_value = (steps.max(2) - 1) as f64;
-            } else {
-                param_info.min_value = 0.0;
-                param_info.max_value = 1.0;
-            }
-            param_info.default_value = map_param_out(param, param.default);
+        instance
+            .guard("clap_plugin_params.get_info", || {
+                if let Some(param) = instance.params.get(param_index as usize) {
+                    let param_info = unsafe { &mut *param_info };
+
+                    param_info.id = instance.param_ids[param_index as usize];
+                    param_info.flags = CLAP_PARAM_IS_AUTOMATABLE;
+                    param_info.cookie = ptr::null_mut();
+                    copy_cstring(&param.name, &mut param_info.name);
+                    copy_cstring("", &mut param_info.module);
+                    if let Some(steps) = param.steps {
+                        param_info.flags |= CLAP_PARAM_IS_STEPPED;
+                        param_info.min_value = 0.0;
+                        param_info.max_value = (steps.max(2) - 1) as f64;
+                        if param.value_names.is_some() {
+                            param_info.flags |= CLAP_PARAM_IS_ENUM;
+                        }
+                    } else {
+                        param_info.min_value = 0.0;
+                        param_info.max_value = 1.0;
+                    }
+                    param_info.default_value = map_param_out(param, param.default);
 
-            return true;
-        }
+                    return true;
+                }
 
-        false
+                false
+            })
+            .unwrap_or(false)
     }
 
     unsafe extern "C" fn params_get_value(
@@ -755,18 +1107,23 @@ impl<P: Plugin> Instance<P> {
         value: *mut f64,
     ) -> bool {
         let instance = unsafe { &*(plugin as *const Self) };
-        let mut main_thread_state = instance.main_thread_state.borrow();
 
-        if let Some(&index) = instance.param_map.get(&param_id) {
-            instance.sync_plugin(&mut *main_thread_state);
+        instance
+            .guard("clap_plugin_params.get_value", || {
+                let mut main_thread_state = instance.main_thread_state.borrow();
 
-            let param = &instance.params[index];
-            let value = unsafe { &mut *value };
-            *value = map_param_out(param, main_thread_state.plugin.get_param(index));
-            return true;
-        }
+                if let Some(&index) = instance.param_map.get(&param_id) {
+                    instance.sync_plugin(&mut *main_thread_state);
 
-        false
+                    let param = &instance.params[index];
+                    let value = unsafe { &mut *value };
+                    *value = map_param_out(param, main_thread_state.plugin.get_param(index));
+                    return true;
+                }
+
+                false
+            })
+            .unwrap_or(false)
     }
 
     unsafe extern "C" fn params_value_to_text(
@@ -777,25 +1134,30 @@ impl<P: Plugin> Instance<P> {
         size: u32,
     ) -> bool {
         let instance = unsafe { &*(plugin as *const Self) };
-        let main_thread_state = instance.main_thread_state.borrow();
 
-        if let Some(&index) = instance.param_map.get(&param_id) {
-            let param = &instance.params[index];
+        instance
+            .guard("clap_plugin_params.value_to_text", || {
+                let main_thread_state = instance.main_thread_state.borrow();
 
-            let mut text = String::new();
-            let _ = main_thread_state.plugin.display_param(
-                index,
-                map_param_in(param, value),
-                &mut text,
-            );
+                if let Some(&index) = instance.param_map.get(&param_id) {
+                    let param = &instance.params[index];
 
-            let dst = unsafe { slice::from_raw_parts_mut(display, size as usize) };
-            copy_cstring(&text, dst);
+                    let mut text = String::new();
+                    let _ = main_thread_state.plugin.display_param(
+                        index,
+                        map_param_in(param, value),
+                        &mut text,
+                    );
 
-            return true;
-        }
+                    let dst = unsafe { slice::from_raw_parts_mut(display, size as usize) };
+                    copy_cstring(&text, dst);
 
-        false
+                    return true;
+                }
+
+                false
+            })
+            .unwrap_or(false)
     }
 
     unsafe extern "C" fn params_text_to_value(
@@ -805,22 +1167,25 @@ impl<P: Plugin> Instance<P> {
         value: *mut f64,
     ) -> bool {
         let instance = unsafe { &*(plugin as *const Self) };
-        let main_thread_state = instance.main_thread_state.borrow();
 
-        if let Some(&index) = instance.param_map.get(&param_id) {
-            if let Ok(text) = unsafe { CStr::from_ptr(display) }.to_str() {
-                let param = &instance.params[index];
-                if let Some(out) = main_thread_state.plugin.parse_param(index, text) {
+        instance
+            .guard("clap_plugin_params.text_to_value", || {
+                let main_thread_state = instance.main_thread_state.borrow();
+
+                if let Some(&index) = instance.param_map.get(&param_id)
+                    && let Ok(text) = unsafe { CStr::from_ptr(display) }.to_str()
+                    && let Some(out) = main_thread_state.plugin.parse_param(index, text)
+                    && !out.is_nan()
+                {
+                    let param = &instance.params[index];
                     let value = unsafe { &mut *value };
-                    *value = map_param_out(param, out);
+                    *value = map_param_out(param, out.clamp(0.0, 1.0));
                     return true;
                 }
-            }
-
-            return true;
-        }
 
-        false
+                false
+            })
+            .unwrap_or(false)
     }
 
     unsafe extern "C" fn params_flush(
@@ -829,79 +1194,107 @@ impl<P: Plugin> Instance<P> {
         out: *const clap_output_events,
     ) {
         let instance = unsafe { &*(plugin as *const Self) };
-        let mut process_state_guard = instance.process_state.borrow();
-        let process_state = &mut *process_state_guard;
 
         // If we are in the active state, flush will be called on the audio thread.
-        if let Some(processor) = &mut process_state.processor {
-            instance.sync_processor(processor);
+        if instance.active.load(Ordering::Acquire) {
+            // Once the plugin has panicked, the processor is no longer called.
+            if instance.failed.load(Ordering::Acquire) {
+                return;
+            }
 
-            let mut params_changed = false;
+            log::audio_thread(|| {
+                instance.guard("clap_plugin_params.flush", || {
+                    let mut process_state_guard = instance.process_state.borrow();
+                    let process_state = &mut *process_state_guard;
 
-            let size = unsafe { (*in_).size.unwrap()(in_) };
-            for i in 0..size {
-                let event = unsafe { (*in_).get.unwrap()(in_, i) };
+                    if let Some(processor) = &mut process_state.processor {
+                        instance.sync_processor(processor);
 
-                if unsafe { (*event).space_id } == CLAP_CORE_EVENT_SPACE_ID
-                    && unsafe { (*event).type_ } == CLAP_EVENT_PARAM_VALUE
-                {
-                    let event = unsafe { &*(event as *const clap_event_param_value) };
+                        let mut params_changed = false;
+
+                        let size = unsafe { (*in_).size.unwrap()(in_) };
+                        for i in 0..size {
+                            let event = unsafe { (*in_).get.unwrap()(in_, i) };
+
+                            if unsafe { (*event).space_id } == CLAP_CORE_EVENT_SPACE_ID
+                                && unsafe { (*event).type_ } == CLAP_EVENT_PARAM_VALUE
+                            {
+                                let event = unsafe { &*(event as *const clap_event_param_value) };
 
-                    if let Some(&index) = instance.param_map.get(&event.param_id) {
-                        let value = map_param_in(&instance.params[index], event.value);
+                                if let Some(&index) = instance.param_map.get(&event.param_id) {
+                                    let value = map_param_in(&instance.params[index], event.value);
 
-                        processor.set_param(index, value);
+                                    rt_check::check("Processor::set_param", || {
+                                        processor.set_param(index, value)
+                                    });
 
-                        instance.plugin_params.set(index, value);
+                                    instance.plugin_params.set(index, value);
 
-                        params_changed = true;
+                                    params_changed = true;
+                                }
+                            }
+                        }
+
+                        if params_changed {
+                            unsafe {
+                                (*instance.host.0).request_callback.unwrap()(instance.host.0)
+                            };
+                        }
+
+                        unsafe {
+                            instance.process_gestures(
+                                &mut process_state.gesture_states,
+                                processor,
+                                out,
+                                0,
+                            )
+                        };
                     }
-                }
-            }
+                })
+            });
 
-            if params_changed {
+            if instance.logger.pending() {
                 unsafe { (*instance.host.0).request_callback.unwrap()(instance.host.0) };
             }
-
-            unsafe {
-                instance.process_gestures(&mut process_state.gesture_states, processor, out, 0)
-            };
         }
         // Otherwise, flush will be called on the main thread.
         else {
-            let mut main_thread_state = instance.main_thread_state.borrow();
-
-            let size = unsafe { (*in_).size.unwrap()(in_) };
-            for i in 0..size {
-                let event = unsafe { (*in_).get.unwrap()(in_, i) };
-
-                if unsafe { (*event).space_id } == CLAP_CORE_EVENT_SPACE_ID
-                    && unsafe { (*event).type_ } == CLAP_EVENT_PARAM_VALUE
-                {
-                    let event = unsafe { &*(event as *const clap_event_param_value) };
-
-                    if let Some(&index) = instance.param_map.get(&event.param_id) {
-                        let value = map_param_in(&instance.params[index], event.value);
-                        main_thread_state.plugin.set_param(index, value);
-
-                        if let Some(editor) = &mut main_thread_state.editor {
-                            editor.param_changed(index, value);
+            instance.guard("clap_plugin_params.flush", || {
+                let mut process_state = instance.process_state.borrow();
+                let mut main_thread_state = instance.main_thread_state.borrow();
+
+                let size = unsafe { (*in_).size.unwrap()(in_) };
+                for i in 0..size {
+                    let event = unsafe { (*in_).get.unwrap()(in_, i) };
+
+                    if unsafe { (*event).space_id } == CLAP_CORE_EVENT_SPACE_ID
+                        && unsafe { (*event).type_ } == CLAP_EVENT_PARAM_VALUE
+                    {
+                        let event = unsafe { &*(event as *const clap_event_param_value) };
+
+                        if let Some(&index) = instance.param_map.get(&event.param_id) {
+                            let value = map_param_in(&instance.params[index], event.value);
+                            main_thread_state.plugin.set_param(index, value);
+
+                            if let Some(editor) = &mut main_thread_state.editor {
+                                editor.param_changed(index, value);
+                            }
                         }
                     }
                 }
-            }
 
-            for update in instance.param_gestures.poll(&mut process_state.gesture_states) {
-                if let Some(value) = update.set_value {
-                    main_thread_state.plugin.set_param(update.index, value);
+                for update in instance.param_gestures.poll(&mut process_state.gesture_states) {
+                    if let Some(value) = update.set_value {
+                        main_thread_state.plugin.set_param(update.index, value);
 
-                    if let Some(editor) = &mut main_thread_state.editor {
-                        editor.param_changed(update.index, value);
+                        if let Some(editor) = &mut main_thread_state.editor {
+                            editor.param_changed(update.index, value);
+                        }
                     }
-                }
 
-                unsafe { instance.send_gesture_events(&update, out, 0) };
-            }
+                    unsafe { instance.send_gesture_events(&update, out, 0) };
+                }
+            });
         }
     }
 }
@@ -928,10 +1321,10 @@ impl<P: Plugin> Instance<P> {
                     )
                 };
 
-                if result == -1 {
-                    Err(io::Error::other("failed to write to stream"))
-                } else {
-                    io::Result::Ok(result as usize)
+                // Only trust counts which fit in the buffer.
+                match usize::try_from(result) {
+                    Ok(count) if count <= buf.len() => Ok(count),
+                    _ => Err(io::Error::other("failed to write to stream")),
                 }
             }
 
@@ -941,11 +1334,16 @@ impl<P: Plugin> Instance<P> {
         }
 
         let instance = unsafe { &*(plugin as *const Self) };
-        let mut main_thread_state = instance.main_thread_state.borrow();
 
-        instance.sync_plugin(&mut *main_thread_state);
-        let result = main_thread_state.plugin.save(&mut StreamWriter(stream));
-        result.is_ok()
+        instance
+            .guard("clap_plugin_state.save", || {
+                let mut main_thread_state = instance.main_thread_state.borrow();
+
+                instance.sync_plugin(&mut *main_thread_state);
+                let result = main_thread_state.plugin.save(&mut StreamWriter(stream));
+                result.is_ok()
+            })
+            .unwrap_or(false)
     }
 
     unsafe extern "C" fn state_load(
@@ -964,31 +1362,41 @@ impl<P: Plugin> Instance<P> {
                     )
                 };
 
-                if result == -1 {
-                    Err(io::Error::other("failed to read from stream"))
-                } else {
-                    io::Result::Ok(result as usize)
+                // Only trust counts which fit in the buffer.
+                match usize::try_from(result) {
+                    Ok(count) if count <= buf.len() => Ok(count),
+                    _ => Err(io::Error::other("failed to read from stream")),
                 }
             }
         }
 
         let instance = unsafe { &*(plugin as *const Self) };
-        let mut main_thread_state = instance.main_thread_state.borrow();
 
-        instance.sync_plugin(&mut *main_thread_state);
-        if main_thread_state.plugin.load(&mut StreamReader(stream)).is_ok() {
-            for (index, _param) in instance.params.iter().enumerate() {
-                let value = main_thread_state.plugin.get_param(index);
-                instance.processor_params.set(index, value);
+        instance
+            .guard("clap_plugin_state.load", || {
+                let mut main_thread_state = instance.main_thread_state.borrow();
 
-                if let Some(editor) = &mut main_thread_state.editor {
-                    editor.param_changed(index, value);
-                }
-            }
+                instance.sync_plugin(&mut *main_thread_state);
+                if main_thread_state.plugin.load(&mut StreamReader(stream)).is_ok() {
+                    for (index, _param) in instance.params.iter().enumerate() {
+                        let value = main_thread_state.plugin.get_param(index);
+                        instance.processor_params.set(index, value);
 
-            return true;
-        }
+                        if let Some(editor) = &mut main_thread_state.editor {
+                            editor.param_changed(index, value);
+                        }
+                    }
+
+                    let main_thread_state = &mut *main_thread_state;
+                    if let Some(editor) = &mut main_thread_state.editor {
+                        main_thread_state.plugin.update_editor(editor);
+                    }
+
+                    return true;
+                }
 
-        false
+                false
+            })
+            .unwrap_or(false)
     }
 }
@@ -2,10 +2,13 @@ use std::ffi::{c_char, c_void};
 
 use clap_sys::{entry::*, version::*};
 
+mod context_menu;
 mod factory;
 mod gui;
 mod host;
 mod instance;
+mod param_indication;
+mod remote_controls;
 
 #[cfg(test)]
 mod tests;
@@ -78,17 +81,21 @@ macro_rules! clap {
                 ::coupler::format::clap::Factory::new();
 
             unsafe extern "C" fn init(_plugin_path: *const ::std::ffi::c_char) -> bool {
-                FACTORY.init()
+                ::coupler::panic::catch("clap_plugin_entry.init", || unsafe { FACTORY.init() })
+                    .unwrap_or(false)
             }
 
             unsafe extern "C" fn deinit() {
-                FACTORY.deinit();
+                ::coupler::panic::catch("clap_plugin_entry.deinit", || unsafe { FACTORY.deinit() });
             }
 
             unsafe extern "C" fn get_factory(
                 factory_id: *const ::std::ffi::c_char,
             ) -> *const ::std::ffi::c_void {
-                FACTORY.get(factory_id)
+                ::coupler::panic::catch("clap_plugin_entry.get_factory", || unsafe {
+                    FACTORY.get(factory_id)
+                })
+                .unwrap_or(::std::ptr::null())
             }
 
             ::coupler::format::clap::EntryPoint::new(init, deinit, get_factory)
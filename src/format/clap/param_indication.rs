@@ -0,0 +1,102 @@
+use std::ffi::{CStr, c_char};
+
+use clap_sys::color::*;
+use clap_sys::ext::draft::param_indication::*;
+use clap_sys::id::*;
+use clap_sys::plugin::*;
+
+use super::instance::Instance;
+use crate::editor::{AutomationState, Color, Editor, ParamMapping};
+use crate::plugin::Plugin;
+
+unsafe fn color_from_ptr(color: *const clap_color) -> Option<Color> {
+    if color.is_null() {
+        return None;
+    }
+
+    let color = unsafe { &*color };
+    Some(Color {
+        r: color.red,
+        g: color.green,
+        b: color.blue,
+        a: color.alpha,
+    })
+}
+
+unsafe fn string_from_ptr(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+
+    Some(unsafe { CStr::from_ptr(ptr) }.to_string_lossy().into_owned())
+}
+
+impl<P: Plugin> Instance<P> {
+    pub(super) const PARAM_INDICATION: clap_plugin_param_indication =
+        clap_plugin_param_indication {
+            set_mapping: Some(Self::param_indication_set_mapping),
+            set_automation: Some(Self::param_indication_set_automation),
+        };
+
+    unsafe extern "C" fn param_indication_set_mapping(
+        plugin: *const clap_plugin,
+        param_id: clap_id,
+        has_mapping: bool,
+        color: *const clap_color,
+        label: *const c_char,
+        description: *const c_char,
+    ) {
+        let instance = unsafe { &*(plugin as *const Self) };
+
+        instance.guard("clap_plugin_param_indication.set_mapping", || {
+            let mut main_thread_state = instance.main_thread_state.borrow();
+
+            let Some(&index) = instance.param_map.get(&param_id) else {
+                return;
+            };
+
+            let mapping = if has_mapping {
+                Some(ParamMapping {
+                    color: unsafe { color_from_ptr(color) },
+                    label: unsafe { string_from_ptr(label) },
+                    description: unsafe { string_from_ptr(description) },
+                })
+            } else {
+                None
+            };
+
+            if let Some(editor) = &mut main_thread_state.editor {
+                editor.param_mapping_changed(index, mapping);
+            }
+        });
+    }
+
+    unsafe extern "C" fn param_indication_set_automation(
+        plugin: *const clap_plugin,
+        param_id: clap_id,
+        automation_state: u32,
+        color: *const clap_color,
+    ) {
+        let instance = unsafe { &*(plugin as *const Self) };
+
+        instance.guard("clap_plugin_param_indication.set_automation", || {
+            let mut main_thread_state = instance.main_thread_state.borrow();
+
+            let Some(&index) = instance.param_map.get(&param_id) else {
+                return;
+            };
+
+            let state = match automation_state {
+                CLAP_PARAM_INDICATION_AUTOMATION_PRESENT => AutomationState::Present,
+                CLAP_PARAM_INDICATION_AUTOMATION_PLAYING => AutomationState::Playing,
+                CLAP_PARAM_INDICATION_AUTOMATION_RECORDING => AutomationState::Recording,
+                CLAP_PARAM_INDICATION_AUTOMATION_OVERRIDING => AutomationState::Overriding,
+                _ => AutomationState::None,
+            };
+
+            if let Some(editor) = &mut main_thread_state.editor {
+                editor.param_automation_changed(index, state, unsafe { color_from_ptr(color) });
+            }
+        });
+    }
+}
@@ -0,0 +1,56 @@
+use clap_sys::ext::draft::remote_controls::*;
+use clap_sys::id::*;
+use clap_sys::plugin::*;
+
+use super::instance::Instance;
+use crate::plugin::Plugin;
+use crate::util::copy_cstring;
+
+impl<P: Plugin> Instance<P> {
+    pub(super) const REMOTE_CONTROLS: clap_plugin_remote_controls = clap_plugin_remote_controls {
+        count: Some(Self::remote_controls_count),
+        get: Some(Self::remote_controls_get),
+    };
+
+    unsafe extern "C" fn remote_controls_count(plugin: *const clap_plugin) -> u32 {
+        let instance = unsafe { &*(plugin as *const Self) };
+
+        instance
+            .guard("clap_plugin_remote_controls.count", || {
+                instance.remote_controls.len() as u32
+            })
+            .unwrap_or(0)
+    }
+
+    unsafe extern "C" fn remote_controls_get(
+        plugin: *const clap_plugin,
+        page_index: u32,
+        page: *mut clap_remote_controls_page,
+    ) -> bool {
+        let instance = unsafe { &*(plugin as *const Self) };
+
+        instance
+            .guard("clap_plugin_remote_controls.get", || {
+                if let Some(remote_controls) = instance.remote_controls.get(page_index as usize) {
+                    let page = unsafe { &mut *page };
+
+                    copy_cstring(&remote_controls.section, &mut page.section_name);
+                    page.page_id = instance.remote_controls_ids[page_index as usize];
+                    copy_cstring(&remote_controls.name, &mut page.page_name);
+                    for (param_id, index) in page.param_ids.iter_mut().zip(&remote_controls.params)
+                    {
+                        *param_id = match index {
+                            Some(index) => instance.param_ids[*index],
+                            None => CLAP_INVALID_ID,
+                        };
+                    }
+                    page.is_for_preset = false;
+
+                    return true;
+                }
+
+                false
+            })
+            .unwrap_or(false)
+    }
+}
@@ -1,3 +1,4 @@
+use std::any::Any;
 use std::ffi::{CStr, c_char};
 use std::{fmt, io};
 
@@ -6,13 +7,14 @@ use clap_sys::version::CLAP_VERSION;
 
 use super::{BuildClapInfo, ClapInfo, ClapPlugin, Factory};
 use crate::buffers::Buffers;
-use crate::bus::{BuildBusConfigs, BuildBuses};
+use crate::bus::{BuildBusConfigs, BuildBuses, BusConfig, BusDir, BusInfo, Layout};
 use crate::editor::{Editor, EditorHost, ParentWindow, Size};
 use crate::events::Events;
 use crate::host::Host;
-use crate::params::BuildParams;
+use crate::params::{BuildParams, ParamInfo};
 use crate::plugin::{BuildInfo, Plugin, PluginInfo};
 use crate::process::{Config, Processor};
+use crate::testing::ClapTestHost;
 
 const NAME: &str = "test plugin";
 const VERSION: &str = "1.2.3";
@@ -21,7 +23,9 @@ const URL: &str = "https://example.com/";
 const EMAIL: &str = "example@example.com";
 const ID: &str = "com.example.plugin";
 
-struct TestPlugin;
+struct TestPlugin {
+    messages: Vec<&'static str>,
+}
 
 impl Plugin for TestPlugin {
     type Processor = TestProcessor;
@@ -37,11 +41,33 @@ impl Plugin for TestPlugin {
         })
     }
     fn new(_host: Host) -> Self {
-        TestPlugin
+        TestPlugin {
+            messages: Vec::new(),
+        }
+    }
+    fn buses(&self, build: impl BuildBuses) {
+        build.bus(
+            "main",
+            BusInfo {
+                name: "Main",
+                dir: BusDir::InOut,
+            },
+        );
+    }
+    fn bus_configs(&self, build: impl BuildBusConfigs) {
+        build.config(
+            "stereo",
+            BusConfig {
+                layouts: &[Layout::Stereo],
+            },
+        );
+    }
+    fn params(&self, build: impl BuildParams) {
+        build.param("gain", ParamInfo::new("Gain", 1.0)).param(
+            "mode",
+            ParamInfo::new("Mode", 0.0).with_steps(3).with_value_names(&["A", "B", "C"]),
+        );
     }
-    fn buses(&self, _build: impl BuildBuses) {}
-    fn bus_configs(&self, _build: impl BuildBusConfigs) {}
-    fn params(&self, _build: impl BuildParams) {}
     fn set_param(&mut self, _index: usize, _value: f64) {}
     fn get_param(&self, _index: usize) -> f64 {
         0.0
@@ -57,17 +83,23 @@ impl Plugin for TestPlugin {
     ) -> Result<(), fmt::Error> {
         Ok(())
     }
-    fn save(&self, _output: impl io::Write) -> io::Result<()> {
-        Ok(())
+    fn save(&self, mut output: impl io::Write) -> io::Result<()> {
+        output.write_all(self.messages.join(",").as_bytes())
     }
-    fn load(&mut self, _input: impl io::Read) -> io::Result<()> {
+    fn load(&mut self, mut input: impl io::Read) -> io::Result<()> {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+        if data == b"panic" {
+            panic!("failed to load state");
+        }
+
         Ok(())
     }
     fn processor(&mut self, _config: Config) -> Self::Processor {
         TestProcessor
     }
     fn has_editor(&self) -> bool {
-        false
+        true
     }
     fn editor_size(&self) -> Size {
         Size {
@@ -75,8 +107,13 @@ impl Plugin for TestPlugin {
             height: 0.0,
         }
     }
-    fn editor(&mut self, _host: EditorHost, _parent: &ParentWindow) -> Self::Editor {
-        TestEditor
+    fn editor(&mut self, host: EditorHost, _parent: &ParentWindow) -> Self::Editor {
+        TestEditor { host }
+    }
+    fn message(&mut self, message: Box<dyn Any + Send>) {
+        if let Ok(message) = message.downcast::<&'static str>() {
+            self.messages.push(*message);
+        }
     }
 
     #[allow(unused_variables)]
@@ -99,7 +136,10 @@ impl Processor for TestProcessor {
     fn process(&mut self, _buffers: Buffers, _events: Events) {}
 }
 
-struct TestEditor;
+// Sends a message from within `set_size`, and panics when resized to zero width.
+struct TestEditor {
+    host: EditorHost,
+}
 
 impl Editor for TestEditor {
     fn size(&self) -> Size {
@@ -109,6 +149,14 @@ impl Editor for TestEditor {
         }
     }
     fn param_changed(&mut self, _index: usize, _value: f64) {}
+    fn set_size(&mut self, size: Size) -> bool {
+        if size.width == 0.0 {
+            panic!("failed to resize editor");
+        }
+
+        self.host.send_message("resized");
+        true
+    }
 }
 
 unsafe fn str_from_ptr<'a>(ptr: *const c_char) -> Result<&'a str, std::str::Utf8Error> {
@@ -150,3 +198,103 @@ fn factory() {
 
     unsafe { factory.deinit() };
 }
+
+#[test]
+fn test_host() {
+    let mut host = ClapTestHost::<TestPlugin>::new();
+    assert_eq!(host.input_channels(), &[2]);
+    assert_eq!(host.output_channels(), &[2]);
+    assert_eq!(host.param_count(), 2);
+    assert_eq!(host.param_name(1), "Mode");
+
+    host.activate(44100.0, 64);
+
+    let input = vec![vec![vec![0.5; 64], vec![-0.5; 64]]];
+    let output = host.process(64, &input, &[]);
+    assert_eq!(output.outputs, input);
+    assert!(output.events.is_empty());
+
+    host.deactivate();
+
+    let state = host.save();
+    assert!(host.load(&state));
+}
+
+#[test]
+fn list_params() {
+    let host = ClapTestHost::<TestPlugin>::new();
+    assert!(!host.param_is_list(0));
+    assert!(host.param_is_list(1));
+}
+
+#[test]
+fn panic_containment() {
+    let mut host = ClapTestHost::<TestPlugin>::new();
+    assert!(!host.load(b"panic"));
+
+    host.activate(44100.0, 64);
+
+    // The plugin is marked as failed, so process outputs silence and reports an error.
+    let input = vec![vec![vec![0.5; 64], vec![-0.5; 64]]];
+    let output = host.try_process(64, &input, &[]).unwrap_err();
+    assert_eq!(output.outputs, vec![vec![vec![0.0; 64]; 2]]);
+}
+
+#[test]
+fn editor_message() {
+    let mut host = ClapTestHost::<TestPlugin>::new();
+    assert!(host.open_editor());
+
+    // The message is sent from within `set_size`, and delivered from the `on_main_thread`
+    // callback the plugin requests.
+    let size = Size {
+        width: 100.0,
+        height: 100.0,
+    };
+    assert!(host.resize_editor(size));
+    assert_eq!(host.save(), b"resized");
+
+    host.close_editor();
+}
+
+#[test]
+fn editor_panic_containment() {
+    let mut host = ClapTestHost::<TestPlugin>::new();
+    assert!(host.open_editor());
+
+    let size = Size {
+        width: 0.0,
+        height: 100.0,
+    };
+    assert!(!host.resize_editor(size));
+
+    host.activate(44100.0, 64);
+
+    // A panic in the editor marks the plugin as failed, just like one in the plugin itself.
+    let input = vec![vec![vec![0.5; 64], vec![-0.5; 64]]];
+    let output = host.try_process(64, &input, &[]).unwrap_err();
+    assert_eq!(output.outputs, vec![vec![vec![0.0; 64]; 2]]);
+}
+
+// Reports a byte count computed from the length of the buffer, without reading anything.
+struct BadCount(fn(usize) -> usize);
+
+impl io::Read for BadCount {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Ok(self.0(buf.len()))
+    }
+}
+
+#[test]
+fn misreported_read_counts() {
+    let mut host = ClapTestHost::<TestPlugin>::new();
+    assert!(!host.load_from(BadCount(|len| len + 1)));
+    assert!(!host.load_from(BadCount(|_| usize::MAX - 1)));
+
+    // The bad counts are treated as read errors rather than panics, so the plugin still works.
+    host.activate(44100.0, 64);
+
+    let input = vec![vec![vec![0.5; 64], vec![-0.5; 64]]];
+    let output = host.process(64, &input, &[]);
+    assert_eq!(output.outputs, input);
+}
@@ -0,0 +1,84 @@
+//! Calls which are queued and applied from the wrapper's next idle tick or main thread callback.
+//!
+//! Messages sent by the editor are always queued, since the editor is usually still running when it
+//! sends them. Hosts may also call back into the plugin while an `Editor` method is running, for
+//! example resizing the editor from within `request_resize` or performing a menu action from within
+//! `show_context_menu`. Those calls are queued as well, since the main thread state is still
+//! borrowed.
+
+use std::any::Any;
+use std::mem;
+
+use crate::editor::{Editor, Size};
+use crate::plugin::Plugin;
+use crate::sync::sync_cell::SyncCell;
+
+pub struct Deferred {
+    messages: Vec<Box<dyn Any + Send>>,
+    size: Option<Size>,
+    menu_actions: Vec<u32>,
+}
+
+impl Deferred {
+    pub fn new() -> Deferred {
+        Deferred {
+            messages: Vec::new(),
+            size: None,
+            menu_actions: Vec::new(),
+        }
+    }
+
+    pub fn send_message(&mut self, message: Box<dyn Any + Send>) {
+        self.messages.push(message);
+    }
+
+    /// Only the most recent size is kept.
+    pub fn set_size(&mut self, size: Size) {
+        self.size = Some(size);
+    }
+
+    pub fn context_menu_action(&mut self, id: u32) {
+        self.menu_actions.push(id);
+    }
+
+    /// Applies the calls recorded in `deferred`. Returns `true` if any messages were delivered to
+    /// the plugin, in which case the host should be told that the plugin's state has changed.
+    ///
+    /// Calls recorded while this is running, such as messages sent from `update_editor`, are left
+    /// for the next call.
+    pub fn apply<P: Plugin>(
+        deferred: &SyncCell<Deferred>,
+        plugin: &mut P,
+        mut editor: Option<&mut P::Editor>,
+    ) -> bool {
+        let Deferred {
+            messages,
+            size,
+            menu_actions,
+        } = mem::replace(&mut *deferred.borrow(), Deferred::new());
+
+        if let Some(editor) = &mut editor {
+            if let Some(size) = size {
+                editor.set_size(size);
+            }
+
+            for id in menu_actions {
+                editor.context_menu_action(id);
+            }
+        }
+
+        if messages.is_empty() {
+            return false;
+        }
+
+        for message in messages {
+            plugin.message(message);
+        }
+
+        if let Some(editor) = editor {
+            plugin.update_editor(editor);
+        }
+
+        true
+    }
+}
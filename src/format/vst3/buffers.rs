@@ -15,6 +15,25 @@ unsafe fn iter_slice_unaligned<T>(ptr: *const T, len: usize) -> impl Iterator<It
     (0..len).map(move |i| unsafe { ptr::read_unaligned(ptr.add(i)) })
 }
 
+/// Fills every output channel provided by the host with silence.
+pub unsafe fn clear_outputs(data: &ProcessData) {
+    let len = data.numSamples as usize;
+    let outputs = unsafe { slice_from_raw_parts_checked(data.outputs, data.numOutputs as usize) };
+    for output in outputs {
+        let channels = unsafe {
+            iter_slice_unaligned(
+                output.__field0.channelBuffers32,
+                output.numChannels as usize,
+            )
+        };
+        for channel in channels {
+            if !channel.is_null() {
+                unsafe { slice::from_raw_parts_mut(channel, len) }.fill(0.0);
+            }
+        }
+    }
+}
+
 pub struct ScratchBuffers {
     inputs_active: Vec<bool>,
     outputs_active: Vec<bool>,
@@ -2,23 +2,33 @@ use std::collections::{HashMap, HashSet};
 use std::ffi::{CStr, c_void};
 use std::ptr;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use vst3::{Class, ComPtr, ComRef, ComWrapper, Steinberg::Vst::*, Steinberg::*};
 
-use super::buffers::ScratchBuffers;
+use super::buffers::{ScratchBuffers, clear_outputs};
 use super::host::Vst3Host;
+#[cfg(target_os = "linux")]
+use super::run_loop::RunLoop;
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+use super::timer::IdleTimer;
 use super::util::{copy_wstring, utf16_from_ptr};
 use super::view::PlugView;
 use crate::bus::{BusDir, Layout};
 use crate::collect::{
     OwnedBusInfo, OwnedParamInfo, collect_bus_configs, collect_buses, collect_params,
 };
-use crate::editor::Editor;
+use crate::editor::{Editor, ParamMapping};
 use crate::events::{Data, Event, Events};
 use crate::host::Host;
+use crate::log::{self, Logger};
+use crate::panic;
+use crate::params::ParamFunction;
 use crate::plugin::Plugin;
 use crate::process::{Config, Processor};
+use crate::rt_check;
 use crate::sync::params::ParamValues;
+use crate::sync::thread_check::MainThreadCell;
 use crate::sync::{sync_cell::SyncCell, thread_cell::ThreadCell};
 use crate::util::{RequireSendSync, slice_from_raw_parts_checked};
 
@@ -45,6 +55,11 @@ pub struct MainThreadState<P: Plugin> {
     pub handler: Option<ComPtr<IComponentHandler>>,
     pub editor: Option<ThreadCell<P::Editor>>,
     pub frame: Option<ComPtr<IPlugFrame>>,
+    pub scale: f64,
+    #[cfg(target_os = "linux")]
+    pub run_loop: Option<RunLoop>,
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    pub idle_timer: Option<IdleTimer>,
 }
 
 struct ProcessState<P: Plugin> {
@@ -67,7 +82,10 @@ pub struct Component<P: Plugin> {
     processor_params: ParamValues,
     _host: Arc<Vst3Host>,
     has_editor: bool,
-    main_thread_state: Arc<SyncCell<MainThreadState<P>>>,
+    // Set once plugin code has panicked, after which the component only outputs silence.
+    failed: Arc<AtomicBool>,
+    logger: Arc<Logger>,
+    main_thread_state: Arc<MainThreadCell<MainThreadState<P>>>,
     // When the audio processor is *not* active, references to ProcessState may only be formed from
     // the main thread. When the audio processor *is* active, references to ProcessState may only
     // be formed from the audio thread.
@@ -130,7 +148,9 @@ impl<P: Plugin> Component<P> {
             processor_params: ParamValues::with_count(param_count),
             _host: host,
             has_editor,
-            main_thread_state: Arc::new(SyncCell::new(MainThreadState {
+            failed: Arc::new(AtomicBool::new(false)),
+            logger: Arc::new(Logger::new()),
+            main_thread_state: Arc::new(MainThreadCell::new(MainThreadState {
                 layouts,
                 sample_rate: 0.0,
                 max_buffer_size: 0,
@@ -138,6 +158,11 @@ impl<P: Plugin> Component<P> {
                 handler: None,
                 editor: None,
                 frame: None,
+                scale: 1.0,
+                #[cfg(target_os = "linux")]
+                run_loop: None,
+                #[cfg(any(target_os = "windows", target_os = "macos"))]
+                idle_timer: None,
             })),
             process_state: SyncCell::new(ProcessState {
                 layouts: Vec::new(),
@@ -149,6 +174,12 @@ impl<P: Plugin> Component<P> {
         }
     }
 
+    /// Runs `f`, marking the component as failed if it panics. Also writes out any log messages
+    /// queued from the audio thread.
+    fn guard<R>(&self, context: &'static str, f: impl FnOnce() -> R) -> Option<R> {
+        panic::guard(&self.failed, &self.logger, context, f)
+    }
+
     fn sync_plugin(&self, plugin: &mut P) {
         for (index, value) in self.plugin_params.poll() {
             plugin.set_param(index, value);
@@ -157,7 +188,7 @@ impl<P: Plugin> Component<P> {
 
     fn sync_processor(&self, processor: &mut P::Processor) {
         for (index, value) in self.processor_params.poll() {
-            processor.set_param(index, value);
+            rt_check::check("Processor::set_param", || processor.set_param(index, value));
         }
     }
 }
@@ -168,6 +199,8 @@ impl<P: Plugin> Class for Component<P> {
         IAudioProcessor,
         IProcessContextRequirements,
         IEditController,
+        IParameterHighlight,
+        IParameterFunctionName,
     );
 }
 
@@ -191,7 +224,7 @@ impl<P: Plugin> IComponentTrait for Component<P> {
     }
 
     unsafe fn getBusCount(&self, type_: MediaType, dir: BusDirection) -> int32 {
-        match type_ as MediaTypes {
+        self.guard("IComponent::getBusCount", || match type_ as MediaTypes {
             MediaTypes_::kAudio => match dir as BusDirections {
                 BusDirections_::kInput => self.input_bus_map.len() as int32,
                 BusDirections_::kOutput => self.output_bus_map.len() as int32,
@@ -199,7 +232,8 @@ impl<P: Plugin> IComponentTrait for Component<P> {
             },
             MediaTypes_::kEvent => 0,
             _ => 0,
-        }
+        })
+        .unwrap_or(0)
     }
 
     unsafe fn getBusInfo(
@@ -209,43 +243,46 @@ impl<P: Plugin> IComponentTrait for Component<P> {
         index: int32,
         bus: *mut vst3::Steinberg::Vst::BusInfo,
     ) -> tresult {
-        let main_thread_state = self.main_thread_state.borrow();
-
-        match type_ as MediaTypes {
-            MediaTypes_::kAudio => {
-                let bus_index = match dir as BusDirections {
-                    BusDirections_::kInput => self.input_bus_map.get(index as usize),
-                    BusDirections_::kOutput => self.output_bus_map.get(index as usize),
-                    _ => return kInvalidArgument,
-                };
+        self.guard("IComponent::getBusInfo", || {
+            let main_thread_state = self.main_thread_state.borrow();
+
+            match type_ as MediaTypes {
+                MediaTypes_::kAudio => {
+                    let bus_index = match dir as BusDirections {
+                        BusDirections_::kInput => self.input_bus_map.get(index as usize),
+                        BusDirections_::kOutput => self.output_bus_map.get(index as usize),
+                        _ => return kInvalidArgument,
+                    };
 
-                if let Some(&bus_index) = bus_index {
-                    let info = self.buses.get(bus_index);
-                    let layout = main_thread_state.layouts.get(bus_index);
-
-                    if let (Some(info), Some(layout)) = (info, layout) {
-                        let bus = unsafe { &mut *bus };
-
-                        bus.mediaType = type_;
-                        bus.direction = dir;
-                        bus.channelCount = layout.channel_count() as int32;
-                        copy_wstring(&info.name, &mut bus.name);
-                        bus.busType = if index == 0 {
-                            BusTypes_::kMain as BusType
-                        } else {
-                            BusTypes_::kAux as BusType
-                        };
-                        bus.flags = BusInfo_::BusFlags_::kDefaultActive as uint32;
-
-                        return kResultOk;
+                    if let Some(&bus_index) = bus_index {
+                        let info = self.buses.get(bus_index);
+                        let layout = main_thread_state.layouts.get(bus_index);
+
+                        if let (Some(info), Some(layout)) = (info, layout) {
+                            let bus = unsafe { &mut *bus };
+
+                            bus.mediaType = type_;
+                            bus.direction = dir;
+                            bus.channelCount = layout.channel_count() as int32;
+                            copy_wstring(&info.name, &mut bus.name);
+                            bus.busType = if index == 0 {
+                                BusTypes_::kMain as BusType
+                            } else {
+                                BusTypes_::kAux as BusType
+                            };
+                            bus.flags = BusInfo_::BusFlags_::kDefaultActive as uint32;
+
+                            return kResultOk;
+                        }
                     }
                 }
+                MediaTypes_::kEvent => {}
+                _ => {}
             }
-            MediaTypes_::kEvent => {}
-            _ => {}
-        }
 
-        kInvalidArgument
+            kInvalidArgument
+        })
+        .unwrap_or(kInternalError)
     }
 
     unsafe fn getRoutingInfo(
@@ -263,67 +300,77 @@ impl<P: Plugin> IComponentTrait for Component<P> {
         index: int32,
         state: TBool,
     ) -> tresult {
-        let mut process_state = self.process_state.borrow();
-
-        match type_ as MediaTypes {
-            MediaTypes_::kAudio => match dir as BusDirections {
-                BusDirections_::kInput => {
-                    if self.input_bus_map.get(index as usize).is_some() {
-                        process_state.scratch_buffers.set_input_active(index as usize, state != 0);
-                        return kResultOk;
+        self.guard("IComponent::activateBus", || {
+            let mut process_state = self.process_state.borrow();
+
+            match type_ as MediaTypes {
+                MediaTypes_::kAudio => match dir as BusDirections {
+                    BusDirections_::kInput => {
+                        if self.input_bus_map.get(index as usize).is_some() {
+                            process_state
+                                .scratch_buffers
+                                .set_input_active(index as usize, state != 0);
+                            return kResultOk;
+                        }
                     }
-                }
-                BusDirections_::kOutput => {
-                    if self.output_bus_map.get(index as usize).is_some() {
-                        process_state.scratch_buffers.set_output_active(index as usize, state != 0);
-                        return kResultOk;
+                    BusDirections_::kOutput => {
+                        if self.output_bus_map.get(index as usize).is_some() {
+                            process_state
+                                .scratch_buffers
+                                .set_output_active(index as usize, state != 0);
+                            return kResultOk;
+                        }
                     }
-                }
+                    _ => {}
+                },
+                MediaTypes_::kEvent => {}
                 _ => {}
-            },
-            MediaTypes_::kEvent => {}
-            _ => {}
-        }
+            }
 
-        kInvalidArgument
+            kInvalidArgument
+        })
+        .unwrap_or(kInternalError)
     }
 
     unsafe fn setActive(&self, state: TBool) -> tresult {
-        let mut main_thread_state_guard = self.main_thread_state.borrow();
-        let main_thread_state = &mut *main_thread_state_guard;
+        self.guard("IComponent::setActive", || {
+            let mut main_thread_state_guard = self.main_thread_state.borrow();
+            let main_thread_state = &mut *main_thread_state_guard;
 
-        let mut process_state_guard = self.process_state.borrow();
-        let process_state = &mut *process_state_guard;
-
-        if state == 0 {
-            // Apply any remaining processor -> plugin parameter changes. There won't be any more
-            // until the plugin becomes active again.
-            self.sync_plugin(&mut main_thread_state.plugin);
+            let mut process_state_guard = self.process_state.borrow();
+            let process_state = &mut *process_state_guard;
 
-            process_state.processor = None;
-        } else {
-            process_state.layouts = main_thread_state.layouts.clone();
-            process_state.max_buffer_size = main_thread_state.max_buffer_size;
-            process_state.scratch_buffers.resize(
-                &self.buses,
-                &main_thread_state.layouts,
-                main_thread_state.max_buffer_size,
-            );
+            if state == 0 {
+                // Apply any remaining processor -> plugin parameter changes. There won't be any
+                // more until the plugin becomes active again.
+                self.sync_plugin(&mut main_thread_state.plugin);
 
-            // Discard any pending plugin -> processor parameter changes, since they will already be
-            // reflected in the initial state of the processor.
-            for _ in self.processor_params.poll() {}
-
-            let config = Config {
-                layouts: &main_thread_state.layouts,
-                sample_rate: main_thread_state.sample_rate,
-                max_buffer_size: main_thread_state.max_buffer_size,
-            };
+                process_state.processor = None;
+            } else {
+                process_state.layouts = main_thread_state.layouts.clone();
+                process_state.max_buffer_size = main_thread_state.max_buffer_size;
+                process_state.scratch_buffers.resize(
+                    &self.buses,
+                    &main_thread_state.layouts,
+                    main_thread_state.max_buffer_size,
+                );
+
+                // Discard any pending plugin -> processor parameter changes, since they will
+                // already be reflected in the initial state of the processor.
+                for _ in self.processor_params.poll() {}
+
+                let config = Config {
+                    layouts: &main_thread_state.layouts,
+                    sample_rate: main_thread_state.sample_rate,
+                    max_buffer_size: main_thread_state.max_buffer_size,
+                };
 
-            process_state.processor = Some(main_thread_state.plugin.processor(config));
-        }
+                process_state.processor = Some(main_thread_state.plugin.processor(config));
+            }
 
-        kResultOk
+            kResultOk
+        })
+        .unwrap_or(kInternalError)
     }
 
     unsafe fn setState(&self, state: *mut IBStream) -> tresult {
@@ -334,38 +381,46 @@ impl<P: Plugin> IComponentTrait for Component<P> {
         impl<'a> Read for StreamReader<'a> {
             fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
                 let ptr = buf.as_mut_ptr() as *mut c_void;
-                let len = buf.len() as int32;
+                let len = buf.len().min(int32::MAX as usize) as int32;
                 let mut bytes: int32 = 0;
                 let result = unsafe { self.0.read(ptr, len, &mut bytes) };
 
-                if result == kResultOk {
-                    Ok(bytes as usize)
-                } else {
-                    Err(Error::other("failed to read from stream"))
+                // Only trust counts which fit in the buffer.
+                match usize::try_from(bytes) {
+                    Ok(count) if result == kResultOk && count <= buf.len() => Ok(count),
+                    _ => Err(Error::other("failed to read from stream")),
                 }
             }
         }
 
-        if let Some(state) = unsafe { ComRef::from_raw(state) } {
-            let mut main_thread_state = self.main_thread_state.borrow();
+        self.guard("IComponent::setState", || {
+            if let Some(state) = unsafe { ComRef::from_raw(state) } {
+                let mut main_thread_state = self.main_thread_state.borrow();
 
-            self.sync_plugin(&mut main_thread_state.plugin);
+                self.sync_plugin(&mut main_thread_state.plugin);
 
-            if main_thread_state.plugin.load(&mut StreamReader(state)).is_ok() {
-                for (index, _param) in self.params.iter().enumerate() {
-                    let value = main_thread_state.plugin.get_param(index);
-                    self.processor_params.set(index, value);
+                if main_thread_state.plugin.load(&mut StreamReader(state)).is_ok() {
+                    for (index, _param) in self.params.iter().enumerate() {
+                        let value = main_thread_state.plugin.get_param(index);
+                        self.processor_params.set(index, value);
+
+                        if let Some(editor) = &mut main_thread_state.editor {
+                            editor.param_changed(index, value);
+                        }
+                    }
 
+                    let main_thread_state = &mut *main_thread_state;
                     if let Some(editor) = &mut main_thread_state.editor {
-                        editor.param_changed(index, value);
+                        main_thread_state.plugin.update_editor(editor);
                     }
-                }
 
-                return kResultOk;
+                    return kResultOk;
+                }
             }
-        }
 
-        kResultFalse
+            kResultFalse
+        })
+        .unwrap_or(kInternalError)
     }
 
     unsafe fn getState(&self, state: *mut IBStream) -> tresult {
@@ -376,14 +431,14 @@ impl<P: Plugin> IComponentTrait for Component<P> {
         impl<'a> Write for StreamWriter<'a> {
             fn write(&mut self, buf: &[u8]) -> Result<usize> {
                 let ptr = buf.as_ptr() as *mut c_void;
-                let len = buf.len() as int32;
+                let len = buf.len().min(int32::MAX as usize) as int32;
                 let mut bytes: int32 = 0;
                 let result = unsafe { self.0.write(ptr, len, &mut bytes) };
 
-                if result == kResultOk {
-                    Ok(bytes as usize)
-                } else {
-                    Err(Error::other("failed to write to stream"))
+                // Only trust counts which fit in the buffer.
+                match usize::try_from(bytes) {
+                    Ok(count) if result == kResultOk && count <= buf.len() => Ok(count),
+                    _ => Err(Error::other("failed to write to stream")),
                 }
             }
 
@@ -392,17 +447,20 @@ impl<P: Plugin> IComponentTrait for Component<P> {
             }
         }
 
-        if let Some(state) = unsafe { ComRef::from_raw(state) } {
-            let mut main_thread_state = self.main_thread_state.borrow();
+        self.guard("IComponent::getState", || {
+            if let Some(state) = unsafe { ComRef::from_raw(state) } {
+                let mut main_thread_state = self.main_thread_state.borrow();
 
-            self.sync_plugin(&mut main_thread_state.plugin);
+                self.sync_plugin(&mut main_thread_state.plugin);
 
-            if main_thread_state.plugin.save(&mut StreamWriter(state)).is_ok() {
-                return kResultOk;
+                if main_thread_state.plugin.save(&mut StreamWriter(state)).is_ok() {
+                    return kResultOk;
+                }
             }
-        }
 
-        kResultFalse
+            kResultFalse
+        })
+        .unwrap_or(kInternalError)
     }
 }
 
@@ -414,44 +472,48 @@ impl<P: Plugin> IAudioProcessorTrait for Component<P> {
         outputs: *mut SpeakerArrangement,
         numOuts: int32,
     ) -> tresult {
-        let input_count = numIns as usize;
-        let output_count = numOuts as usize;
-        if input_count != self.input_bus_map.len() || output_count != self.output_bus_map.len() {
-            return kInvalidArgument;
-        }
-
-        let mut candidate = Vec::new();
+        self.guard("IAudioProcessor::setBusArrangements", || {
+            let input_count = numIns as usize;
+            let output_count = numOuts as usize;
+            if input_count != self.input_bus_map.len() || output_count != self.output_bus_map.len()
+            {
+                return kInvalidArgument;
+            }
 
-        let mut inputs = unsafe { slice_from_raw_parts_checked(inputs, input_count).iter() };
-        let mut outputs = unsafe { slice_from_raw_parts_checked(outputs, output_count).iter() };
-        for bus in &self.buses {
-            let arrangement = match bus.dir {
-                BusDir::In => *inputs.next().unwrap(),
-                BusDir::Out => *outputs.next().unwrap(),
-                BusDir::InOut => {
-                    let input_arrangement = *inputs.next().unwrap();
-                    let output_arrangement = *outputs.next().unwrap();
-                    if input_arrangement != output_arrangement {
-                        return kResultFalse;
+            let mut candidate = Vec::new();
+
+            let mut inputs = unsafe { slice_from_raw_parts_checked(inputs, input_count).iter() };
+            let mut outputs = unsafe { slice_from_raw_parts_checked(outputs, output_count).iter() };
+            for bus in &self.buses {
+                let arrangement = match bus.dir {
+                    BusDir::In => *inputs.next().unwrap(),
+                    BusDir::Out => *outputs.next().unwrap(),
+                    BusDir::InOut => {
+                        let input_arrangement = *inputs.next().unwrap();
+                        let output_arrangement = *outputs.next().unwrap();
+                        if input_arrangement != output_arrangement {
+                            return kResultFalse;
+                        }
+                        output_arrangement
                     }
-                    output_arrangement
-                }
-            };
+                };
 
-            if let Some(layout) = speaker_arrangement_to_layout(arrangement) {
-                candidate.push(layout);
-            } else {
-                return kResultFalse;
+                if let Some(layout) = speaker_arrangement_to_layout(arrangement) {
+                    candidate.push(layout);
+                } else {
+                    return kResultFalse;
+                }
             }
-        }
 
-        if self.bus_config_set.contains(&candidate) {
-            let mut main_thread_state = self.main_thread_state.borrow();
-            main_thread_state.layouts = candidate;
-            return kResultTrue;
-        }
+            if self.bus_config_set.contains(&candidate) {
+                let mut main_thread_state = self.main_thread_state.borrow();
+                main_thread_state.layouts = candidate;
+                return kResultTrue;
+            }
 
-        kResultFalse
+            kResultFalse
+        })
+        .unwrap_or(kInternalError)
     }
 
     unsafe fn getBusArrangement(
@@ -460,75 +522,125 @@ impl<P: Plugin> IAudioProcessorTrait for Component<P> {
         index: int32,
         arr: *mut SpeakerArrangement,
     ) -> tresult {
-        let main_thread_state = self.main_thread_state.borrow();
+        self.guard("IAudioProcessor::getBusArrangement", || {
+            let main_thread_state = self.main_thread_state.borrow();
 
-        let bus_index = match dir as BusDirections {
-            BusDirections_::kInput => self.input_bus_map.get(index as usize),
-            BusDirections_::kOutput => self.output_bus_map.get(index as usize),
-            _ => return kInvalidArgument,
-        };
+            let bus_index = match dir as BusDirections {
+                BusDirections_::kInput => self.input_bus_map.get(index as usize),
+                BusDirections_::kOutput => self.output_bus_map.get(index as usize),
+                _ => return kInvalidArgument,
+            };
 
-        if let Some(&bus_index) = bus_index {
-            #[allow(clippy::unnecessary_cast)] // The type of BusDirection varies by platform
-            if let Some(layout) = main_thread_state.layouts.get(bus_index as usize) {
-                let arr = unsafe { &mut *arr };
-                *arr = layout_to_speaker_arrangement(layout);
-                return kResultOk;
+            if let Some(&bus_index) = bus_index {
+                #[allow(clippy::unnecessary_cast)] // The type of BusDirection varies by platform
+                if let Some(layout) = main_thread_state.layouts.get(bus_index as usize) {
+                    let arr = unsafe { &mut *arr };
+                    *arr = layout_to_speaker_arrangement(layout);
+                    return kResultOk;
+                }
             }
-        }
 
-        kInvalidArgument
+            kInvalidArgument
+        })
+        .unwrap_or(kInternalError)
     }
 
     unsafe fn canProcessSampleSize(&self, symbolicSampleSize: int32) -> tresult {
-        match symbolicSampleSize as SymbolicSampleSizes {
-            SymbolicSampleSizes_::kSample32 => kResultTrue,
-            SymbolicSampleSizes_::kSample64 => kResultFalse,
-            _ => kInvalidArgument,
-        }
+        self.guard("IAudioProcessor::canProcessSampleSize", || {
+            match symbolicSampleSize as SymbolicSampleSizes {
+                SymbolicSampleSizes_::kSample32 => kResultTrue,
+                SymbolicSampleSizes_::kSample64 => kResultFalse,
+                _ => kInvalidArgument,
+            }
+        })
+        .unwrap_or(kInternalError)
     }
 
     unsafe fn getLatencySamples(&self) -> uint32 {
-        let mut main_thread_state = self.main_thread_state.borrow();
+        self.guard("IAudioProcessor::getLatencySamples", || {
+            let mut main_thread_state = self.main_thread_state.borrow();
 
-        self.sync_plugin(&mut main_thread_state.plugin);
+            self.sync_plugin(&mut main_thread_state.plugin);
 
-        let config = Config {
-            layouts: &main_thread_state.layouts,
-            sample_rate: main_thread_state.sample_rate,
-            max_buffer_size: main_thread_state.max_buffer_size,
-        };
+            let config = Config {
+                layouts: &main_thread_state.layouts,
+                sample_rate: main_thread_state.sample_rate,
+                max_buffer_size: main_thread_state.max_buffer_size,
+            };
 
-        main_thread_state.plugin.latency(config) as uint32
+            main_thread_state.plugin.latency(config) as uint32
+        })
+        .unwrap_or(0)
     }
 
     unsafe fn setupProcessing(&self, setup: *mut ProcessSetup) -> tresult {
-        let mut main_thread_state = self.main_thread_state.borrow();
+        self.guard("IAudioProcessor::setupProcessing", || {
+            let mut main_thread_state = self.main_thread_state.borrow();
 
-        let setup = unsafe { &*setup };
-        main_thread_state.sample_rate = setup.sampleRate;
-        main_thread_state.max_buffer_size = setup.maxSamplesPerBlock as usize;
+            let setup = unsafe { &*setup };
+            main_thread_state.sample_rate = setup.sampleRate;
+            main_thread_state.max_buffer_size = setup.maxSamplesPerBlock as usize;
 
-        kResultOk
+            kResultOk
+        })
+        .unwrap_or(kInternalError)
     }
 
     unsafe fn setProcessing(&self, state: TBool) -> tresult {
-        let mut process_state_guard = self.process_state.borrow();
-        let process_state = &mut *process_state_guard;
+        // Once the plugin has panicked, the processor is no longer called.
+        if self.failed.load(Ordering::Acquire) {
+            return kInternalError;
+        }
 
-        let Some(processor) = &mut process_state.processor else {
-            return kNotInitialized;
-        };
+        log::audio_thread(|| {
+            self.guard("IAudioProcessor::setProcessing", || {
+                let mut process_state_guard = self.process_state.borrow();
+                let process_state = &mut *process_state_guard;
 
-        if state == 0 {
-            self.sync_processor(processor);
-            processor.reset();
-        }
+                let Some(processor) = &mut process_state.processor else {
+                    return kNotInitialized;
+                };
 
-        kResultOk
+                if state == 0 {
+                    self.sync_processor(processor);
+                    rt_check::check("Processor::reset", || processor.reset());
+                }
+
+                kResultOk
+            })
+        })
+        .unwrap_or(kInternalError)
     }
 
     unsafe fn process(&self, data: *mut ProcessData) -> tresult {
+        if !self.failed.load(Ordering::Acquire) {
+            let result = log::audio_thread(|| {
+                self.guard("IAudioProcessor::process", || unsafe {
+                    self.process_inner(data)
+                })
+            });
+            if let Some(result) = result {
+                return result;
+            }
+        }
+
+        // Once the plugin has panicked, output silence.
+        unsafe { clear_outputs(&*data) };
+
+        kInternalError
+    }
+
+    unsafe fn getTailSamples(&self) -> uint32 {
+        kInfiniteTail
+    }
+}
+
+impl<P: Plugin> Component<P> {
+    unsafe fn process_inner(&self, data: *mut ProcessData) -> tresult {
+        self.main_thread_state
+            .thread_check()
+            .check_audio_thread("IAudioProcessor::process");
+
         let mut process_state_guard = self.process_state.borrow();
         let process_state = &mut *process_state_guard;
 
@@ -595,7 +707,7 @@ impl<P: Plugin> IAudioProcessorTrait for Component<P> {
             process_state.events.sort_unstable_by_key(|event| event.time);
 
             let events = Events::new(&process_state.events);
-            processor.process(buffers, events);
+            rt_check::check("Processor::process", || processor.process(buffers, events));
         } else {
             if let Some(param_changes) = unsafe { ComRef::from_raw(data.inputParameterChanges) } {
                 for index in 0..unsafe { param_changes.getParameterCount() } {
@@ -620,7 +732,9 @@ impl<P: Plugin> IAudioProcessorTrait for Component<P> {
                             continue;
                         }
 
-                        processor.set_param(param_index, value);
+                        rt_check::check("Processor::set_param", || {
+                            processor.set_param(param_index, value)
+                        });
 
                         self.plugin_params.set(param_index, value);
                     }
@@ -630,10 +744,6 @@ impl<P: Plugin> IAudioProcessorTrait for Component<P> {
 
         kResultOk
     }
-
-    unsafe fn getTailSamples(&self) -> uint32 {
-        kInfiniteTail
-    }
 }
 
 impl<P: Plugin> IProcessContextRequirementsTrait for Component<P> {
@@ -656,30 +766,39 @@ impl<P: Plugin> IEditControllerTrait for Component<P> {
     }
 
     unsafe fn getParameterCount(&self) -> int32 {
-        self.params.len() as int32
+        self.guard("IEditController::getParameterCount", || {
+            self.params.len() as int32
+        })
+        .unwrap_or(0)
     }
 
     unsafe fn getParameterInfo(&self, paramIndex: int32, info: *mut ParameterInfo) -> tresult {
-        if let Some(param) = self.params.get(paramIndex as usize) {
-            let info = unsafe { &mut *info };
-
-            info.id = self.param_ids[paramIndex as usize] as ParamID;
-            copy_wstring(&param.name, &mut info.title);
-            copy_wstring(&param.name, &mut info.shortTitle);
-            copy_wstring("", &mut info.units);
-            info.stepCount = if let Some(steps) = param.steps {
-                (steps.max(2) - 1) as int32
-            } else {
-                0
-            };
-            info.defaultNormalizedValue = param.default;
-            info.unitId = 0;
-            info.flags = ParameterInfo_::ParameterFlags_::kCanAutomate as int32;
+        self.guard("IEditController::getParameterInfo", || {
+            if let Some(param) = self.params.get(paramIndex as usize) {
+                let info = unsafe { &mut *info };
+
+                info.id = self.param_ids[paramIndex as usize] as ParamID;
+                copy_wstring(&param.name, &mut info.title);
+                copy_wstring(&param.name, &mut info.shortTitle);
+                copy_wstring("", &mut info.units);
+                info.stepCount = if let Some(steps) = param.steps {
+                    (steps.max(2) - 1) as int32
+                } else {
+                    0
+                };
+                info.defaultNormalizedValue = param.default;
+                info.unitId = 0;
+                info.flags = ParameterInfo_::ParameterFlags_::kCanAutomate as int32;
+                if param.value_names.is_some() {
+                    info.flags |= ParameterInfo_::ParameterFlags_::kIsList as int32;
+                }
 
-            return kResultOk;
-        }
+                return kResultOk;
+            }
 
-        kInvalidArgument
+            kInvalidArgument
+        })
+        .unwrap_or(kInternalError)
     }
 
     unsafe fn getParamStringByValue(
@@ -688,17 +807,20 @@ impl<P: Plugin> IEditControllerTrait for Component<P> {
         valueNormalized: ParamValue,
         string: *mut String128,
     ) -> tresult {
-        let main_thread_state = self.main_thread_state.borrow();
+        self.guard("IEditController::getParamStringByValue", || {
+            let main_thread_state = self.main_thread_state.borrow();
 
-        if let Some(&index) = self.param_map.get(&id) {
-            let mut text = String::new();
-            let _ = main_thread_state.plugin.display_param(index, valueNormalized, &mut text);
-            copy_wstring(&text, unsafe { &mut *string });
+            if let Some(&index) = self.param_map.get(&id) {
+                let mut text = String::new();
+                let _ = main_thread_state.plugin.display_param(index, valueNormalized, &mut text);
+                copy_wstring(&text, unsafe { &mut *string });
 
-            return kResultOk;
-        }
+                return kResultOk;
+            }
 
-        kInvalidArgument
+            kInvalidArgument
+        })
+        .unwrap_or(kInternalError)
     }
 
     unsafe fn getParamValueByString(
@@ -707,18 +829,21 @@ impl<P: Plugin> IEditControllerTrait for Component<P> {
         string: *mut TChar,
         valueNormalized: *mut ParamValue,
     ) -> tresult {
-        let main_thread_state = self.main_thread_state.borrow();
-
-        if let Some(&index) = self.param_map.get(&id) {
-            if let Ok(display) = String::from_utf16(unsafe { utf16_from_ptr(string) }) {
-                if let Some(value) = main_thread_state.plugin.parse_param(index, &display) {
-                    unsafe { *valueNormalized = value };
-                    return kResultOk;
-                }
+        self.guard("IEditController::getParamValueByString", || {
+            let main_thread_state = self.main_thread_state.borrow();
+
+            if let Some(&index) = self.param_map.get(&id)
+                && let Ok(display) = String::from_utf16(unsafe { utf16_from_ptr(string) })
+                && let Some(value) = main_thread_state.plugin.parse_param(index, &display)
+                && !value.is_nan()
+            {
+                unsafe { *valueNormalized = value.clamp(0.0, 1.0) };
+                return kResultOk;
             }
-        }
 
-        kInvalidArgument
+            kInvalidArgument
+        })
+        .unwrap_or(kInternalError)
     }
 
     unsafe fn normalizedParamToPlain(
@@ -734,53 +859,148 @@ impl<P: Plugin> IEditControllerTrait for Component<P> {
     }
 
     unsafe fn getParamNormalized(&self, id: ParamID) -> ParamValue {
-        let main_thread_state = self.main_thread_state.borrow();
+        self.guard("IEditController::getParamNormalized", || {
+            let main_thread_state = self.main_thread_state.borrow();
 
-        if let Some(&index) = self.param_map.get(&id) {
-            return main_thread_state.plugin.get_param(index);
-        }
+            if let Some(&index) = self.param_map.get(&id) {
+                return main_thread_state.plugin.get_param(index);
+            }
 
-        0.0
+            0.0
+        })
+        .unwrap_or(0.0)
     }
 
     unsafe fn setParamNormalized(&self, id: ParamID, value: ParamValue) -> tresult {
-        let mut main_thread_state = self.main_thread_state.borrow();
+        self.guard("IEditController::setParamNormalized", || {
+            let mut main_thread_state = self.main_thread_state.borrow();
 
-        if let Some(&index) = self.param_map.get(&id) {
-            main_thread_state.plugin.set_param(index, value);
+            if let Some(&index) = self.param_map.get(&id) {
+                main_thread_state.plugin.set_param(index, value);
 
-            if let Some(editor) = &mut main_thread_state.editor {
-                editor.param_changed(index, value);
-            }
+                if let Some(editor) = &mut main_thread_state.editor {
+                    editor.param_changed(index, value);
+                }
 
-            return kResultOk;
-        }
+                return kResultOk;
+            }
 
-        kInvalidArgument
+            kInvalidArgument
+        })
+        .unwrap_or(kInternalError)
     }
 
     unsafe fn setComponentHandler(&self, handler: *mut IComponentHandler) -> tresult {
-        let mut main_thread_state = self.main_thread_state.borrow();
+        self.guard("IEditController::setComponentHandler", || {
+            let mut main_thread_state = self.main_thread_state.borrow();
 
-        if let Some(handler) = unsafe { ComRef::from_raw(handler) } {
-            main_thread_state.handler = Some(handler.to_com_ptr());
-        } else {
-            main_thread_state.handler = None;
-        }
+            if let Some(handler) = unsafe { ComRef::from_raw(handler) } {
+                main_thread_state.handler = Some(handler.to_com_ptr());
+            } else {
+                main_thread_state.handler = None;
+            }
 
-        kResultOk
+            kResultOk
+        })
+        .unwrap_or(kInternalError)
     }
 
     unsafe fn createView(&self, name: FIDString) -> *mut IPlugView {
-        if !self.has_editor {
-            return ptr::null_mut();
-        }
+        self.guard("IEditController::createView", || {
+            if !self.has_editor {
+                return ptr::null_mut();
+            }
 
-        if unsafe { CStr::from_ptr(name) } != unsafe { CStr::from_ptr(ViewType::kEditor) } {
-            return ptr::null_mut();
-        }
+            if unsafe { CStr::from_ptr(name) } != unsafe { CStr::from_ptr(ViewType::kEditor) } {
+                return ptr::null_mut();
+            }
+
+            let view = ComWrapper::new(PlugView::new(
+                &self.param_ids,
+                &self.main_thread_state,
+                &self.failed,
+                &self.logger,
+            ));
+            let view_ptr = view.to_com_ptr::<IPlugView>().unwrap();
+            view.set_this(view_ptr.as_ptr());
+            view_ptr.into_raw()
+        })
+        .unwrap_or(ptr::null_mut())
+    }
+}
+
+impl<P: Plugin> IParameterHighlightTrait for Component<P> {
+    unsafe fn setParamHighlight(&self, id: ParamID, state: TBool) -> tresult {
+        self.guard("IParameterHighlight::setParamHighlight", || {
+            let mut main_thread_state = self.main_thread_state.borrow();
+
+            let Some(&index) = self.param_map.get(&id) else {
+                return kInvalidArgument;
+            };
+
+            // VST3 provides no details about the mapping, only whether the parameter is
+            // highlighted.
+            let mapping = if state != 0 {
+                Some(ParamMapping {
+                    color: None,
+                    label: None,
+                    description: None,
+                })
+            } else {
+                None
+            };
+
+            if let Some(editor) = &mut main_thread_state.editor {
+                editor.param_mapping_changed(index, mapping);
+            }
+
+            kResultOk
+        })
+        .unwrap_or(kInternalError)
+    }
+}
+
+impl<P: Plugin> IParameterFunctionNameTrait for Component<P> {
+    unsafe fn getParameterIDFromFunctionName(
+        &self,
+        _unit_id: UnitID,
+        function_name: FIDString,
+        param_id: *mut ParamID,
+    ) -> tresult {
+        self.guard(
+            "IParameterFunctionName::getParameterIDFromFunctionName",
+            || {
+                if function_name.is_null() || param_id.is_null() {
+                    return kInvalidArgument;
+                }
+
+                let function_name = unsafe { CStr::from_ptr(function_name) };
+                let function = if function_name
+                    == unsafe { CStr::from_ptr(FunctionNameType::kDryWetMix) }
+                {
+                    ParamFunction::DryWetMix
+                } else if function_name
+                    == unsafe { CStr::from_ptr(FunctionNameType::kLowLatencyMode) }
+                {
+                    ParamFunction::LowLatencyMode
+                } else if function_name == unsafe { CStr::from_ptr(FunctionNameType::kRandomize) } {
+                    ParamFunction::Randomize
+                } else {
+                    return kResultFalse;
+                };
 
-        let view = ComWrapper::new(PlugView::new(&self.param_ids, &self.main_thread_state));
-        view.to_com_ptr::<IPlugView>().unwrap().into_raw()
+                let main_thread_state = self.main_thread_state.borrow();
+
+                if let Some(index) = main_thread_state.plugin.function_param(function)
+                    && let Some(&id) = self.param_ids.get(index)
+                {
+                    unsafe { *param_id = id };
+                    return kResultOk;
+                }
+
+                kResultFalse
+            },
+        )
+        .unwrap_or(kInternalError)
     }
 }
@@ -6,6 +6,7 @@ use vst3::{Class, ComWrapper, Steinberg::Vst::*, Steinberg::*, uid};
 use super::component::Component;
 use super::util::copy_wstring;
 use super::{Uuid, Vst3Plugin, with_vst3_info};
+use crate::panic;
 use crate::plugin::{Plugin, with_info};
 use crate::util::{RequireSendSync, copy_cstring};
 
@@ -40,17 +41,20 @@ impl<P: Plugin> Class for Factory<P> {
 
 impl<P: Plugin> IPluginFactoryTrait for Factory<P> {
     unsafe fn getFactoryInfo(&self, info: *mut PFactoryInfo) -> tresult {
-        let info = unsafe { &mut *info };
+        panic::catch("IPluginFactory::getFactoryInfo", || {
+            let info = unsafe { &mut *info };
 
-        info.flags = PFactoryInfo_::FactoryFlags_::kUnicode as int32;
+            info.flags = PFactoryInfo_::FactoryFlags_::kUnicode as int32;
 
-        with_info::<P, _>(|plugin_info| {
-            copy_cstring(plugin_info.vendor, &mut info.vendor);
-            copy_cstring(plugin_info.url, &mut info.url);
-            copy_cstring(plugin_info.email, &mut info.email);
-        });
+            with_info::<P, _>(|plugin_info| {
+                copy_cstring(plugin_info.vendor, &mut info.vendor);
+                copy_cstring(plugin_info.url, &mut info.url);
+                copy_cstring(plugin_info.email, &mut info.email);
+            });
 
-        kResultOk
+            kResultOk
+        })
+        .unwrap_or(kInternalError)
     }
 
     unsafe fn countClasses(&self) -> int32 {
@@ -58,21 +62,24 @@ impl<P: Plugin> IPluginFactoryTrait for Factory<P> {
     }
 
     unsafe fn getClassInfo(&self, index: int32, info: *mut PClassInfo) -> tresult {
-        if index == 0 {
-            let info = unsafe { &mut *info };
+        panic::catch("IPluginFactory::getClassInfo", || {
+            if index == 0 {
+                let info = unsafe { &mut *info };
 
-            info.cid = uuid_to_tuid(&self.class_id);
-            info.cardinality = PClassInfo_::ClassCardinality_::kManyInstances as int32;
-            copy_cstring("Audio Module Class", &mut info.category);
+                info.cid = uuid_to_tuid(&self.class_id);
+                info.cardinality = PClassInfo_::ClassCardinality_::kManyInstances as int32;
+                copy_cstring("Audio Module Class", &mut info.category);
 
-            with_info::<P, _>(|plugin_info| {
-                copy_cstring(plugin_info.name, &mut info.name);
-            });
+                with_info::<P, _>(|plugin_info| {
+                    copy_cstring(plugin_info.name, &mut info.name);
+                });
 
-            return kResultOk;
-        }
+                return kResultOk;
+            }
 
-        kInvalidArgument
+            kInvalidArgument
+        })
+        .unwrap_or(kInternalError)
     }
 
     unsafe fn createInstance(
@@ -81,68 +88,77 @@ impl<P: Plugin> IPluginFactoryTrait for Factory<P> {
         iid: FIDString,
         obj: *mut *mut c_void,
     ) -> tresult {
-        let cid = unsafe { &*(cid as *const TUID) };
-        let class_id = uuid_to_tuid(&self.class_id);
-        if cid == &class_id {
-            let component = ComWrapper::new(Component::<P>::new());
-            let unknown = component.as_com_ref::<FUnknown>().unwrap();
-            let ptr = unknown.as_ptr();
-            return unsafe { ((*(*ptr).vtbl).queryInterface)(ptr, iid as *const TUID, obj) };
-        }
-
-        kInvalidArgument
+        panic::catch("IPluginFactory::createInstance", || {
+            let cid = unsafe { &*(cid as *const TUID) };
+            let class_id = uuid_to_tuid(&self.class_id);
+            if cid == &class_id {
+                let component = ComWrapper::new(Component::<P>::new());
+                let unknown = component.as_com_ref::<FUnknown>().unwrap();
+                let ptr = unknown.as_ptr();
+                return unsafe { ((*(*ptr).vtbl).queryInterface)(ptr, iid as *const TUID, obj) };
+            }
+
+            kInvalidArgument
+        })
+        .unwrap_or(kInternalError)
     }
 }
 
 impl<P: Plugin> IPluginFactory2Trait for Factory<P> {
     unsafe fn getClassInfo2(&self, index: int32, info: *mut PClassInfo2) -> tresult {
-        if index == 0 {
-            let info = unsafe { &mut *info };
-
-            info.cid = uuid_to_tuid(&self.class_id);
-            info.cardinality = PClassInfo_::ClassCardinality_::kManyInstances as int32;
-            copy_cstring("Audio Module Class", &mut info.category);
-            info.classFlags = 0;
-            copy_cstring("Fx", &mut info.subCategories);
-            let version_str = unsafe { CStr::from_ptr(SDKVersionString) }.to_str().unwrap();
-            copy_cstring(version_str, &mut info.sdkVersion);
-
-            with_info::<P, _>(|plugin_info| {
-                copy_cstring(plugin_info.name, &mut info.name);
-                copy_cstring(plugin_info.vendor, &mut info.vendor);
-                copy_cstring(plugin_info.version, &mut info.version);
-            });
-
-            return kResultOk;
-        }
-
-        kInvalidArgument
+        panic::catch("IPluginFactory2::getClassInfo2", || {
+            if index == 0 {
+                let info = unsafe { &mut *info };
+
+                info.cid = uuid_to_tuid(&self.class_id);
+                info.cardinality = PClassInfo_::ClassCardinality_::kManyInstances as int32;
+                copy_cstring("Audio Module Class", &mut info.category);
+                info.classFlags = 0;
+                copy_cstring("Fx", &mut info.subCategories);
+                let version_str = unsafe { CStr::from_ptr(SDKVersionString) }.to_str().unwrap();
+                copy_cstring(version_str, &mut info.sdkVersion);
+
+                with_info::<P, _>(|plugin_info| {
+                    copy_cstring(plugin_info.name, &mut info.name);
+                    copy_cstring(plugin_info.vendor, &mut info.vendor);
+                    copy_cstring(plugin_info.version, &mut info.version);
+                });
+
+                return kResultOk;
+            }
+
+            kInvalidArgument
+        })
+        .unwrap_or(kInternalError)
     }
 }
 
 impl<P: Plugin> IPluginFactory3Trait for Factory<P> {
     unsafe fn getClassInfoUnicode(&self, index: int32, info: *mut PClassInfoW) -> tresult {
-        if index == 0 {
-            let info = unsafe { &mut *info };
-
-            info.cid = uuid_to_tuid(&self.class_id);
-            info.cardinality = PClassInfo_::ClassCardinality_::kManyInstances as int32;
-            copy_cstring("Audio Module Class", &mut info.category);
-            info.classFlags = 0;
-            copy_cstring("Fx", &mut info.subCategories);
-            let version_str = unsafe { CStr::from_ptr(SDKVersionString) }.to_str().unwrap();
-            copy_wstring(version_str, &mut info.sdkVersion);
-
-            with_info::<P, _>(|plugin_info| {
-                copy_wstring(plugin_info.name, &mut info.name);
-                copy_wstring(plugin_info.vendor, &mut info.vendor);
-                copy_wstring(plugin_info.version, &mut info.version);
-            });
-
-            return kResultOk;
-        }
-
-        kInvalidArgument
+        panic::catch("IPluginFactory3::getClassInfoUnicode", || {
+            if index == 0 {
+                let info = unsafe { &mut *info };
+
+                info.cid = uuid_to_tuid(&self.class_id);
+                info.cardinality = PClassInfo_::ClassCardinality_::kManyInstances as int32;
+                copy_cstring("Audio Module Class", &mut info.category);
+                info.classFlags = 0;
+                copy_cstring("Fx", &mut info.subCategories);
+                let version_str = unsafe { CStr::from_ptr(SDKVersionString) }.to_str().unwrap();
+                copy_wstring(version_str, &mut info.sdkVersion);
+
+                with_info::<P, _>(|plugin_info| {
+                    copy_wstring(plugin_info.name, &mut info.name);
+                    copy_wstring(plugin_info.vendor, &mut info.vendor);
+                    copy_wstring(plugin_info.version, &mut info.version);
+                });
+
+                return kResultOk;
+            }
+
+            kInvalidArgument
+        })
+        .unwrap_or(kInternalError)
     }
 
     unsafe fn setHostContext(&self, _context: *mut FUnknown) -> tresult {
@@ -11,6 +11,12 @@ mod host;
 mod util;
 mod view;
 
+#[cfg(target_os = "linux")]
+mod run_loop;
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+mod timer;
+
 #[cfg(test)]
 mod tests;
 
@@ -117,7 +123,10 @@ macro_rules! vst3 {
 
         #[unsafe(no_mangle)]
         extern "system" fn GetPluginFactory() -> *mut ::std::ffi::c_void {
-            ::coupler::format::vst3::get_plugin_factory::<$plugin>()
+            ::coupler::panic::catch("GetPluginFactory", || {
+                ::coupler::format::vst3::get_plugin_factory::<$plugin>()
+            })
+            .unwrap_or(::std::ptr::null_mut())
         }
     };
 }
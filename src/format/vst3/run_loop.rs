@@ -0,0 +1,99 @@
+use std::sync::{Arc, Weak};
+
+use vst3::Steinberg::Linux::*;
+use vst3::Steinberg::*;
+use vst3::{Class, ComPtr, ComWrapper};
+
+use super::view::ViewContext;
+use crate::editor::IDLE_INTERVAL_MS;
+use crate::plugin::Plugin;
+use crate::util::RequireSendSync;
+
+struct IdleHandler<P: Plugin> {
+    context: Weak<ViewContext<P>>,
+}
+
+impl<P: Plugin> RequireSendSync for IdleHandler<P> {}
+
+impl<P: Plugin> IdleHandler<P> {
+    fn idle(&self, context: &'static str) {
+        if let Some(view_context) = self.context.upgrade() {
+            view_context.idle(context);
+        }
+    }
+}
+
+impl<P: Plugin> Class for IdleHandler<P> {
+    type Interfaces = (ITimerHandler, IEventHandler);
+}
+
+impl<P: Plugin> ITimerHandlerTrait for IdleHandler<P> {
+    unsafe fn onTimer(&self) {
+        self.idle("ITimerHandler::onTimer");
+    }
+}
+
+impl<P: Plugin> IEventHandlerTrait for IdleHandler<P> {
+    unsafe fn onFDIsSet(&self, _fd: FileDescriptor) {
+        self.idle("IEventHandler::onFDIsSet");
+    }
+}
+
+/// Timer and file descriptor registrations with the host's `IRunLoop`, which drive
+/// [`Editor::idle`](crate::editor::Editor::idle) on Linux.
+pub struct RunLoop {
+    run_loop: ComPtr<IRunLoop>,
+    timer_handler: Option<ComPtr<ITimerHandler>>,
+    event_handler: Option<ComPtr<IEventHandler>>,
+}
+
+impl RunLoop {
+    pub fn register<P: Plugin>(
+        frame: &ComPtr<IPlugFrame>,
+        context: &Arc<ViewContext<P>>,
+        poll_fd: Option<FileDescriptor>,
+    ) -> Option<RunLoop> {
+        let run_loop = frame.cast::<IRunLoop>()?;
+
+        let handler = ComWrapper::new(IdleHandler {
+            context: Arc::downgrade(context),
+        });
+
+        let mut timer_handler = handler.to_com_ptr::<ITimerHandler>();
+        if let Some(handler) = &timer_handler {
+            let result = unsafe { run_loop.registerTimer(handler.as_ptr(), IDLE_INTERVAL_MS as _) };
+            if result != kResultOk {
+                timer_handler = None;
+            }
+        }
+
+        let mut event_handler = None;
+        if let Some(fd) = poll_fd {
+            event_handler = handler.to_com_ptr::<IEventHandler>();
+            if let Some(handler) = &event_handler {
+                let result = unsafe { run_loop.registerEventHandler(handler.as_ptr(), fd) };
+                if result != kResultOk {
+                    event_handler = None;
+                }
+            }
+        }
+
+        Some(RunLoop {
+            run_loop,
+            timer_handler,
+            event_handler,
+        })
+    }
+}
+
+impl Drop for RunLoop {
+    fn drop(&mut self) {
+        if let Some(handler) = &self.timer_handler {
+            unsafe { self.run_loop.unregisterTimer(handler.as_ptr()) };
+        }
+
+        if let Some(handler) = &self.event_handler {
+            unsafe { self.run_loop.unregisterEventHandler(handler.as_ptr()) };
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use std::any::Any;
 use std::error::Error;
 use std::ffi::CStr;
 use std::{fmt, io, ptr, slice};
@@ -13,13 +14,14 @@ use vst3::{ComPtr, Interface, uid};
 
 use super::{BuildVst3Info, Uuid, Vst3Info, Vst3Plugin, get_plugin_factory};
 use crate::buffers::Buffers;
-use crate::bus::{BuildBusConfigs, BuildBuses};
+use crate::bus::{BuildBusConfigs, BuildBuses, BusConfig, BusDir, BusInfo, Layout};
 use crate::editor::{Editor, EditorHost, ParentWindow, Size};
 use crate::events::Events;
 use crate::host::Host;
-use crate::params::BuildParams;
+use crate::params::{BuildParams, ParamInfo};
 use crate::plugin::{BuildInfo, Plugin, PluginInfo};
 use crate::process::{Config, Processor};
+use crate::testing::Vst3TestHost;
 
 const NAME: &str = "test plugin";
 const VERSION: &str = "1.2.3";
@@ -28,7 +30,9 @@ const URL: &str = "https://example.com/";
 const EMAIL: &str = "example@example.com";
 const CLASS_ID: [u32; 4] = [0x11111111, 0x22222222, 0x33333333, 0x44444444];
 
-struct TestPlugin;
+struct TestPlugin {
+    messages: Vec<&'static str>,
+}
 
 impl Plugin for TestPlugin {
     type Processor = TestProcessor;
@@ -44,11 +48,33 @@ impl Plugin for TestPlugin {
         })
     }
     fn new(_host: Host) -> Self {
-        TestPlugin
+        TestPlugin {
+            messages: Vec::new(),
+        }
+    }
+    fn buses(&self, build: impl BuildBuses) {
+        build.bus(
+            "main",
+            BusInfo {
+                name: "Main",
+                dir: BusDir::InOut,
+            },
+        );
+    }
+    fn bus_configs(&self, build: impl BuildBusConfigs) {
+        build.config(
+            "stereo",
+            BusConfig {
+                layouts: &[Layout::Stereo],
+            },
+        );
+    }
+    fn params(&self, build: impl BuildParams) {
+        build.param("gain", ParamInfo::new("Gain", 1.0)).param(
+            "mode",
+            ParamInfo::new("Mode", 0.0).with_steps(3).with_value_names(&["A", "B", "C"]),
+        );
     }
-    fn buses(&self, _build: impl BuildBuses) {}
-    fn bus_configs(&self, _build: impl BuildBusConfigs) {}
-    fn params(&self, _build: impl BuildParams) {}
     fn set_param(&mut self, _index: usize, _value: f64) {}
     fn get_param(&self, _index: usize) -> f64 {
         0.0
@@ -64,17 +90,23 @@ impl Plugin for TestPlugin {
     ) -> Result<(), fmt::Error> {
         Ok(())
     }
-    fn save(&self, _output: impl io::Write) -> io::Result<()> {
-        Ok(())
+    fn save(&self, mut output: impl io::Write) -> io::Result<()> {
+        output.write_all(self.messages.join(",").as_bytes())
     }
-    fn load(&mut self, _input: impl io::Read) -> io::Result<()> {
+    fn load(&mut self, mut input: impl io::Read) -> io::Result<()> {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+        if data == b"panic" {
+            panic!("failed to load state");
+        }
+
         Ok(())
     }
     fn processor(&mut self, _config: Config) -> Self::Processor {
         TestProcessor
     }
     fn has_editor(&self) -> bool {
-        false
+        true
     }
     fn editor_size(&self) -> Size {
         Size {
@@ -82,8 +114,13 @@ impl Plugin for TestPlugin {
             height: 0.0,
         }
     }
-    fn editor(&mut self, _host: EditorHost, _parent: &ParentWindow) -> Self::Editor {
-        TestEditor
+    fn editor(&mut self, host: EditorHost, _parent: &ParentWindow) -> Self::Editor {
+        TestEditor { host }
+    }
+    fn message(&mut self, message: Box<dyn Any + Send>) {
+        if let Ok(message) = message.downcast::<&'static str>() {
+            self.messages.push(*message);
+        }
     }
 
     #[allow(unused_variables)]
@@ -108,7 +145,10 @@ impl Processor for TestProcessor {
     fn process(&mut self, _buffers: Buffers, _events: Events) {}
 }
 
-struct TestEditor;
+// Sends a message from within `set_size`, and panics when resized to zero width.
+struct TestEditor {
+    host: EditorHost,
+}
 
 impl Editor for TestEditor {
     fn size(&self) -> Size {
@@ -118,6 +158,14 @@ impl Editor for TestEditor {
         }
     }
     fn param_changed(&mut self, _index: usize, _value: f64) {}
+    fn set_size(&mut self, size: Size) -> bool {
+        if size.width == 0.0 {
+            panic!("failed to resize editor");
+        }
+
+        self.host.send_message("resized");
+        true
+    }
 }
 
 fn str_from_chars(chars: &[char8]) -> Result<&str, Box<dyn Error>> {
@@ -277,3 +325,107 @@ fn factory() {
 
     unsafe { ComPtr::from_raw(obj as *mut IComponent) }.unwrap();
 }
+
+#[test]
+fn test_host() {
+    let mut host = Vst3TestHost::<TestPlugin>::new();
+    assert_eq!(host.input_channels(), &[2]);
+    assert_eq!(host.output_channels(), &[2]);
+    assert_eq!(host.param_count(), 2);
+    assert_eq!(host.param_name(1), "Mode");
+
+    host.activate(44100.0, 64);
+
+    let input = vec![vec![vec![0.5; 64], vec![-0.5; 64]]];
+    let output = host.process(64, &input, &[]);
+    assert_eq!(output.outputs, input);
+    assert!(output.events.is_empty());
+
+    host.deactivate();
+
+    let state = host.save();
+    assert!(host.load(&state));
+}
+
+#[test]
+fn list_params() {
+    let host = Vst3TestHost::<TestPlugin>::new();
+    assert!(!host.param_is_list(0));
+    assert!(host.param_is_list(1));
+}
+
+#[test]
+fn panic_containment() {
+    let mut host = Vst3TestHost::<TestPlugin>::new();
+    assert!(!host.load(b"panic"));
+
+    host.activate(44100.0, 64);
+
+    // The plugin is marked as failed, so process outputs silence and reports an error.
+    let input = vec![vec![vec![0.5; 64], vec![-0.5; 64]]];
+    let output = host.try_process(64, &input, &[]).unwrap_err();
+    assert_eq!(output.outputs, vec![vec![vec![0.0; 64]; 2]]);
+}
+
+// Messages are delivered from the idle timer, which VST3 hosts only provide on Linux.
+#[cfg(target_os = "linux")]
+#[test]
+fn editor_message() {
+    let mut host = Vst3TestHost::<TestPlugin>::new();
+    assert!(host.open_editor());
+
+    // The message is sent from within `set_size`, and delivered from the next idle tick.
+    let size = Size {
+        width: 100.0,
+        height: 100.0,
+    };
+    assert!(host.resize_editor(size));
+    assert_eq!(host.save(), b"");
+
+    host.idle_editor();
+    assert_eq!(host.save(), b"resized");
+
+    host.close_editor();
+}
+
+#[test]
+fn editor_panic_containment() {
+    let mut host = Vst3TestHost::<TestPlugin>::new();
+    assert!(host.open_editor());
+
+    let size = Size {
+        width: 0.0,
+        height: 100.0,
+    };
+    assert!(!host.resize_editor(size));
+
+    host.activate(44100.0, 64);
+
+    // A panic in the editor marks the plugin as failed, just like one in the plugin itself.
+    let input = vec![vec![vec![0.5; 64], vec![-0.5; 64]]];
+    let output = host.try_process(64, &input, &[]).unwrap_err();
+    assert_eq!(output.outputs, vec![vec![vec![0.0; 64]; 2]]);
+}
+
+// Reports a byte count computed from the length of the buffer, without reading anything.
+struct BadCount(fn(usize) -> usize);
+
+impl io::Read for BadCount {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Ok(self.0(buf.len()))
+    }
+}
+
+#[test]
+fn misreported_read_counts() {
+    let mut host = Vst3TestHost::<TestPlugin>::new();
+    assert!(!host.load_from(BadCount(|len| len + 1)));
+    assert!(!host.load_from(BadCount(|_| usize::MAX - 1)));
+
+    // The bad counts are treated as read errors rather than panics, so the plugin still works.
+    host.activate(44100.0, 64);
+
+    let input = vec![vec![vec![0.5; 64], vec![-0.5; 64]]];
+    let output = host.process(64, &input, &[]);
+    assert_eq!(output.outputs, input);
+}
@@ -0,0 +1,166 @@
+//! A main-thread timer which drives [`Editor::idle`](crate::editor::Editor::idle) on Windows and
+//! macOS, where VST3 hosts don't provide one.
+
+use std::rc::Rc;
+
+type Callback = Rc<dyn Fn()>;
+
+/// Calls a closure periodically on the main thread until dropped. Must be created and dropped on
+/// the main thread.
+pub struct IdleTimer {
+    #[cfg(target_os = "windows")]
+    id: usize,
+    #[cfg(target_os = "macos")]
+    timer: *mut std::ffi::c_void,
+    #[cfg(target_os = "macos")]
+    callback: *mut Callback,
+}
+
+// The timer is only created, fired and dropped on the main thread, but it is stored in the
+// component's main thread state, which must be `Send`.
+unsafe impl Send for IdleTimer {}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::ffi::c_void;
+    use std::ptr;
+    use std::rc::Rc;
+
+    use super::{Callback, IdleTimer};
+
+    type TimerProc = unsafe extern "system" fn(*mut c_void, u32, usize, u32);
+
+    #[link(name = "user32")]
+    unsafe extern "system" {
+        fn SetTimer(hwnd: *mut c_void, id: usize, elapse: u32, func: Option<TimerProc>) -> usize;
+        fn KillTimer(hwnd: *mut c_void, id: usize) -> i32;
+    }
+
+    thread_local! {
+        // Timers without a window are identified only by the ID returned from `SetTimer`.
+        static CALLBACKS: RefCell<HashMap<usize, Callback>> = RefCell::new(HashMap::new());
+    }
+
+    unsafe extern "system" fn timer_proc(_hwnd: *mut c_void, _msg: u32, id: usize, _time: u32) {
+        // Clone the callback so that it can drop its own timer.
+        let callback = CALLBACKS.with(|callbacks| callbacks.borrow().get(&id).cloned());
+        if let Some(callback) = callback {
+            callback();
+        }
+    }
+
+    impl IdleTimer {
+        pub fn new(interval_ms: u32, callback: impl Fn() + 'static) -> Option<IdleTimer> {
+            let id = unsafe { SetTimer(ptr::null_mut(), 0, interval_ms, Some(timer_proc)) };
+            if id == 0 {
+                return None;
+            }
+
+            CALLBACKS.with(|callbacks| callbacks.borrow_mut().insert(id, Rc::new(callback)));
+
+            Some(IdleTimer { id })
+        }
+    }
+
+    impl Drop for IdleTimer {
+        fn drop(&mut self) {
+            unsafe { KillTimer(ptr::null_mut(), self.id) };
+
+            let callback = CALLBACKS.with(|callbacks| callbacks.borrow_mut().remove(&self.id));
+            drop(callback);
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::c_void;
+    use std::ptr;
+    use std::rc::Rc;
+
+    use super::{Callback, IdleTimer};
+
+    type CFRunLoopTimerCallBack = extern "C" fn(timer: *mut c_void, info: *mut c_void);
+
+    #[repr(C)]
+    struct CFRunLoopTimerContext {
+        version: isize,
+        info: *mut c_void,
+        retain: Option<extern "C" fn(*const c_void) -> *const c_void>,
+        release: Option<extern "C" fn(*const c_void)>,
+        copy_description: Option<extern "C" fn(*const c_void) -> *const c_void>,
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    unsafe extern "C" {
+        static kCFRunLoopCommonModes: *const c_void;
+
+        fn CFAbsoluteTimeGetCurrent() -> f64;
+        fn CFRunLoopGetMain() -> *mut c_void;
+        fn CFRunLoopTimerCreate(
+            allocator: *const c_void,
+            fire_date: f64,
+            interval: f64,
+            flags: usize,
+            order: isize,
+            callout: CFRunLoopTimerCallBack,
+            context: *mut CFRunLoopTimerContext,
+        ) -> *mut c_void;
+        fn CFRunLoopAddTimer(run_loop: *mut c_void, timer: *mut c_void, mode: *const c_void);
+        fn CFRunLoopTimerInvalidate(timer: *mut c_void);
+        fn CFRelease(cf: *const c_void);
+    }
+
+    extern "C" fn fire(_timer: *mut c_void, info: *mut c_void) {
+        // Clone the callback so that it can drop its own timer.
+        let callback = unsafe { &*(info as *const Callback) }.clone();
+        callback();
+    }
+
+    impl IdleTimer {
+        pub fn new(interval_ms: u32, callback: impl Fn() + 'static) -> Option<IdleTimer> {
+            let callback: *mut Callback = Box::into_raw(Box::new(Rc::new(callback)));
+
+            let mut context = CFRunLoopTimerContext {
+                version: 0,
+                info: callback as *mut c_void,
+                retain: None,
+                release: None,
+                copy_description: None,
+            };
+
+            let interval = interval_ms as f64 / 1000.0;
+            let timer = unsafe {
+                CFRunLoopTimerCreate(
+                    ptr::null(),
+                    CFAbsoluteTimeGetCurrent() + interval,
+                    interval,
+                    0,
+                    0,
+                    fire,
+                    &mut context,
+                )
+            };
+            if timer.is_null() {
+                drop(unsafe { Box::from_raw(callback) });
+                return None;
+            }
+
+            unsafe { CFRunLoopAddTimer(CFRunLoopGetMain(), timer, kCFRunLoopCommonModes) };
+
+            Some(IdleTimer { timer, callback })
+        }
+    }
+
+    impl Drop for IdleTimer {
+        fn drop(&mut self) {
+            unsafe {
+                CFRunLoopTimerInvalidate(self.timer);
+                CFRelease(self.timer);
+                drop(Box::from_raw(self.callback));
+            }
+        }
+    }
+}
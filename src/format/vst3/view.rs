@@ -1,22 +1,90 @@
+use std::any::Any;
 use std::ffi::{CStr, c_void};
+use std::ptr;
 use std::rc::Rc;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use std::sync::{Arc, Weak};
 
-use vst3::Steinberg::Vst::{IComponentHandler, IComponentHandlerTrait};
-use vst3::{Class, ComPtr, ComRef, Steinberg::*};
+use vst3::Steinberg::Vst::{
+    IComponentHandler, IComponentHandler2, IComponentHandler2Trait, IComponentHandler3,
+    IComponentHandler3Trait, IComponentHandlerTrait, IContextMenuItem, IContextMenuTarget,
+    IContextMenuTargetTrait, IContextMenuTrait, ParamID,
+};
+use vst3::{Class, ComPtr, ComRef, ComWrapper, Steinberg::*};
 
 use super::component::MainThreadState;
-use crate::editor::{Editor, EditorHost, EditorHostInner, ParentWindow, RawParent};
+#[cfg(target_os = "linux")]
+use super::run_loop::RunLoop;
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+use super::timer::IdleTimer;
+use super::util::copy_wstring;
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+use crate::editor::IDLE_INTERVAL_MS;
+use crate::editor::{
+    Editor, EditorHost, EditorHostInner, KeyCode, KeyEvent, MenuItem, Modifiers, ParentWindow,
+    RawParent, Size,
+};
+use crate::format::deferred::Deferred;
+use crate::log::Logger;
+use crate::panic;
 use crate::plugin::Plugin;
+use crate::sync::thread_check::MainThreadCell;
 use crate::sync::{sync_cell::SyncCell, thread_cell::ThreadCell};
 use crate::util::RequireSendSync;
 
-struct Vst3EditorHost {
+/// State shared between a view and the objects it hands out to the editor and the host, which
+/// hold it weakly so that they don't keep the view's state alive.
+pub struct ViewContext<P: Plugin> {
+    pub main_thread_state: Arc<MainThreadCell<MainThreadState<P>>>,
+    pub deferred: SyncCell<Deferred>,
+    failed: Arc<AtomicBool>,
+    logger: Arc<Logger>,
+}
+
+impl<P: Plugin> ViewContext<P> {
+    /// Runs `f`, marking the component as failed if it panics.
+    pub fn guard<R>(&self, context: &'static str, f: impl FnOnce() -> R) -> Option<R> {
+        panic::guard(&self.failed, &self.logger, context, f)
+    }
+
+    /// Applies deferred calls and calls [`Editor::idle`]. Does nothing if the main thread state is
+    /// borrowed, since hosts may run their event loop from within a call made by the editor.
+    pub fn idle(&self, context: &'static str) {
+        self.guard(context, || {
+            let Ok(mut main_thread_state) = self.main_thread_state.try_borrow() else {
+                return;
+            };
+
+            let main_thread_state = &mut *main_thread_state;
+            let delivered = Deferred::apply(
+                &self.deferred,
+                &mut main_thread_state.plugin,
+                main_thread_state.editor.as_deref_mut(),
+            );
+
+            if delivered
+                && let Some(handler) = &main_thread_state.handler
+                && let Some(handler) = handler.cast::<IComponentHandler2>()
+            {
+                unsafe { handler.setDirty(1) };
+            }
+
+            if let Some(editor) = &mut main_thread_state.editor {
+                editor.idle();
+            }
+        });
+    }
+}
+
+struct Vst3EditorHost<P: Plugin> {
     handler: Option<ComPtr<IComponentHandler>>,
+    frame: Option<ComPtr<IPlugFrame>>,
+    view: *mut IPlugView,
     param_ids: Arc<Vec<u32>>,
+    context: Weak<ViewContext<P>>,
 }
 
-impl EditorHostInner for Vst3EditorHost {
+impl<P: Plugin> EditorHostInner for Vst3EditorHost<P> {
     fn begin_gesture(&self, index: usize) {
         if let Some(handler) = &self.handler {
             unsafe {
@@ -40,11 +108,193 @@ impl EditorHostInner for Vst3EditorHost {
             }
         }
     }
+
+    fn request_resize(&self, size: Size) -> bool {
+        if let Some(frame) = &self.frame {
+            let mut rect = ViewRect {
+                left: 0,
+                top: 0,
+                right: size.width.round() as int32,
+                bottom: size.height.round() as int32,
+            };
+
+            return unsafe { frame.resizeView(self.view, &mut rect) } == kResultOk;
+        }
+
+        false
+    }
+
+    fn send_message(&self, message: Box<dyn Any + Send>) {
+        let Some(context) = self.context.upgrade() else {
+            return;
+        };
+
+        // The editor is usually still running when it sends a message, so the message is delivered
+        // from the next idle tick rather than from here.
+        context.deferred.borrow().send_message(message);
+    }
+
+    fn show_context_menu(&self, param: Option<usize>, x: f64, y: f64, items: &[MenuItem]) -> bool {
+        let Some(handler) = &self.handler else {
+            return false;
+        };
+        let Some(handler) = handler.cast::<IComponentHandler3>() else {
+            return false;
+        };
+
+        let param_id = match param {
+            Some(index) => match self.param_ids.get(index) {
+                Some(&id) => Some(id),
+                None => return false,
+            },
+            None => None,
+        };
+        let param_id_ptr = param_id.as_ref().map_or(ptr::null(), |id| id as *const ParamID);
+
+        let menu = unsafe { handler.createContextMenu(self.view, param_id_ptr) };
+        let Some(menu) = (unsafe { ComPtr::from_raw(menu) }) else {
+            return false;
+        };
+
+        let target = ComWrapper::new(ContextMenuTarget {
+            context: self.context.clone(),
+        });
+        let Some(target) = target.to_com_ptr::<IContextMenuTarget>() else {
+            return false;
+        };
+
+        for item in items {
+            // Values from IContextMenuItem::Flags in ivstcontextmenu.h.
+            const IS_SEPARATOR: int32 = 1 << 0;
+            const IS_DISABLED: int32 = 1 << 1;
+            const IS_CHECKED: int32 = 1 << 2;
+            const IS_GROUP_START: int32 = 1 << 3 | IS_DISABLED;
+            const IS_GROUP_END: int32 = 1 << 4 | IS_SEPARATOR;
+
+            let (label, tag, flags) = match item {
+                MenuItem::Entry {
+                    label,
+                    id,
+                    enabled,
+                    checked,
+                } => {
+                    let mut flags = 0;
+                    if !enabled {
+                        flags |= IS_DISABLED;
+                    }
+                    if *checked {
+                        flags |= IS_CHECKED;
+                    }
+                    (label.as_str(), *id as int32, flags)
+                }
+                MenuItem::Separator => ("", 0, IS_SEPARATOR),
+                MenuItem::BeginSubmenu { label } => (label.as_str(), 0, IS_GROUP_START),
+                MenuItem::EndSubmenu => ("", 0, IS_GROUP_END),
+            };
+
+            let mut menu_item = IContextMenuItem {
+                name: [0; 128],
+                tag,
+                flags,
+            };
+            copy_wstring(label, &mut menu_item.name);
+
+            unsafe { menu.addItem(&menu_item, target.as_ptr()) };
+        }
+
+        unsafe { menu.popup(x.round() as UCoord, y.round() as UCoord) == kResultOk }
+    }
+}
+
+struct ContextMenuTarget<P: Plugin> {
+    context: Weak<ViewContext<P>>,
+}
+
+impl<P: Plugin> RequireSendSync for ContextMenuTarget<P> {}
+
+impl<P: Plugin> Class for ContextMenuTarget<P> {
+    type Interfaces = (IContextMenuTarget,);
+}
+
+impl<P: Plugin> IContextMenuTargetTrait for ContextMenuTarget<P> {
+    unsafe fn executeMenuItem(&self, tag: int32) -> tresult {
+        let Some(context) = self.context.upgrade() else {
+            return kResultFalse;
+        };
+
+        context
+            .guard("IContextMenuTarget::executeMenuItem", || {
+                // Hosts may invoke menu items from within the call to `popup`, in which case the
+                // main thread state is still borrowed by an editor callback. Apply the action from
+                // the next idle tick instead.
+                let Ok(mut main_thread_state) = context.main_thread_state.try_borrow() else {
+                    context.deferred.borrow().context_menu_action(tag as u32);
+                    return kResultOk;
+                };
+
+                if let Some(editor) = &mut main_thread_state.editor {
+                    editor.context_menu_action(tag as u32);
+                    return kResultOk;
+                }
+
+                kResultFalse
+            })
+            .unwrap_or(kInternalError)
+    }
+}
+
+fn key_event(key: char16, key_code: int16, modifiers: int16) -> KeyEvent {
+    // Values from VirtualKeyCodes in keycodes.h.
+    let code = match key_code {
+        1 => KeyCode::Backspace,
+        2 => KeyCode::Tab,
+        4 | 19 => KeyCode::Enter,
+        6 => KeyCode::Escape,
+        7 => KeyCode::Space,
+        8 => KeyCode::PageDown,
+        9 => KeyCode::End,
+        10 => KeyCode::Home,
+        11 => KeyCode::Left,
+        12 => KeyCode::Up,
+        13 => KeyCode::Right,
+        14 => KeyCode::Down,
+        15 => KeyCode::PageUp,
+        16 => KeyCode::PageDown,
+        21 => KeyCode::Insert,
+        22 => KeyCode::Delete,
+        _ => KeyCode::Other,
+    };
+
+    let char = if key != 0 {
+        char::decode_utf16([key as u16]).next().and_then(Result::ok)
+    } else {
+        None
+    };
+
+    // Values from KeyModifier in keycodes.h.
+    let modifiers = Modifiers {
+        shift: modifiers & (1 << 0) != 0,
+        alt: modifiers & (1 << 1) != 0,
+        command: modifiers & (1 << 2) != 0,
+        control: modifiers & (1 << 3) != 0,
+    };
+
+    KeyEvent {
+        char,
+        code,
+        modifiers,
+    }
+}
+
+fn handled(handled: bool) -> tresult {
+    if handled { kResultTrue } else { kResultFalse }
 }
 
 pub struct PlugView<P: Plugin> {
+    // Non-owning pointer to this view's own IPlugView interface, passed to IPlugFrame::resizeView.
+    this: AtomicPtr<IPlugView>,
     param_ids: Arc<Vec<u32>>,
-    main_thread_state: Arc<SyncCell<MainThreadState<P>>>,
+    context: Arc<ViewContext<P>>,
 }
 
 impl<P: Plugin> RequireSendSync for PlugView<P> {}
@@ -52,17 +302,29 @@ impl<P: Plugin> RequireSendSync for PlugView<P> {}
 impl<P: Plugin> PlugView<P> {
     pub fn new(
         param_ids: &Arc<Vec<u32>>,
-        main_thread_state: &Arc<SyncCell<MainThreadState<P>>>,
+        main_thread_state: &Arc<MainThreadCell<MainThreadState<P>>>,
+        failed: &Arc<AtomicBool>,
+        logger: &Arc<Logger>,
     ) -> PlugView<P> {
         PlugView {
+            this: AtomicPtr::new(ptr::null_mut()),
             param_ids: param_ids.clone(),
-            main_thread_state: main_thread_state.clone(),
+            context: Arc::new(ViewContext {
+                main_thread_state: main_thread_state.clone(),
+                deferred: SyncCell::new(Deferred::new()),
+                failed: failed.clone(),
+                logger: logger.clone(),
+            }),
         }
     }
+
+    pub fn set_this(&self, this: *mut IPlugView) {
+        self.this.store(this, Ordering::Relaxed);
+    }
 }
 
 impl<P: Plugin> Class for PlugView<P> {
-    type Interfaces = (IPlugView,);
+    type Interfaces = (IPlugView, IPlugViewContentScaleSupport);
 }
 
 impl<P: Plugin> IPlugViewTrait for PlugView<P> {
@@ -88,95 +350,280 @@ impl<P: Plugin> IPlugViewTrait for PlugView<P> {
     }
 
     unsafe fn attached(&self, parent: *mut c_void, type_: FIDString) -> tresult {
-        if unsafe { self.isPlatformTypeSupported(type_) } != kResultTrue {
-            return kResultFalse;
-        }
-
-        #[cfg(target_os = "windows")]
-        let raw_parent = RawParent::Win32(parent);
-
-        #[cfg(target_os = "macos")]
-        let raw_parent = RawParent::Cocoa(parent);
+        self.context
+            .guard("IPlugView::attached", || {
+                if unsafe { self.isPlatformTypeSupported(type_) } != kResultTrue {
+                    return kResultFalse;
+                }
+
+                #[cfg(target_os = "windows")]
+                let raw_parent = RawParent::Win32(parent);
+
+                #[cfg(target_os = "macos")]
+                let raw_parent = RawParent::Cocoa(parent);
+
+                #[cfg(target_os = "linux")]
+                let raw_parent = RawParent::X11(parent as std::ffi::c_ulong);
+
+                let mut main_thread_state = self.context.main_thread_state.borrow();
+
+                let host = EditorHost::from_inner(Rc::new(Vst3EditorHost {
+                    handler: main_thread_state.handler.clone(),
+                    frame: main_thread_state.frame.clone(),
+                    view: self.this.load(Ordering::Relaxed),
+                    param_ids: self.param_ids.clone(),
+                    context: Arc::downgrade(&self.context),
+                }));
+                let parent = unsafe { ParentWindow::from_raw(raw_parent) }
+                    .with_scale(main_thread_state.scale);
+                let editor = main_thread_state.plugin.editor(host, &parent);
+                main_thread_state.editor = Some(ThreadCell::new(editor));
+
+                #[cfg(target_os = "linux")]
+                {
+                    let poll_fd =
+                        main_thread_state.editor.as_ref().and_then(|editor| editor.poll_fd());
+                    let run_loop = main_thread_state
+                        .frame
+                        .as_ref()
+                        .and_then(|frame| RunLoop::register(frame, &self.context, poll_fd));
+                    main_thread_state.run_loop = run_loop;
+                }
+
+                #[cfg(any(target_os = "windows", target_os = "macos"))]
+                {
+                    let context = Arc::downgrade(&self.context);
+                    main_thread_state.idle_timer = IdleTimer::new(IDLE_INTERVAL_MS, move || {
+                        if let Some(context) = context.upgrade() {
+                            context.idle("IdleTimer::fire");
+                        }
+                    });
+                }
+
+                kResultOk
+            })
+            .unwrap_or(kInternalError)
+    }
 
-        #[cfg(target_os = "linux")]
-        let raw_parent = RawParent::X11(parent as std::ffi::c_ulong);
+    unsafe fn removed(&self) -> tresult {
+        self.context
+            .guard("IPlugView::removed", || {
+                let mut main_thread_state = self.context.main_thread_state.borrow();
+
+                #[cfg(target_os = "linux")]
+                {
+                    main_thread_state.run_loop = None;
+                }
+                #[cfg(any(target_os = "windows", target_os = "macos"))]
+                {
+                    main_thread_state.idle_timer = None;
+                }
+                main_thread_state.editor = None;
+
+                kResultOk
+            })
+            .unwrap_or(kInternalError)
+    }
 
-        let mut main_thread_state = self.main_thread_state.borrow();
+    unsafe fn onWheel(&self, distance: f32) -> tresult {
+        self.context
+            .guard("IPlugView::onWheel", || {
+                let mut main_thread_state = self.context.main_thread_state.borrow();
 
-        let host = EditorHost::from_inner(Rc::new(Vst3EditorHost {
-            handler: main_thread_state.handler.clone(),
-            param_ids: self.param_ids.clone(),
-        }));
-        let parent = unsafe { ParentWindow::from_raw(raw_parent) };
-        let editor = main_thread_state.plugin.editor(host, &parent);
-        main_thread_state.editor = Some(ThreadCell::new(editor));
+                if let Some(editor) = &mut main_thread_state.editor {
+                    return handled(editor.wheel(distance as f64));
+                }
 
-        kResultOk
+                kResultFalse
+            })
+            .unwrap_or(kInternalError)
     }
 
-    unsafe fn removed(&self) -> tresult {
-        let mut main_thread_state = self.main_thread_state.borrow();
+    unsafe fn onKeyDown(&self, key: char16, keyCode: int16, modifiers: int16) -> tresult {
+        self.context
+            .guard("IPlugView::onKeyDown", || {
+                let mut main_thread_state = self.context.main_thread_state.borrow();
 
-        main_thread_state.editor = None;
+                if let Some(editor) = &mut main_thread_state.editor {
+                    return handled(editor.key_down(key_event(key, keyCode, modifiers)));
+                }
 
-        kResultOk
+                kResultFalse
+            })
+            .unwrap_or(kInternalError)
     }
 
-    unsafe fn onWheel(&self, _distance: f32) -> tresult {
-        kResultFalse
-    }
+    unsafe fn onKeyUp(&self, key: char16, keyCode: int16, modifiers: int16) -> tresult {
+        self.context
+            .guard("IPlugView::onKeyUp", || {
+                let mut main_thread_state = self.context.main_thread_state.borrow();
 
-    unsafe fn onKeyDown(&self, _key: char16, _keyCode: int16, _modifiers: int16) -> tresult {
-        kResultFalse
-    }
+                if let Some(editor) = &mut main_thread_state.editor {
+                    return handled(editor.key_up(key_event(key, keyCode, modifiers)));
+                }
 
-    unsafe fn onKeyUp(&self, _key: char16, _keyCode: int16, _modifiers: int16) -> tresult {
-        kResultFalse
+                kResultFalse
+            })
+            .unwrap_or(kInternalError)
     }
 
     unsafe fn getSize(&self, size: *mut ViewRect) -> tresult {
-        if size.is_null() {
-            return kResultFalse;
-        }
-
-        let main_thread_state = self.main_thread_state.borrow();
-
-        let editor_size = if let Some(editor) = &main_thread_state.editor {
-            editor.size()
-        } else {
-            main_thread_state.plugin.editor_size()
-        };
-
-        let rect = unsafe { &mut *size };
-        rect.left = 0;
-        rect.top = 0;
-        rect.right = editor_size.width.round() as int32;
-        rect.bottom = editor_size.height.round() as int32;
-
-        kResultOk
+        self.context
+            .guard("IPlugView::getSize", || {
+                if size.is_null() {
+                    return kResultFalse;
+                }
+
+                let Ok(main_thread_state) = self.context.main_thread_state.try_borrow() else {
+                    return kResultFalse;
+                };
+
+                let editor_size = if let Some(editor) = &main_thread_state.editor {
+                    editor.size()
+                } else {
+                    main_thread_state.plugin.editor_size()
+                };
+
+                let rect = unsafe { &mut *size };
+                rect.left = 0;
+                rect.top = 0;
+                rect.right = editor_size.width.round() as int32;
+                rect.bottom = editor_size.height.round() as int32;
+
+                kResultOk
+            })
+            .unwrap_or(kInternalError)
     }
 
-    unsafe fn onSize(&self, _newSize: *mut ViewRect) -> tresult {
-        kNotImplemented
+    unsafe fn onSize(&self, newSize: *mut ViewRect) -> tresult {
+        self.context
+            .guard("IPlugView::onSize", || {
+                if newSize.is_null() {
+                    return kInvalidArgument;
+                }
+
+                let rect = unsafe { &*newSize };
+                let size = Size {
+                    width: (rect.right - rect.left) as f64,
+                    height: (rect.bottom - rect.top) as f64,
+                };
+
+                // The host may resize the editor from within `request_resize`, while the editor is
+                // still running. Apply the size from the next idle tick in that case.
+                let Ok(mut main_thread_state) = self.context.main_thread_state.try_borrow() else {
+                    self.context.deferred.borrow().set_size(size);
+                    return kResultOk;
+                };
+
+                let Some(editor) = &mut main_thread_state.editor else {
+                    return kResultFalse;
+                };
+
+                if editor.set_size(size) {
+                    kResultOk
+                } else {
+                    kResultFalse
+                }
+            })
+            .unwrap_or(kInternalError)
     }
 
-    unsafe fn onFocus(&self, _state: TBool) -> tresult {
-        kResultFalse
+    unsafe fn onFocus(&self, state: TBool) -> tresult {
+        self.context
+            .guard("IPlugView::onFocus", || {
+                let mut main_thread_state = self.context.main_thread_state.borrow();
+
+                if let Some(editor) = &mut main_thread_state.editor {
+                    return handled(editor.focus_changed(state != 0));
+                }
+
+                kResultFalse
+            })
+            .unwrap_or(kInternalError)
     }
 
     unsafe fn setFrame(&self, frame: *mut IPlugFrame) -> tresult {
-        let mut main_thread_state = self.main_thread_state.borrow();
-        main_thread_state.frame =
-            unsafe { ComRef::from_raw(frame) }.map(|frame| frame.to_com_ptr());
-
-        kResultOk
+        self.context
+            .guard("IPlugView::setFrame", || {
+                let mut main_thread_state = self.context.main_thread_state.borrow();
+                main_thread_state.frame =
+                    unsafe { ComRef::from_raw(frame) }.map(|frame| frame.to_com_ptr());
+
+                kResultOk
+            })
+            .unwrap_or(kInternalError)
     }
 
     unsafe fn canResize(&self) -> tresult {
-        kNotImplemented
+        self.context
+            .guard("IPlugView::canResize", || {
+                let Ok(main_thread_state) = self.context.main_thread_state.try_borrow() else {
+                    return kResultFalse;
+                };
+
+                if let Some(editor) = &main_thread_state.editor
+                    && editor.resize_hints().is_some()
+                {
+                    return kResultTrue;
+                }
+
+                kResultFalse
+            })
+            .unwrap_or(kInternalError)
     }
 
-    unsafe fn checkSizeConstraint(&self, _rect: *mut ViewRect) -> tresult {
-        kNotImplemented
+    unsafe fn checkSizeConstraint(&self, rect: *mut ViewRect) -> tresult {
+        self.context
+            .guard("IPlugView::checkSizeConstraint", || {
+                if rect.is_null() {
+                    return kInvalidArgument;
+                }
+
+                let Ok(main_thread_state) = self.context.main_thread_state.try_borrow() else {
+                    return kResultFalse;
+                };
+
+                let Some(editor) = &main_thread_state.editor else {
+                    return kResultFalse;
+                };
+
+                let Some(resize_hints) = editor.resize_hints() else {
+                    return kResultFalse;
+                };
+
+                let rect = unsafe { &mut *rect };
+                let size = resize_hints.constrain(Size {
+                    width: (rect.right - rect.left) as f64,
+                    height: (rect.bottom - rect.top) as f64,
+                });
+                rect.right = rect.left + size.width.round() as int32;
+                rect.bottom = rect.top + size.height.round() as int32;
+
+                kResultTrue
+            })
+            .unwrap_or(kInternalError)
+    }
+}
+
+impl<P: Plugin> IPlugViewContentScaleSupportTrait for PlugView<P> {
+    unsafe fn setContentScaleFactor(
+        &self,
+        factor: IPlugViewContentScaleSupport_::ScaleFactor,
+    ) -> tresult {
+        self.context
+            .guard(
+                "IPlugViewContentScaleSupport::setContentScaleFactor",
+                || {
+                    let mut main_thread_state = self.context.main_thread_state.borrow();
+
+                    main_thread_state.scale = factor as f64;
+                    if let Some(editor) = &mut main_thread_state.editor {
+                        editor.set_scale(factor as f64);
+                    }
+
+                    kResultOk
+                },
+            )
+            .unwrap_or(kInternalError)
     }
 }
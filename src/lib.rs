@@ -9,10 +9,17 @@ pub mod events;
 pub mod format;
 pub mod host;
 pub mod key;
+pub mod log;
+pub mod panic;
 pub mod params;
 pub mod plugin;
 pub mod process;
+pub mod rt_check;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
 mod collect;
 mod sync;
 mod util;
+
+pub use sync::channel;
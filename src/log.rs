@@ -0,0 +1,309 @@
+//! Logging from any thread, routed to the host where possible.
+//!
+//! Messages logged while the host is calling into a plugin instance are passed to the host's log,
+//! if the instance is running under a CLAP host which supports the log extension. Otherwise, and
+//! for messages logged from the plugin's own threads, they are appended to the file named by the
+//! `COUPLER_LOG_FILE` environment variable, or written to stderr if it is not set.
+//!
+//! Logging from inside a call to the [`Processor`](crate::process::Processor) never blocks or
+//! allocates. Such messages are truncated to [`MAX_AUDIO_MESSAGE_LEN`] bytes and queued by the
+//! instance, and are written out the next time the host calls into it from another thread.
+//!
+//! ```ignore
+//! coupler::log!(Level::Info, "loaded preset {}", name);
+//! ```
+
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::fmt::{self, Display, Write as _};
+use std::fs::{File, OpenOptions};
+use std::io::Write as _;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::{env, ptr, str};
+
+use crate::sync::channel::{Receiver, Sender, channel};
+
+pub const LOG_FILE_VAR: &str = "COUPLER_LOG_FILE";
+
+/// Messages logged from the audio thread are truncated to this many bytes.
+pub const MAX_AUDIO_MESSAGE_LEN: usize = 256;
+
+const QUEUE_CAPACITY: usize = 256;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Level {
+    Error,
+    Warning,
+    Info,
+    Debug,
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Info => "info",
+            Level::Debug => "debug",
+        })
+    }
+}
+
+/// Logs a formatted message at the given [`Level`].
+#[macro_export]
+macro_rules! log {
+    ($level:expr, $($arg:tt)+) => {
+        ::coupler::log::log($level, format_args!($($arg)+))
+    };
+}
+
+thread_local! {
+    static AUDIO_THREAD: Cell<bool> = const { Cell::new(false) };
+    // The logger of the instance which the host is currently calling into on this thread.
+    static CURRENT: Cell<*const Logger> = const { Cell::new(ptr::null()) };
+}
+
+#[derive(Copy, Clone)]
+struct Record {
+    level: Level,
+    len: usize,
+    bytes: [u8; MAX_AUDIO_MESSAGE_LEN],
+}
+
+impl Record {
+    fn message(&self) -> &str {
+        // Truncation always happens at a character boundary.
+        str::from_utf8(&self.bytes[..self.len]).unwrap_or_default()
+    }
+}
+
+impl fmt::Write for Record {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut end = s.len().min(MAX_AUDIO_MESSAGE_LEN - self.len);
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        self.bytes[self.len..self.len + end].copy_from_slice(&s.as_bytes()[..end]);
+        self.len += end;
+
+        Ok(())
+    }
+}
+
+type Sink = Box<dyn Fn(Level, &str) + Send + Sync>;
+
+/// The log of a single plugin instance.
+///
+/// Each instance has its own queue for messages from the audio thread, since hosts only make one
+/// audio thread call into an instance at a time.
+pub(crate) struct Logger {
+    sender: Sender<Record>,
+    receiver: Receiver<Record>,
+    pending: AtomicBool,
+    dropped: AtomicUsize,
+    sink: OnceLock<Sink>,
+}
+
+impl Logger {
+    pub fn new() -> Logger {
+        let (sender, receiver) = channel(QUEUE_CAPACITY);
+
+        Logger {
+            sender,
+            receiver,
+            pending: AtomicBool::new(false),
+            dropped: AtomicUsize::new(0),
+            sink: OnceLock::new(),
+        }
+    }
+
+    /// Routes this instance's messages to `sink` rather than to the log file or stderr.
+    pub fn set_sink(&self, sink: impl Fn(Level, &str) + Send + Sync + 'static) {
+        let _ = self.sink.set(Box::new(sink));
+    }
+
+    /// Runs `f` with messages logged on the current thread being routed to this logger.
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        struct Restore(*const Logger);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                let _ = CURRENT.try_with(|current| current.set(self.0));
+            }
+        }
+
+        let _restore = Restore(CURRENT.with(|current| current.replace(self)));
+        f()
+    }
+
+    /// Returns true if there are queued messages waiting for a call to [`flush`].
+    pub fn pending(&self) -> bool {
+        self.pending.load(Ordering::Acquire)
+    }
+
+    fn queue(&self, level: Level, args: fmt::Arguments) {
+        let mut record = Record {
+            level,
+            len: 0,
+            bytes: [0; MAX_AUDIO_MESSAGE_LEN],
+        };
+        let _ = record.write_fmt(args);
+
+        if self.sender.send(record).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        self.pending.store(true, Ordering::Release);
+    }
+
+    fn flush(&self) {
+        if !self.pending.swap(false, Ordering::Acquire) {
+            return;
+        }
+
+        for record in self.receiver.drain() {
+            self.write(record.level, record.message());
+        }
+
+        let dropped = self.dropped.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            let message = format!("{dropped} messages from the audio thread were dropped");
+            self.write(Level::Warning, &message);
+        }
+    }
+
+    fn write(&self, level: Level, message: &str) {
+        if let Some(sink) = self.sink.get() {
+            sink(level, message);
+        } else {
+            write(level, message);
+        }
+    }
+}
+
+fn current<R>(f: impl FnOnce(Option<&Logger>) -> R) -> R {
+    // The pointer is only set while `Logger::scope` is running, so the logger is still alive.
+    let current = CURRENT.try_with(|current| current.get()).unwrap_or(ptr::null());
+    f(unsafe { current.as_ref() })
+}
+
+/// Logs a message. Usually called through the [`log!`](crate::log!) macro.
+pub fn log(level: Level, args: fmt::Arguments) {
+    current(|logger| {
+        if AUDIO_THREAD.with(|audio_thread| audio_thread.get()) {
+            if let Some(logger) = logger {
+                logger.queue(level, args);
+            }
+            return;
+        }
+
+        let message = args.as_str().map_or_else(|| args.to_string().into(), Cow::Borrowed);
+        if let Some(logger) = logger {
+            logger.flush();
+            logger.write(level, &message);
+        } else {
+            write(level, &message);
+        }
+    })
+}
+
+fn write(level: Level, message: &str) {
+    static FILE: OnceLock<Option<Mutex<File>>> = OnceLock::new();
+
+    let file = FILE.get_or_init(|| {
+        let path = env::var_os(LOG_FILE_VAR)?;
+        let file = OpenOptions::new().create(true).append(true).open(path).ok()?;
+        Some(Mutex::new(file))
+    });
+
+    if let Some(file) = file
+        && let Ok(mut file) = file.lock()
+    {
+        let _ = writeln!(file, "[{level}] {message}");
+        return;
+    }
+
+    eprintln!("[{level}] {message}");
+}
+
+/// Runs `f` with messages logged on the current thread being queued rather than written.
+pub(crate) fn audio_thread<R>(f: impl FnOnce() -> R) -> R {
+    struct Restore(bool);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let _ = AUDIO_THREAD.try_with(|audio_thread| audio_thread.set(self.0));
+        }
+    }
+
+    let _restore = Restore(AUDIO_THREAD.with(|audio_thread| audio_thread.replace(true)));
+    f()
+}
+
+/// Writes any messages queued from the audio thread by the current logger. Does nothing when
+/// called from inside [`audio_thread`].
+pub(crate) fn flush() {
+    if AUDIO_THREAD.with(|audio_thread| audio_thread.get()) {
+        return;
+    }
+
+    current(|logger| {
+        if let Some(logger) = logger {
+            logger.flush();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    type Messages = Arc<Mutex<Vec<(Level, String)>>>;
+
+    fn collecting_logger() -> (Logger, Messages) {
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let messages_clone = messages.clone();
+
+        let logger = Logger::new();
+        logger.set_sink(move |level, message| {
+            messages_clone.lock().unwrap().push((level, message.to_string()));
+        });
+
+        (logger, messages)
+    }
+
+    #[test]
+    fn route_and_queue() {
+        let (logger, messages) = collecting_logger();
+        let (other, other_messages) = collecting_logger();
+
+        let long = "é".repeat(MAX_AUDIO_MESSAGE_LEN);
+        logger.scope(|| {
+            crate::log!(Level::Info, "main {}", 1);
+
+            audio_thread(|| {
+                crate::log!(Level::Warning, "audio {}", 2);
+                other.scope(|| crate::log!(Level::Info, "other"));
+                crate::log!(Level::Debug, "x{long}");
+            });
+
+            assert_eq!(messages.lock().unwrap().len(), 1);
+            flush();
+        });
+        other.scope(flush);
+
+        let messages = messages.lock().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0], (Level::Info, "main 1".to_string()));
+        assert_eq!(messages[1], (Level::Warning, "audio 2".to_string()));
+        assert_eq!(messages[2].1.len(), MAX_AUDIO_MESSAGE_LEN - 1);
+
+        assert_eq!(
+            *other_messages.lock().unwrap(),
+            [(Level::Info, "other".to_string())]
+        );
+    }
+}
@@ -0,0 +1,110 @@
+//! Containment of panics at the boundary between the plugin and the host.
+//!
+//! Panics in plugin code are caught before they can unwind into the host. A plugin instance which
+//! has panicked is marked as failed, after which it outputs silence and reports an error from
+//! every call to process.
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::log::{self, Level, Logger};
+
+/// Information about a panic which was caught at the plugin boundary.
+#[derive(Debug)]
+pub struct PanicReport<'a> {
+    /// The plugin API entry point which panicked, e.g. `IComponent::setState`.
+    pub context: &'static str,
+    pub message: &'a str,
+}
+
+type Hook = Box<dyn Fn(&PanicReport) + Send + Sync>;
+
+static HOOK: RwLock<Option<Hook>> = RwLock::new(None);
+
+/// Sets a function to be called whenever a panic is caught at the plugin boundary, for example to
+/// send a crash report. The hook is called on the thread which panicked.
+pub fn set_hook(hook: impl Fn(&PanicReport) + Send + Sync + 'static) {
+    if let Ok(mut current) = HOOK.write() {
+        *current = Some(Box::new(hook));
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+/// Runs `f`, returning `None` if it panics.
+#[doc(hidden)]
+pub fn catch<R>(context: &'static str, f: impl FnOnce() -> R) -> Option<R> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => Some(result),
+        Err(payload) => {
+            let report = PanicReport {
+                context,
+                message: panic_message(&*payload),
+            };
+
+            log::log(
+                Level::Error,
+                format_args!("panic in {}: {}", report.context, report.message),
+            );
+
+            if let Ok(hook) = HOOK.read()
+                && let Some(hook) = &*hook
+            {
+                let _ = panic::catch_unwind(AssertUnwindSafe(|| hook(&report)));
+            }
+
+            None
+        }
+    }
+}
+
+/// Runs `f` at a plugin API entry point, routing log messages to `logger` and setting `failed` if
+/// it panics. Also writes out any log messages queued from the audio thread.
+pub(crate) fn guard<R>(
+    failed: &AtomicBool,
+    logger: &Logger,
+    context: &'static str,
+    f: impl FnOnce() -> R,
+) -> Option<R> {
+    logger.scope(|| {
+        log::flush();
+
+        let result = catch(context, f);
+        if result.is_none() {
+            failed.store(true, Ordering::Release);
+        }
+        result
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Mutex;
+
+    #[test]
+    fn catch_and_report() {
+        static REPORTS: Mutex<Vec<(&'static str, String)>> = Mutex::new(Vec::new());
+
+        set_hook(|report| {
+            REPORTS.lock().unwrap().push((report.context, report.message.to_string()));
+        });
+
+        assert_eq!(catch("ok", || 1), Some(1));
+        assert_eq!(catch("fail", || -> i32 { panic!("oops {}", 1) }), None);
+
+        let reports = REPORTS.lock().unwrap();
+        assert_eq!(*reports, [("fail", "oops 1".to_string())]);
+    }
+}
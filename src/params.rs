@@ -1,5 +1,5 @@
 use std::fmt::{self, Display};
-use std::str::FromStr;
+use std::str::{self, FromStr};
 
 use crate::key::Key;
 
@@ -9,13 +9,58 @@ pub use coupler_derive::{Enum, Params};
 mod format;
 mod range;
 
-pub use format::{DefaultFormat, Format};
-pub use range::{DefaultRange, Encode, Log, Range};
+pub use format::{
+    Cents, Decibels, DefaultFormat, Format, Hertz, NoteName, OnOff, Pan, Percent, Seconds,
+    Semitones,
+};
+pub use range::{Db, DefaultRange, Encode, Log, Pow, Range, Skew, Stepped};
 
+/// Describes a parameter to the host.
+///
+/// `ParamInfo` is constructed with [`ParamInfo::new`], so that fields can be added without
+/// breaking existing plugins.
+#[non_exhaustive]
 pub struct ParamInfo<'a> {
     pub name: &'a str,
     pub default: f64,
     pub steps: Option<u32>,
+    pub value_names: Option<&'a [&'a str]>,
+}
+
+impl<'a> ParamInfo<'a> {
+    /// A continuous parameter with the given name and normalized default value.
+    pub fn new(name: &'a str, default: f64) -> ParamInfo<'a> {
+        ParamInfo {
+            name,
+            default,
+            steps: None,
+            value_names: None,
+        }
+    }
+
+    /// Makes the parameter discrete, with `steps` evenly spaced values across its normalized range.
+    pub fn with_steps(self, steps: u32) -> ParamInfo<'a> {
+        ParamInfo {
+            steps: Some(steps),
+            ..self
+        }
+    }
+
+    /// Names for each step, in order, for hosts that can present the parameter as a list.
+    pub fn with_value_names(self, value_names: &'a [&'a str]) -> ParamInfo<'a> {
+        ParamInfo {
+            value_names: Some(value_names),
+            ..self
+        }
+    }
+}
+
+/// A well-known role which a parameter can serve, allowing hosts to expose it in a generic way.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ParamFunction {
+    DryWetMix,
+    LowLatencyMode,
+    Randomize,
 }
 
 pub trait BuildParams {
@@ -23,8 +68,63 @@ pub trait BuildParams {
     fn reserve<'k>(self, key: impl Into<Key<'k>>) -> Self;
 }
 
+/// The number of parameters on a [`RemoteControlsPage`].
+pub const REMOTE_CONTROLS_COUNT: usize = 8;
+
+/// A page of parameters to be assigned to the knobs of a hardware control surface.
+///
+/// Parameters are referred to by the string of the key they were declared with. Slots which are
+/// `None` are left unassigned, as are slots which don't refer to a parameter, for which a warning
+/// is logged.
+pub struct RemoteControlsPage<'a> {
+    pub name: &'a str,
+    pub section: &'a str,
+    pub params: [Option<Key<'a>>; REMOTE_CONTROLS_COUNT],
+}
+
+pub trait BuildRemoteControls {
+    fn page<'k>(self, key: impl Into<Key<'k>>, page: RemoteControlsPage) -> Self;
+    fn reserve<'k>(self, key: impl Into<Key<'k>>) -> Self;
+}
+
+/// A set of parameters which can be registered with a [`BuildParams`] and addressed by index.
+///
+/// `#[derive(Params)]` implements this for a struct with `#[param]` fields. If any `#[param]` field
+/// doesn't declare a `default`, the struct must implement `Default`, which is used for the missing
+/// defaults, and in debug builds `params` checks that any declared defaults agree with it. If every
+/// field declares one, adding `#[params(default)]` to the struct also derives `Default` from the
+/// declared values.
 pub trait Params {
+    /// The number of parameters passed to the builder by [`params`](Params::params), which is
+    /// used to lay out the indices of nested parameter structs. Hand-written implementations must
+    /// keep this in sync with `params`.
+    ///
+    /// Adding this constant was a breaking change for hand-written implementations, which must now
+    /// define it. It has no default, since a wrong count would silently shift the indices of any
+    /// parameters following a nested struct.
+    const PARAM_COUNT: usize;
+
     fn params(&self, build: impl BuildParams);
+
+    /// Sets every parameter to the default reported by [`params`](Params::params).
+    ///
+    /// The default implementation doesn't allocate, but calls `params` once per parameter. The
+    /// derived implementation sets each parameter directly.
+    fn reset_to_defaults(&mut self) {
+        for index in 0..Self::PARAM_COUNT {
+            let mut default = None;
+            self.params(FindDefault {
+                index,
+                current: &mut 0,
+                default: &mut default,
+            });
+
+            if let Some(default) = default {
+                self.set_param(index, default);
+            }
+        }
+    }
+
     fn set_param(&mut self, index: usize, value: f64);
     fn get_param(&self, index: usize) -> f64;
     fn parse_param(&self, index: usize, text: &str) -> Option<f64>;
@@ -37,3 +137,330 @@ pub trait Params {
 }
 
 pub trait Enum: Encode + FromStr + Display {}
+
+// Finds the default of the parameter at `index`.
+struct FindDefault<'a> {
+    index: usize,
+    current: &'a mut usize,
+    default: &'a mut Option<f64>,
+}
+
+impl<'a> BuildParams for FindDefault<'a> {
+    fn param<'k>(self, _key: impl Into<Key<'k>>, param: ParamInfo) -> Self {
+        if *self.current == self.index {
+            *self.default = Some(param.default);
+        }
+        *self.current += 1;
+        self
+    }
+
+    fn reserve<'k>(self, _key: impl Into<Key<'k>>) -> Self {
+        self
+    }
+}
+
+/// A `BuildParams` adapter which prepends a prefix to every key passed through it.
+///
+/// `Prefixed` borrows the wrapped builder through an `Option` so that a nested set of parameters
+/// can be built in the middle of a chain of calls on the outer builder. It is used by the code
+/// generated for `#[params(nested)]` fields.
+#[doc(hidden)]
+pub struct Prefixed<'a, B> {
+    prefix: &'a str,
+    index: Option<usize>,
+    build: &'a mut Option<B>,
+}
+
+// Prefixed keys are assembled on the stack, so that building parameters doesn't allocate unless a
+// key is unusually long.
+const KEY_BUFFER_LEN: usize = 256;
+
+struct KeyBuffer {
+    bytes: [u8; KEY_BUFFER_LEN],
+    len: usize,
+}
+
+impl fmt::Write for KeyBuffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        if end > KEY_BUFFER_LEN {
+            return Err(fmt::Error);
+        }
+
+        self.bytes[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+
+        Ok(())
+    }
+}
+
+impl<'a, B: BuildParams> Prefixed<'a, B> {
+    pub fn new(prefix: &'a str, build: &'a mut Option<B>) -> Prefixed<'a, B> {
+        Prefixed {
+            prefix,
+            index: None,
+            build,
+        }
+    }
+
+    /// Prefixes keys with `prefix`, followed by `index` and an underscore, for an element of an
+    /// array of nested parameters.
+    pub fn indexed(prefix: &'a str, index: usize, build: &'a mut Option<B>) -> Prefixed<'a, B> {
+        Prefixed {
+            prefix,
+            index: Some(index),
+            build,
+        }
+    }
+
+    fn write_key(&self, write: &mut impl fmt::Write, key: &str) -> fmt::Result {
+        write.write_str(self.prefix)?;
+        if let Some(index) = self.index {
+            write!(write, "{index}_")?;
+        }
+        write.write_str(key)
+    }
+
+    fn with_key<R>(&self, key: &str, f: impl FnOnce(&str) -> R) -> R {
+        let mut buffer = KeyBuffer {
+            bytes: [0; KEY_BUFFER_LEN],
+            len: 0,
+        };
+
+        // Only whole strings are written to the buffer, so it always holds valid UTF-8.
+        if self.write_key(&mut buffer, key).is_ok()
+            && let Ok(key) = str::from_utf8(&buffer.bytes[..buffer.len])
+        {
+            return f(key);
+        }
+
+        let mut string = String::new();
+        let _ = self.write_key(&mut string, key);
+        f(&string)
+    }
+}
+
+impl<'a, B: BuildParams> BuildParams for Prefixed<'a, B> {
+    fn param<'k>(self, key: impl Into<Key<'k>>, param: ParamInfo) -> Self {
+        let key = key.into();
+
+        if let Some(build) = self.build.take() {
+            let build = self.with_key(key.str, |str| {
+                build.param(Key::new(key.generation, str), param)
+            });
+            *self.build = Some(build);
+        }
+
+        self
+    }
+
+    fn reserve<'k>(self, key: impl Into<Key<'k>>) -> Self {
+        let key = key.into();
+
+        if let Some(build) = self.build.take() {
+            let build = self.with_key(key.str, |str| build.reserve(Key::new(key.generation, str)));
+            *self.build = Some(build);
+        }
+
+        self
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use super::*;
+
+    #[derive(Params, Clone, PartialEq, Debug)]
+    #[params(default)]
+    struct Voice {
+        #[param(name = "Level", range = 0.0..=1.0, default = 0.5)]
+        level: f32,
+        #[param(name = "Octave", range = -2..=2, default = 1)]
+        octave: i32,
+    }
+
+    #[derive(Params, Clone, PartialEq, Debug)]
+    struct Synth {
+        #[param(name = "Gain", default = 0.25)]
+        gain: f32,
+        #[params(nested, prefix = "osc_")]
+        osc: Voice,
+        #[params(nested)]
+        voice: [Voice; 2],
+        #[param(name = "Mix")]
+        mix: f32,
+    }
+
+    impl Default for Synth {
+        fn default() -> Synth {
+            Synth {
+                gain: 0.25,
+                osc: Voice::default(),
+                voice: [Voice::default(), Voice::default()],
+                mix: 1.0,
+            }
+        }
+    }
+
+    struct Collect<'a> {
+        params: &'a mut Vec<(String, f64)>,
+    }
+
+    impl<'a> BuildParams for Collect<'a> {
+        fn param<'k>(self, key: impl Into<Key<'k>>, param: ParamInfo) -> Self {
+            self.params.push((key.into().str.to_string(), param.default));
+            self
+        }
+
+        fn reserve<'k>(self, _key: impl Into<Key<'k>>) -> Self {
+            self
+        }
+    }
+
+    fn collect(params: &impl Params) -> Vec<(String, f64)> {
+        let mut collected = Vec::new();
+        params.params(Collect {
+            params: &mut collected,
+        });
+        collected
+    }
+
+    #[test]
+    fn nested_layout() {
+        assert_eq!(Voice::PARAM_COUNT, 2);
+        assert_eq!(Synth::PARAM_COUNT, 8);
+
+        let keys = collect(&Synth::default()).into_iter().map(|(key, _)| key).collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                "gain",
+                "osc_level",
+                "osc_octave",
+                "voice_0_level",
+                "voice_0_octave",
+                "voice_1_level",
+                "voice_1_octave",
+                "mix",
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_indices() {
+        let mut synth = Synth::default();
+
+        synth.set_param(1, 0.0);
+        synth.set_param(4, 0.0);
+        synth.set_param(5, 1.0);
+        synth.set_param(7, 0.5);
+        synth.set_param(Synth::PARAM_COUNT, 1.0);
+
+        assert_eq!(synth.osc.level, 0.0);
+        assert_eq!(synth.voice[0].octave, -2);
+        assert_eq!(synth.voice[1].level, 1.0);
+        assert_eq!(synth.mix, 0.5);
+
+        let octave = -2..=2;
+        assert_eq!(synth.get_param(4), octave.encode(&-2));
+        assert_eq!(synth.get_param(5), 1.0);
+        assert_eq!(synth.get_param(6), octave.encode(&1));
+        assert_eq!(synth.get_param(Synth::PARAM_COUNT), 0.0);
+
+        assert_eq!(synth.parse_param(6, "2"), Some(octave.encode(&2)));
+        assert_eq!(synth.parse_param(Synth::PARAM_COUNT, "2"), None);
+    }
+
+    #[test]
+    fn declared_defaults() {
+        assert_eq!(
+            Voice::default(),
+            Voice {
+                level: 0.5,
+                octave: 1,
+            }
+        );
+
+        let defaults = collect(&Synth::default());
+        assert_eq!(defaults[0].1, 0.25);
+        assert_eq!(defaults[2].1, (-2..=2).encode(&1));
+        assert_eq!(defaults[7].1, 1.0);
+
+        // Reported defaults don't follow the current state.
+        let mut synth = Synth::default();
+        for index in 0..Synth::PARAM_COUNT {
+            synth.set_param(index, 0.0);
+        }
+        assert_eq!(collect(&synth), defaults);
+
+        synth.reset_to_defaults();
+        assert_eq!(synth, Synth::default());
+    }
+
+    #[test]
+    fn provided_reset_to_defaults() {
+        // Uses the provided `reset_to_defaults` rather than the derived one.
+        struct Manual(Synth);
+
+        impl Params for Manual {
+            const PARAM_COUNT: usize = Synth::PARAM_COUNT;
+
+            fn params(&self, build: impl BuildParams) {
+                self.0.params(build);
+            }
+
+            fn set_param(&mut self, index: usize, value: f64) {
+                self.0.set_param(index, value);
+            }
+
+            fn get_param(&self, index: usize) -> f64 {
+                self.0.get_param(index)
+            }
+
+            fn parse_param(&self, index: usize, text: &str) -> Option<f64> {
+                self.0.parse_param(index, text)
+            }
+
+            fn display_param(
+                &self,
+                index: usize,
+                value: f64,
+                write: impl fmt::Write,
+            ) -> Result<(), fmt::Error> {
+                self.0.display_param(index, value, write)
+            }
+        }
+
+        let mut manual = Manual(Synth::default());
+        for index in 0..Manual::PARAM_COUNT {
+            manual.set_param(index, 0.0);
+        }
+
+        manual.reset_to_defaults();
+        assert_eq!(manual.0, Synth::default());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "declared default for `gain`")]
+    fn mismatched_default() {
+        #[derive(Params)]
+        struct Mismatched {
+            #[param(name = "Gain", default = 0.5)]
+            gain: f32,
+            #[param(name = "Mix")]
+            mix: f32,
+        }
+
+        impl Default for Mismatched {
+            fn default() -> Mismatched {
+                Mismatched {
+                    gain: 1.0,
+                    mix: 1.0,
+                }
+            }
+        }
+
+        collect(&Mismatched::default());
+    }
+}
@@ -17,3 +17,453 @@ impl<T: FromStr + Display> Format<T> for DefaultFormat {
         write!(write, "{}", value)
     }
 }
+
+// Splits user-entered text into a leading number and a trailing unit suffix, ignoring whitespace.
+// Accepts an optional sign and "inf" in place of the number.
+fn parse_number(text: &str) -> Option<(f64, &str)> {
+    let text = text.trim();
+
+    let (sign, rest) = if let Some(rest) = text.strip_prefix('-') {
+        (-1.0, rest)
+    } else if let Some(rest) = text.strip_prefix('+') {
+        (1.0, rest)
+    } else {
+        (1.0, text)
+    };
+
+    if rest.get(..3).is_some_and(|inf| inf.eq_ignore_ascii_case("inf")) {
+        return Some((sign * f64::INFINITY, rest[3..].trim()));
+    }
+
+    let len = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+    let (number, unit) = rest.split_at(len);
+    if !number.contains(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    Some((sign * number.parse::<f64>().ok()?, unit.trim()))
+}
+
+fn is_unit(unit: &str, units: &[&str]) -> bool {
+    units.iter().any(|u| unit.eq_ignore_ascii_case(u))
+}
+
+/// Displays a linear gain factor in decibels, e.g. "-6.0 dB". A gain of zero, or NaN, is displayed
+/// as "-inf dB".
+pub struct Decibels;
+
+/// Displays a frequency in Hz, switching to kHz above 1000 Hz. Accepts input such as "440",
+/// "1.2k" or "1.2 kHz".
+pub struct Hertz;
+
+/// Displays a time value in seconds, switching to milliseconds below one second. Accepts input
+/// such as "250ms" or "1.5 s". Numbers without a unit are interpreted as seconds.
+pub struct Seconds;
+
+/// Displays a value in the range `0.0..=1.0` as a percentage.
+pub struct Percent;
+
+/// Displays a pitch offset in semitones, e.g. "+7.00 st".
+pub struct Semitones;
+
+/// Displays a pitch offset in cents, e.g. "-25 ct".
+pub struct Cents;
+
+/// Displays a value in the range `-1.0..=1.0` as a stereo position, e.g. "L50", "C" or "R100". NaN
+/// is displayed as "C".
+pub struct Pan;
+
+/// Displays a MIDI note number as a note name, e.g. "C#4", where note 60 is "C4". Accepts either a
+/// note name (with `#` or `b` accidentals) or a note number.
+pub struct NoteName;
+
+/// Displays a boolean as "On" or "Off".
+pub struct OnOff;
+
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+fn parse_note(text: &str) -> Option<i64> {
+    let text = text.trim();
+
+    if let Ok(note) = text.parse::<i64>() {
+        return Some(note);
+    }
+
+    let mut chars = text.chars();
+    let mut pitch = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+
+    let mut rest = chars.as_str();
+    if let Some(stripped) = rest.strip_prefix('#') {
+        pitch += 1;
+        rest = stripped;
+    } else if let Some(stripped) = rest.strip_prefix('b') {
+        pitch -= 1;
+        rest = stripped;
+    }
+
+    let octave = rest.trim().parse::<i64>().ok()?;
+
+    octave.checked_add(1)?.checked_mul(12)?.checked_add(pitch)
+}
+
+fn display_note(note: i64, mut write: impl fmt::Write) -> Result<(), fmt::Error> {
+    let name = NOTE_NAMES[note.rem_euclid(12) as usize];
+    let octave = note.div_euclid(12) - 1;
+    write!(write, "{}{}", name, octave)
+}
+
+macro_rules! float_format {
+    ($float:ty) => {
+        impl Format<$float> for Decibels {
+            fn parse(&self, text: &str) -> Option<$float> {
+                let (db, unit) = parse_number(text)?;
+                if !is_unit(unit, &["", "db"]) {
+                    return None;
+                }
+
+                Some((10.0f64).powf(db / 20.0) as $float)
+            }
+
+            fn display(&self, value: $float, mut write: impl fmt::Write) -> Result<(), fmt::Error> {
+                if value <= 0.0 || value.is_nan() {
+                    write.write_str("-inf dB")
+                } else {
+                    write!(write, "{:.1} dB", 20.0 * (value as f64).log10())
+                }
+            }
+        }
+
+        impl Format<$float> for Hertz {
+            fn parse(&self, text: &str) -> Option<$float> {
+                let (value, unit) = parse_number(text)?;
+                if is_unit(unit, &["", "hz"]) {
+                    Some(value as $float)
+                } else if is_unit(unit, &["k", "khz"]) {
+                    Some((value * 1000.0) as $float)
+                } else {
+                    None
+                }
+            }
+
+            fn display(&self, value: $float, mut write: impl fmt::Write) -> Result<(), fmt::Error> {
+                if value.abs() >= 1000.0 {
+                    write!(write, "{:.2} kHz", value / 1000.0)
+                } else {
+                    write!(write, "{:.1} Hz", value)
+                }
+            }
+        }
+
+        impl Format<$float> for Seconds {
+            fn parse(&self, text: &str) -> Option<$float> {
+                let (value, unit) = parse_number(text)?;
+                if is_unit(unit, &["", "s", "sec"]) {
+                    Some(value as $float)
+                } else if is_unit(unit, &["ms"]) {
+                    Some((value / 1000.0) as $float)
+                } else {
+                    None
+                }
+            }
+
+            fn display(&self, value: $float, mut write: impl fmt::Write) -> Result<(), fmt::Error> {
+                if value.abs() < 1.0 {
+                    write!(write, "{:.1} ms", value * 1000.0)
+                } else {
+                    write!(write, "{:.2} s", value)
+                }
+            }
+        }
+
+        impl Format<$float> for Percent {
+            fn parse(&self, text: &str) -> Option<$float> {
+                let (value, unit) = parse_number(text)?;
+                if !is_unit(unit, &["", "%"]) {
+                    return None;
+                }
+
+                Some((value / 100.0) as $float)
+            }
+
+            fn display(&self, value: $float, mut write: impl fmt::Write) -> Result<(), fmt::Error> {
+                write!(write, "{:.1}%", value * 100.0)
+            }
+        }
+
+        impl Format<$float> for Semitones {
+            fn parse(&self, text: &str) -> Option<$float> {
+                let (value, unit) = parse_number(text)?;
+                if !is_unit(unit, &["", "st", "semi", "semitones"]) {
+                    return None;
+                }
+
+                Some(value as $float)
+            }
+
+            fn display(&self, value: $float, mut write: impl fmt::Write) -> Result<(), fmt::Error> {
+                write!(write, "{:+.2} st", value)
+            }
+        }
+
+        impl Format<$float> for Cents {
+            fn parse(&self, text: &str) -> Option<$float> {
+                let (value, unit) = parse_number(text)?;
+                if !is_unit(unit, &["", "c", "ct", "cents"]) {
+                    return None;
+                }
+
+                Some(value as $float)
+            }
+
+            fn display(&self, value: $float, mut write: impl fmt::Write) -> Result<(), fmt::Error> {
+                write!(write, "{:+.0} ct", value)
+            }
+        }
+
+        impl Format<$float> for Pan {
+            fn parse(&self, text: &str) -> Option<$float> {
+                let text = text.trim();
+
+                if text.eq_ignore_ascii_case("c") || text.eq_ignore_ascii_case("center") {
+                    return Some(0.0);
+                }
+
+                // A side on its own means panned all the way.
+                if text.eq_ignore_ascii_case("l") {
+                    return Some(-1.0);
+                } else if text.eq_ignore_ascii_case("r") {
+                    return Some(1.0);
+                }
+
+                // Accept both "L50" and "50L" orderings. Without a side, the sign gives the side.
+                let (side, amount) = if let Some(amount) = text.strip_prefix(['L', 'l']) {
+                    (Some(-1.0), amount)
+                } else if let Some(amount) = text.strip_prefix(['R', 'r']) {
+                    (Some(1.0), amount)
+                } else if let Some(amount) = text.strip_suffix(['L', 'l']) {
+                    (Some(-1.0), amount)
+                } else if let Some(amount) = text.strip_suffix(['R', 'r']) {
+                    (Some(1.0), amount)
+                } else {
+                    (None, text)
+                };
+
+                let (value, unit) = parse_number(amount)?;
+                if !is_unit(unit, &["", "%"]) || value.is_nan() {
+                    return None;
+                }
+
+                // A side with a negative amount, such as "L-50", is ambiguous.
+                if side.is_some() && value < 0.0 {
+                    return None;
+                }
+                let side = side.unwrap_or(1.0);
+
+                Some((side * value / 100.0).clamp(-1.0, 1.0) as $float)
+            }
+
+            fn display(&self, value: $float, mut write: impl fmt::Write) -> Result<(), fmt::Error> {
+                let amount = (value.abs() * 100.0).round();
+                if amount == 0.0 || amount.is_nan() {
+                    write.write_str("C")
+                } else if value < 0.0 {
+                    write!(write, "L{}", amount)
+                } else {
+                    write!(write, "R{}", amount)
+                }
+            }
+        }
+
+        impl Format<$float> for NoteName {
+            fn parse(&self, text: &str) -> Option<$float> {
+                parse_note(text).map(|note| note as $float)
+            }
+
+            fn display(&self, value: $float, write: impl fmt::Write) -> Result<(), fmt::Error> {
+                display_note(value.round() as i64, write)
+            }
+        }
+    };
+}
+
+float_format!(f32);
+float_format!(f64);
+
+macro_rules! int_format {
+    ($int:ty) => {
+        impl Format<$int> for NoteName {
+            fn parse(&self, text: &str) -> Option<$int> {
+                parse_note(text)?.try_into().ok()
+            }
+
+            fn display(&self, value: $int, write: impl fmt::Write) -> Result<(), fmt::Error> {
+                display_note(value as i64, write)
+            }
+        }
+    };
+}
+
+int_format!(u8);
+int_format!(u16);
+int_format!(u32);
+int_format!(u64);
+int_format!(i8);
+int_format!(i16);
+int_format!(i32);
+int_format!(i64);
+
+impl Format<bool> for OnOff {
+    fn parse(&self, text: &str) -> Option<bool> {
+        let text = text.trim();
+
+        if is_unit(text, &["on", "true", "yes", "1"]) {
+            Some(true)
+        } else if is_unit(text, &["off", "false", "no", "0"]) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    fn display(&self, value: bool, mut write: impl fmt::Write) -> Result<(), fmt::Error> {
+        write.write_str(if value { "On" } else { "Off" })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn display<T, F: Format<T>>(format: F, value: T) -> String {
+        let mut text = String::new();
+        format.display(value, &mut text).unwrap();
+        text
+    }
+
+    #[test]
+    fn decibels() {
+        assert_eq!(display(Decibels, 1.0f32), "0.0 dB");
+        assert_eq!(display(Decibels, 0.0f32), "-inf dB");
+        assert_eq!(display(Decibels, 0.5f64), "-6.0 dB");
+        assert_eq!(display(Decibels, f32::NAN), "-inf dB");
+
+        assert_eq!(Format::<f64>::parse(&Decibels, "0"), Some(1.0));
+        assert_eq!(Format::<f64>::parse(&Decibels, "-inf"), Some(0.0));
+        assert_eq!(Format::<f64>::parse(&Decibels, " -inf dB "), Some(0.0));
+        assert_eq!(Format::<f64>::parse(&Decibels, "-20dB"), Some(0.1));
+        assert_eq!(Format::<f64>::parse(&Decibels, "20 Hz"), None);
+    }
+
+    #[test]
+    fn hertz() {
+        assert_eq!(display(Hertz, 440.0f32), "440.0 Hz");
+        assert_eq!(display(Hertz, 1200.0f32), "1.20 kHz");
+
+        assert_eq!(Format::<f32>::parse(&Hertz, "440"), Some(440.0));
+        assert_eq!(Format::<f32>::parse(&Hertz, "440hz"), Some(440.0));
+        assert_eq!(Format::<f32>::parse(&Hertz, "1.2k"), Some(1200.0));
+        assert_eq!(Format::<f32>::parse(&Hertz, "1.2 KHz"), Some(1200.0));
+        assert_eq!(Format::<f32>::parse(&Hertz, "k"), None);
+    }
+
+    #[test]
+    fn seconds() {
+        assert_eq!(display(Seconds, 0.25f64), "250.0 ms");
+        assert_eq!(display(Seconds, 1.5f64), "1.50 s");
+
+        assert_eq!(Format::<f64>::parse(&Seconds, "250ms"), Some(0.25));
+        assert_eq!(Format::<f64>::parse(&Seconds, "1.5 s"), Some(1.5));
+        assert_eq!(Format::<f64>::parse(&Seconds, "2"), Some(2.0));
+    }
+
+    #[test]
+    fn percent() {
+        assert_eq!(display(Percent, 0.5f32), "50.0%");
+
+        assert_eq!(Format::<f32>::parse(&Percent, "50%"), Some(0.5));
+        assert_eq!(Format::<f32>::parse(&Percent, "25"), Some(0.25));
+    }
+
+    #[test]
+    fn pitch() {
+        assert_eq!(display(Semitones, 7.0f32), "+7.00 st");
+        assert_eq!(display(Cents, -25.0f32), "-25 ct");
+
+        assert_eq!(Format::<f32>::parse(&Semitones, "-12 st"), Some(-12.0));
+        assert_eq!(Format::<f32>::parse(&Cents, "+25c"), Some(25.0));
+    }
+
+    #[test]
+    fn pan() {
+        assert_eq!(display(Pan, 0.0f32), "C");
+        assert_eq!(display(Pan, -0.5f32), "L50");
+        assert_eq!(display(Pan, 1.0f32), "R100");
+        assert_eq!(display(Pan, f64::NAN), "C");
+
+        assert_eq!(Format::<f32>::parse(&Pan, "C"), Some(0.0));
+        assert_eq!(Format::<f32>::parse(&Pan, "L50"), Some(-0.5));
+        assert_eq!(Format::<f32>::parse(&Pan, "50R"), Some(0.5));
+        assert_eq!(Format::<f32>::parse(&Pan, "-100"), Some(-1.0));
+        assert_eq!(Format::<f32>::parse(&Pan, "L"), Some(-1.0));
+        assert_eq!(Format::<f32>::parse(&Pan, " r "), Some(1.0));
+        assert_eq!(Format::<f32>::parse(&Pan, ""), None);
+        assert_eq!(Format::<f32>::parse(&Pan, "L-50"), None);
+        assert_eq!(Format::<f32>::parse(&Pan, "-50R"), None);
+        assert_eq!(Format::<f32>::parse(&Pan, "L+50"), Some(-0.5));
+    }
+
+    #[test]
+    fn note_name() {
+        assert_eq!(display(NoteName, 60u8), "C4");
+        assert_eq!(display(NoteName, 61i32), "C#4");
+        assert_eq!(display(NoteName, 0u8), "C-1");
+        assert_eq!(display(NoteName, 69.0f32), "A4");
+
+        assert_eq!(Format::<u8>::parse(&NoteName, "C4"), Some(60));
+        assert_eq!(Format::<u8>::parse(&NoteName, "c#4"), Some(61));
+        assert_eq!(Format::<u8>::parse(&NoteName, "Db4"), Some(61));
+        assert_eq!(Format::<u8>::parse(&NoteName, "Bb3"), Some(58));
+        assert_eq!(Format::<u8>::parse(&NoteName, "C-1"), Some(0));
+        assert_eq!(Format::<u8>::parse(&NoteName, "64"), Some(64));
+        assert_eq!(Format::<u8>::parse(&NoteName, "C-2"), None);
+        assert_eq!(Format::<u8>::parse(&NoteName, "H4"), None);
+
+        assert_eq!(display(NoteName, 60u64), "C4");
+        assert_eq!(Format::<u64>::parse(&NoteName, "A4"), Some(69));
+    }
+
+    #[test]
+    fn malformed_input() {
+        assert_eq!(Format::<f64>::parse(&Decibels, "a€"), None);
+        assert_eq!(Format::<f64>::parse(&Hertz, "-é€"), None);
+        assert_eq!(
+            Format::<i64>::parse(&NoteName, "C9223372036854775807"),
+            None
+        );
+        assert_eq!(
+            Format::<i64>::parse(&NoteName, "B-9223372036854775807"),
+            None
+        );
+    }
+
+    #[test]
+    fn on_off() {
+        assert_eq!(display(OnOff, true), "On");
+        assert_eq!(display(OnOff, false), "Off");
+
+        assert_eq!(OnOff.parse("on"), Some(true));
+        assert_eq!(OnOff.parse("OFF"), Some(false));
+        assert_eq!(OnOff.parse("maybe"), None);
+    }
+}
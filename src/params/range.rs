@@ -1,17 +1,40 @@
+use std::ops::{Bound, RangeBounds};
+
 pub trait Range<T> {
     fn steps(&self) -> Option<u32>;
     fn encode(&self, value: &T) -> f64;
     fn decode(&self, value: f64) -> T;
+
+    /// Names for each step of a stepped range, in order, for hosts that can present the
+    /// parameter as a list.
+    fn value_names(&self) -> Option<&[&str]> {
+        None
+    }
 }
 
 pub trait Encode {
     fn steps() -> Option<u32>;
     fn encode(&self) -> f64;
     fn decode(value: f64) -> Self;
+
+    fn value_names() -> Option<&'static [&'static str]> {
+        None
+    }
 }
 
 pub struct DefaultRange;
 
+// Normalized values can come straight from a host or a saved project, so clamp them to `[0, 1]`
+// before decoding, mapping NaN to 0.
+#[inline]
+fn clamp_normalized(value: f64) -> f64 {
+    if value.is_nan() {
+        0.0
+    } else {
+        value.clamp(0.0, 1.0)
+    }
+}
+
 impl<T: Encode> Range<T> for DefaultRange {
     fn steps(&self) -> Option<u32> {
         T::steps()
@@ -24,11 +47,218 @@ impl<T: Encode> Range<T> for DefaultRange {
     fn decode(&self, value: f64) -> T {
         T::decode(value)
     }
+
+    fn value_names(&self) -> Option<&[&str]> {
+        T::value_names()
+    }
 }
 
 #[derive(Copy, Clone)]
 pub struct Log<T>(pub T);
 
+/// Maps normalized values onto a range along the curve `x.powf(exponent)`.
+///
+/// `exponent` must be positive.
+#[derive(Copy, Clone)]
+pub struct Pow<R, T> {
+    range: R,
+    exponent: T,
+}
+
+impl<R, T: PartialOrd + From<u8>> Pow<R, T> {
+    pub fn new(range: R, exponent: T) -> Pow<R, T> {
+        assert!(exponent > T::from(0), "Pow exponent must be positive");
+
+        Pow { range, exponent }
+    }
+}
+
+/// Skews a range along a power curve such that a normalized value of 0.5 maps to `center`.
+///
+/// `center` must lie strictly between the start and end of the range.
+#[derive(Copy, Clone)]
+pub struct Skew<R, T> {
+    range: R,
+    center: T,
+}
+
+impl<R: RangeBounds<T>, T: PartialOrd> Skew<R, T> {
+    pub fn new(range: R, center: T) -> Skew<R, T> {
+        let in_range = match (range.start_bound(), range.end_bound()) {
+            (Bound::Included(start), Bound::Included(end) | Bound::Excluded(end)) => {
+                *start < center && center < *end
+            }
+            _ => false,
+        };
+        assert!(
+            in_range,
+            "Skew center must lie strictly between the start and end of the range"
+        );
+
+        Skew { range, center }
+    }
+}
+
+/// Maps a linear gain factor onto a range of decibel values.
+///
+/// Normalized values are linear in decibels. A normalized value of 0.0 maps to a gain of 0.0
+/// (-inf dB) rather than to the start of the range.
+#[derive(Copy, Clone)]
+pub struct Db<R>(pub R);
+
+/// Restricts a range to a discrete set of values spaced `step` apart, starting from the start of
+/// the range.
+///
+/// `step` must be positive.
+#[derive(Copy, Clone)]
+pub struct Stepped<R, T> {
+    range: R,
+    step: T,
+}
+
+impl<R, T: PartialOrd + From<u8>> Stepped<R, T> {
+    pub fn new(range: R, step: T) -> Stepped<R, T> {
+        assert!(step > T::from(0), "Stepped step must be positive");
+
+        Stepped { range, step }
+    }
+}
+
+// Implements the curved and stepped ranges for a single float type and range type. `$bounds`
+// extracts the start and end of the range, and `$extra` is 1 for inclusive ranges and 0 for
+// exclusive ones (used to count the number of steps).
+macro_rules! float_range_ext {
+    ($float:ty, $range:ty, |$r:ident| $bounds:expr, $extra:expr) => {
+        impl Range<$float> for Pow<$range, $float> {
+            #[inline]
+            fn steps(&self) -> Option<u32> {
+                None
+            }
+
+            #[inline]
+            fn encode(&self, value: &$float) -> f64 {
+                let (start, end) = {
+                    let $r = &self.range;
+                    $bounds
+                };
+                let linear = ((value - start) / (end - start)).clamp(0.0, 1.0);
+                linear.powf(1.0 / self.exponent) as f64
+            }
+
+            #[inline]
+            fn decode(&self, value: f64) -> $float {
+                let (start, end) = {
+                    let $r = &self.range;
+                    $bounds
+                };
+                let curved = (clamp_normalized(value) as $float).powf(self.exponent);
+                start + (end - start) * curved
+            }
+        }
+
+        impl Range<$float> for Skew<$range, $float> {
+            #[inline]
+            fn steps(&self) -> Option<u32> {
+                None
+            }
+
+            #[inline]
+            fn encode(&self, value: &$float) -> f64 {
+                let (start, end) = {
+                    let $r = &self.range;
+                    $bounds
+                };
+                let exponent = ((self.center - start) / (end - start)).ln() / (0.5 as $float).ln();
+                let linear = ((value - start) / (end - start)).clamp(0.0, 1.0);
+                linear.powf(1.0 / exponent) as f64
+            }
+
+            #[inline]
+            fn decode(&self, value: f64) -> $float {
+                let (start, end) = {
+                    let $r = &self.range;
+                    $bounds
+                };
+                let exponent = ((self.center - start) / (end - start)).ln() / (0.5 as $float).ln();
+                let curved = (clamp_normalized(value) as $float).powf(exponent);
+                start + (end - start) * curved
+            }
+        }
+
+        impl Range<$float> for Db<$range> {
+            #[inline]
+            fn steps(&self) -> Option<u32> {
+                None
+            }
+
+            #[inline]
+            fn encode(&self, value: &$float) -> f64 {
+                let (start, end) = {
+                    let $r = &self.0;
+                    $bounds
+                };
+
+                if *value <= 0.0 {
+                    return 0.0;
+                }
+
+                let db = 20.0 * value.log10();
+                ((db - start) / (end - start)).clamp(0.0, 1.0) as f64
+            }
+
+            #[inline]
+            fn decode(&self, value: f64) -> $float {
+                let (start, end) = {
+                    let $r = &self.0;
+                    $bounds
+                };
+
+                let value = clamp_normalized(value);
+                if value <= 0.0 {
+                    return 0.0;
+                }
+
+                let db = (1.0 - value as $float) * start + value as $float * end;
+                (10.0 as $float).powf(db / 20.0)
+            }
+        }
+
+        impl Range<$float> for Stepped<$range, $float> {
+            #[inline]
+            fn steps(&self) -> Option<u32> {
+                let (start, end) = {
+                    let $r = &self.range;
+                    $bounds
+                };
+                let count = (((end - start) / self.step).round() as u32).saturating_add($extra);
+                Some(count.max(1))
+            }
+
+            #[inline]
+            fn encode(&self, value: &$float) -> f64 {
+                let (start, _) = {
+                    let $r = &self.range;
+                    $bounds
+                };
+                let count = self.steps().unwrap() as f64;
+                let index = ((value - start) / self.step).round() as f64;
+                (index.clamp(0.0, count - 1.0) + 0.5) / count
+            }
+
+            #[inline]
+            fn decode(&self, value: f64) -> $float {
+                let (start, _) = {
+                    let $r = &self.range;
+                    $bounds
+                };
+                let count = self.steps().unwrap() as f64;
+                let index = (clamp_normalized(value) * count).floor().min(count - 1.0);
+                start + index as $float * self.step
+            }
+        }
+    };
+}
+
 macro_rules! float_range {
     ($float:ty) => {
         impl Range<$float> for std::ops::Range<$float> {
@@ -44,7 +274,8 @@ macro_rules! float_range {
 
             #[inline]
             fn decode(&self, value: f64) -> $float {
-                (1.0 - value as $float) * self.start + value as $float * self.end
+                let value = clamp_normalized(value) as $float;
+                (1.0 - value) * self.start + value * self.end
             }
         }
 
@@ -61,7 +292,8 @@ macro_rules! float_range {
 
             #[inline]
             fn decode(&self, value: f64) -> $float {
-                (1.0 - value as $float) * self.start() + value as $float * self.end()
+                let value = clamp_normalized(value) as $float;
+                (1.0 - value) * self.start() + value * self.end()
             }
         }
 
@@ -78,7 +310,7 @@ macro_rules! float_range {
 
             #[inline]
             fn decode(&self, value: f64) -> $float {
-                self.0.start * (self.0.end / self.0.start).powf(value as $float)
+                self.0.start * (self.0.end / self.0.start).powf(clamp_normalized(value) as $float)
             }
         }
 
@@ -95,7 +327,8 @@ macro_rules! float_range {
 
             #[inline]
             fn decode(&self, value: f64) -> $float {
-                self.0.start() * (self.0.end() / self.0.start()).powf(value as $float)
+                self.0.start()
+                    * (self.0.end() / self.0.start()).powf(clamp_normalized(value) as $float)
             }
         }
 
@@ -118,6 +351,21 @@ macro_rules! float_range {
 float_range!(f32);
 float_range!(f64);
 
+float_range_ext!(f32, std::ops::Range<f32>, |r| (r.start, r.end), 0);
+float_range_ext!(
+    f32,
+    std::ops::RangeInclusive<f32>,
+    |r| (*r.start(), *r.end()),
+    1
+);
+float_range_ext!(f64, std::ops::Range<f64>, |r| (r.start, r.end), 0);
+float_range_ext!(
+    f64,
+    std::ops::RangeInclusive<f64>,
+    |r| (*r.start(), *r.end()),
+    1
+);
+
 macro_rules! int_range {
     ($int:ty) => {
         impl Range<$int> for std::ops::Range<$int> {
@@ -135,7 +383,8 @@ macro_rules! int_range {
             #[inline]
             fn decode(&self, value: f64) -> $int {
                 let steps = self.end as f64 - self.start as f64;
-                (self.start as f64 + value * steps) as $int
+                let value = (self.start as f64 + clamp_normalized(value) * steps).floor() as $int;
+                value.min(self.end.saturating_sub(1)).max(self.start)
             }
         }
 
@@ -154,7 +403,9 @@ macro_rules! int_range {
             #[inline]
             fn decode(&self, value: f64) -> $int {
                 let steps = *self.end() as f64 + 1.0 - *self.start() as f64;
-                (*self.start() as f64 + value * steps) as $int
+                let value =
+                    (*self.start() as f64 + clamp_normalized(value) * steps).floor() as $int;
+                value.min(*self.end()).max(*self.start())
             }
         }
 
@@ -200,3 +451,117 @@ impl Encode for bool {
         value >= 0.5
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::panic;
+
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn pow() {
+        let range = Pow::new(0.0..=100.0, 2.0);
+
+        assert_close(range.decode(0.0), 0.0);
+        assert_close(range.decode(0.5), 25.0);
+        assert_close(range.decode(1.0), 100.0);
+        assert_close(range.encode(&25.0), 0.5);
+        assert_eq!(Range::<f64>::steps(&range), None);
+    }
+
+    #[test]
+    fn skew() {
+        let range = Skew::new(20.0..20000.0, 1000.0);
+
+        assert_close(range.decode(0.0), 20.0);
+        assert_close(range.decode(0.5), 1000.0);
+        assert_close(range.decode(1.0), 20000.0);
+        assert_close(range.encode(&1000.0), 0.5);
+
+        for value in [0.1, 0.25, 0.75, 0.9] {
+            assert_close(range.encode(&range.decode(value)), value);
+        }
+    }
+
+    #[test]
+    fn db() {
+        let range = Db(-60.0..=12.0);
+
+        assert_eq!(range.decode(0.0), 0.0);
+        assert_eq!(range.encode(&0.0), 0.0);
+        assert_close(range.encode(&1.0), 60.0 / 72.0);
+        assert_close(range.decode(60.0 / 72.0), 1.0);
+        assert_close(range.decode(1.0), 10.0f64.powf(12.0 / 20.0));
+
+        // Gains below the bottom of the range are clamped rather than extrapolated
+        assert_eq!(range.encode(&1e-9), 0.0);
+    }
+
+    #[test]
+    fn stepped() {
+        let range = Stepped::new(-12.0..=12.0, 0.5);
+
+        assert_eq!(Range::<f64>::steps(&range), Some(49));
+        assert_eq!(range.decode(0.0), -12.0);
+        assert_eq!(range.decode(1.0), 12.0);
+
+        for index in 0..49 {
+            let value = -12.0 + index as f64 * 0.5;
+            assert_eq!(range.decode(range.encode(&value)), value);
+        }
+
+        // Values between steps snap to the nearest step
+        assert_eq!(range.decode(range.encode(&0.3)), 0.5);
+
+        let range = Stepped::new(0.0..1.0, 0.25);
+        assert_eq!(Range::<f32>::steps(&range), Some(4));
+        assert_eq!(range.decode(1.0), 0.75);
+
+        // Steps too small to count saturate rather than overflowing
+        let range = Stepped::new(0.0..=1.0, 1e-300);
+        assert_eq!(Range::<f64>::steps(&range), Some(u32::MAX));
+
+        // Steps which aren't positive are rejected
+        for step in [0.0, -0.5, f64::NAN] {
+            assert!(panic::catch_unwind(|| Stepped::new(0.0..=1.0, step)).is_err());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "step must be positive")]
+    fn stepped_zero_step() {
+        Stepped::new(0.0..=1.0, 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "exponent must be positive")]
+    fn pow_zero_exponent() {
+        Pow::new(0.0..=100.0, 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "center must lie strictly between")]
+    fn skew_center_outside_range() {
+        Skew::new(20.0..20000.0, 10.0);
+    }
+
+    #[test]
+    fn decode_untrusted() {
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, -1.0, 1.0, 2.0] {
+            assert!((-1.0..=1.0).contains(&(-1.0..1.0f32).decode(value)));
+            assert!((0..4).contains(&(0..4u8).decode(value)));
+            assert!((-3..=3).contains(&(-3..=3i32).decode(value)));
+            assert!(Db(-60.0..=12.0f64).decode(value).is_finite());
+            assert!(Log(20.0..=20000.0f64).decode(value).is_finite());
+        }
+
+        let range = -3..3i64;
+        for index in range.clone() {
+            assert_eq!(range.decode(range.encode(&index)), index);
+        }
+    }
+}
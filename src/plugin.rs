@@ -1,9 +1,10 @@
+use std::any::Any;
 use std::{fmt, io};
 
 use crate::bus::{BuildBusConfigs, BuildBuses};
 use crate::editor::{Editor, EditorHost, ParentWindow, Size};
 use crate::host::Host;
-use crate::params::BuildParams;
+use crate::params::{BuildParams, BuildRemoteControls, ParamFunction};
 use crate::process::{Config, Processor};
 
 #[derive(Default)]
@@ -59,6 +60,22 @@ pub trait Plugin: Send + Sized + 'static {
         write: impl fmt::Write,
     ) -> Result<(), fmt::Error>;
 
+    /// Describes pages of parameters for hardware control surfaces. Exported through CLAP
+    /// `remote-controls`.
+    ///
+    /// Pages are not exported to VST3. The nearest equivalent, `IUnitInfo`, places each parameter in
+    /// exactly one unit and has no notion of slot order, so it cannot represent a parameter that
+    /// appears on several pages or a page with empty slots, and hosts would show the units as the
+    /// plugin's parameter hierarchy rather than as controller pages.
+    #[allow(unused_variables)]
+    fn remote_controls(&self, build: impl BuildRemoteControls) {}
+
+    /// Returns the index of the parameter which serves the given function, if any.
+    #[allow(unused_variables)]
+    fn function_param(&self, function: ParamFunction) -> Option<usize> {
+        None
+    }
+
     fn save(&self, output: impl io::Write) -> io::Result<()>;
     fn load(&mut self, input: impl io::Read) -> io::Result<()>;
 
@@ -68,6 +85,16 @@ pub trait Plugin: Send + Sized + 'static {
     fn editor_size(&self) -> Size;
     fn editor(&mut self, host: EditorHost, parent: &ParentWindow) -> Self::Editor;
 
+    /// Handles a message sent by the editor with [`EditorHost::send_message`]. Called on the main
+    /// thread. The host is notified that the plugin's state has changed afterwards.
+    #[allow(unused_variables)]
+    fn message(&mut self, message: Box<dyn Any + Send>) {}
+
+    /// Pushes non-parameter state to the editor. Called after the plugin's state has been loaded
+    /// and after messages from the editor have been handled.
+    #[allow(unused_variables)]
+    fn update_editor(&self, editor: &mut Self::Editor) {}
+
     #[allow(unused_variables)]
     fn latency(&self, config: Config) -> u64 {
         0
@@ -0,0 +1,176 @@
+//! Real-time safety checks for calls into the processor, enabled with the `rt-check` feature.
+//!
+//! A `SyncCell` borrow which fails is reported to stderr along with the location of the borrow,
+//! whether or not a processor call is in progress, and with a backtrace in debug builds. While a
+//! processor call is in progress, memory allocation is also reported if the plugin installs
+//! [`CheckedAlloc`] as its global allocator:
+//!
+//! ```ignore
+//! #[cfg(feature = "rt-check")]
+//! #[global_allocator]
+//! static ALLOCATOR: coupler::rt_check::CheckedAlloc = coupler::rt_check::CheckedAlloc;
+//! ```
+//!
+//! Without the feature, no checks are performed and [`CheckedAlloc`] simply forwards to the system
+//! allocator.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+
+#[cfg(feature = "rt-check")]
+mod enabled {
+    use std::cell::Cell;
+    use std::fmt;
+    use std::panic::Location;
+
+    thread_local! {
+        static CONTEXT: Cell<Option<&'static str>> = const { Cell::new(None) };
+        // Set while reporting a violation, since reporting itself allocates.
+        static REPORTING: Cell<bool> = const { Cell::new(false) };
+        static VIOLATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    pub fn check<R>(context: &'static str, f: impl FnOnce() -> R) -> R {
+        struct Restore(Option<&'static str>);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                let _ = CONTEXT.try_with(|c| c.set(self.0));
+            }
+        }
+
+        let _restore = Restore(CONTEXT.with(|c| c.replace(Some(context))));
+        f()
+    }
+
+    pub fn violation(what: &str) {
+        let Ok(Some(context)) = CONTEXT.try_with(|c| c.get()) else {
+            return;
+        };
+
+        report(format_args!("{what} in {context}"));
+    }
+
+    /// Reports a failed `SyncCell` borrow. Unlike other violations, this is reported outside of
+    /// processor calls too, since the wrappers borrow their own state around those calls.
+    #[track_caller]
+    pub fn contention(what: &str) {
+        let location = Location::caller();
+        match CONTEXT.try_with(|c| c.get()) {
+            Ok(Some(context)) => report(format_args!("{what} at {location} in {context}")),
+            _ => report(format_args!("{what} at {location}")),
+        }
+    }
+
+    fn report(message: fmt::Arguments) {
+        if REPORTING.try_with(|r| r.replace(true)) != Ok(false) {
+            return;
+        }
+
+        let _ = VIOLATIONS.try_with(|v| v.set(v.get() + 1));
+
+        #[cfg(debug_assertions)]
+        eprintln!(
+            "coupler: {message}\n{}",
+            std::backtrace::Backtrace::force_capture()
+        );
+        #[cfg(not(debug_assertions))]
+        eprintln!("coupler: {message}");
+
+        let _ = REPORTING.try_with(|r| r.set(false));
+    }
+
+    /// Returns the number of violations reported on the current thread.
+    #[cfg(test)]
+    pub fn violation_count() -> usize {
+        VIOLATIONS.with(|v| v.get())
+    }
+}
+
+#[cfg(feature = "rt-check")]
+pub(crate) use enabled::{check, contention, violation};
+
+#[cfg(not(feature = "rt-check"))]
+#[inline(always)]
+pub(crate) fn check<R>(_context: &'static str, f: impl FnOnce() -> R) -> R {
+    f()
+}
+
+#[cfg(not(feature = "rt-check"))]
+#[inline(always)]
+pub(crate) fn violation(_what: &str) {}
+
+#[cfg(not(feature = "rt-check"))]
+#[inline(always)]
+pub(crate) fn contention(_what: &str) {}
+
+/// A global allocator which reports allocations made during processor calls.
+pub struct CheckedAlloc;
+
+unsafe impl GlobalAlloc for CheckedAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        violation("memory allocation");
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        violation("memory allocation");
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        violation("memory deallocation");
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        violation("memory reallocation");
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[cfg(all(test, feature = "rt-check"))]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use super::enabled::violation_count;
+    use super::*;
+    use crate::sync::sync_cell::SyncCell;
+
+    #[global_allocator]
+    static ALLOCATOR: CheckedAlloc = CheckedAlloc;
+
+    #[test]
+    fn allocation() {
+        let before = violation_count();
+
+        let vec = check("test", || Vec::<u32>::with_capacity(16));
+        assert!(violation_count() > before);
+
+        let before = violation_count();
+        drop(vec);
+        let _ = check("test", || 1 + 1);
+        assert_eq!(violation_count(), before);
+    }
+
+    #[test]
+    fn contention() {
+        let cell = SyncCell::new(0);
+        let _guard = cell.borrow();
+
+        // Contention is reported even outside of a processor call.
+        let before = violation_count();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| drop(cell.borrow())));
+        assert!(result.is_err());
+        assert_eq!(violation_count(), before + 1);
+    }
+
+    #[test]
+    fn try_borrow() {
+        let cell = SyncCell::new(0);
+        let _guard = cell.borrow();
+
+        let before = violation_count();
+        check("test", || assert!(cell.try_borrow().is_err()));
+        assert_eq!(violation_count(), before);
+    }
+}
@@ -0,0 +1,228 @@
+//! A bounded, allocation-free channel for sending data from the audio thread to the editor.
+//!
+//! The channel is intended for streaming analysis data such as peak levels, spectra, or waveform
+//! snapshots from a [`Processor`](crate::process::Processor) to an [`Editor`](crate::editor::Editor),
+//! which polls the [`Receiver`] periodically on the main thread. Storage for all messages is
+//! allocated up front by [`channel`], and neither sending nor receiving ever blocks or allocates.
+//!
+//! The channel is single-producer, single-consumer. [`Sender`] and [`Receiver`] can be cloned so
+//! that a plugin can hand a sender to each new processor and a receiver to each new editor, but
+//! only one sender and one receiver may access the channel at a time. If two senders (or two
+//! receivers) race, one of them fails as if the channel were full (or empty).
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+struct Inner<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    // Both positions increase monotonically (modulo wrapping) and are reduced modulo the capacity
+    // when indexing into `slots`.
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    sending: AtomicBool,
+    receiving: AtomicBool,
+}
+
+unsafe impl<T: Send> Send for Inner<T> {}
+unsafe impl<T: Send> Sync for Inner<T> {}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        let head = *self.head.get_mut();
+        let tail = *self.tail.get_mut();
+
+        let mut position = head;
+        while position != tail {
+            let slot = &mut self.slots[position % self.slots.len()];
+            unsafe { slot.get_mut().assume_init_drop() };
+            position = position.wrapping_add(1);
+        }
+    }
+}
+
+/// Creates a channel which can hold up to `capacity` messages at a time.
+pub fn channel<T: Send>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let capacity = capacity.max(1);
+
+    let inner = Arc::new(Inner {
+        slots: (0..capacity).map(|_| UnsafeCell::new(MaybeUninit::uninit())).collect(),
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+        sending: AtomicBool::new(false),
+        receiving: AtomicBool::new(false),
+    });
+
+    (
+        Sender {
+            inner: inner.clone(),
+        },
+        Receiver { inner },
+    )
+}
+
+pub struct Sender<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Sender<T> {
+        Sender {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> Sender<T> {
+    /// Sends a message. If the channel is full, the message is handed back.
+    pub fn send(&self, value: T) -> Result<(), T> {
+        let inner = &*self.inner;
+
+        if inner.sending.swap(true, Ordering::Acquire) {
+            return Err(value);
+        }
+
+        let head = inner.head.load(Ordering::Acquire);
+        let tail = inner.tail.load(Ordering::Relaxed);
+
+        let result = if tail.wrapping_sub(head) < inner.slots.len() {
+            let slot = &inner.slots[tail % inner.slots.len()];
+            unsafe { (*slot.get()).write(value) };
+            inner.tail.store(tail.wrapping_add(1), Ordering::Release);
+            Ok(())
+        } else {
+            Err(value)
+        };
+
+        inner.sending.store(false, Ordering::Release);
+
+        result
+    }
+}
+
+pub struct Receiver<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Receiver<T> {
+        Receiver {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Receives the oldest message in the channel, if any.
+    pub fn recv(&self) -> Option<T> {
+        let inner = &*self.inner;
+
+        if inner.receiving.swap(true, Ordering::Acquire) {
+            return None;
+        }
+
+        let head = inner.head.load(Ordering::Relaxed);
+        let tail = inner.tail.load(Ordering::Acquire);
+
+        let result = if head != tail {
+            let slot = &inner.slots[head % inner.slots.len()];
+            let value = unsafe { (*slot.get()).assume_init_read() };
+            inner.head.store(head.wrapping_add(1), Ordering::Release);
+            Some(value)
+        } else {
+            None
+        };
+
+        inner.receiving.store(false, Ordering::Release);
+
+        result
+    }
+
+    /// Returns an iterator which receives messages until the channel is empty.
+    pub fn drain(&self) -> Drain<'_, T> {
+        Drain { receiver: self }
+    }
+}
+
+pub struct Drain<'a, T> {
+    receiver: &'a Receiver<T>,
+}
+
+impl<'a, T> Iterator for Drain<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn send_recv() {
+        let (sender, receiver) = channel(4);
+
+        for i in 0..4 {
+            assert_eq!(sender.send(i), Ok(()));
+        }
+        assert_eq!(sender.send(4), Err(4));
+
+        assert_eq!(receiver.drain().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+        assert_eq!(receiver.recv(), None);
+
+        assert_eq!(sender.send(5), Ok(()));
+        assert_eq!(receiver.recv(), Some(5));
+    }
+
+    #[test]
+    fn drop_pending() {
+        let value = Rc::new(());
+
+        struct AssertSend<T>(T);
+        unsafe impl<T> Send for AssertSend<T> {}
+
+        let (sender, receiver) = channel(4);
+        sender.send(AssertSend(value.clone())).ok().unwrap();
+        sender.send(AssertSend(value.clone())).ok().unwrap();
+        assert_eq!(Rc::strong_count(&value), 3);
+
+        drop(sender);
+        drop(receiver);
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn threads() {
+        const COUNT: usize = 10000;
+
+        let (sender, receiver) = channel(16);
+
+        let producer = thread::spawn(move || {
+            for i in 0..COUNT {
+                let mut value = i;
+                while let Err(rejected) = sender.send(value) {
+                    value = rejected;
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let mut expected = 0;
+        while expected < COUNT {
+            if let Some(value) = receiver.recv() {
+                assert_eq!(value, expected);
+                expected += 1;
+            } else {
+                thread::yield_now();
+            }
+        }
+
+        producer.join().unwrap();
+    }
+}
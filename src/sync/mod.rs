@@ -1,6 +1,8 @@
 pub mod bitset;
+pub mod channel;
 pub mod float;
 pub mod param_gestures;
 pub mod params;
 pub mod sync_cell;
 pub mod thread_cell;
+pub mod thread_check;
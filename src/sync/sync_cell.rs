@@ -4,6 +4,8 @@ use std::fmt;
 use std::ops::{Deref, DerefMut};
 use std::sync::atomic::{AtomicBool, Ordering};
 
+use crate::rt_check;
+
 /// A mutable memory location that can be shared between threads.
 ///
 /// `SyncCell` can be thought of as a thread-safe version of `RefCell` or, alternatively, as a
@@ -31,10 +33,14 @@ impl<T> SyncCell<T> {
         }
     }
 
+    #[track_caller]
     pub fn borrow(&self) -> Guard<'_, T> {
         match self.try_borrow() {
             Ok(b) => b,
-            Err(_) => panic!("SyncCell already borrowed"),
+            Err(_) => {
+                rt_check::contention("SyncCell contention");
+                panic!("SyncCell already borrowed")
+            }
         }
     }
 }
@@ -0,0 +1,186 @@
+use std::panic::Location;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, ThreadId};
+
+use super::sync_cell::{BorrowMutError, Guard, SyncCell};
+use crate::log::{self, Level};
+
+/// The host's own knowledge of which thread is which, e.g. from the CLAP `thread-check` extension.
+pub trait HostThreadCheck: Send + Sync {
+    fn is_main_thread(&self) -> bool;
+    fn is_audio_thread(&self) -> bool;
+}
+
+/// Debug checks that calls from the host arrive on the right thread.
+///
+/// Unless the host can answer for itself, the main thread is taken to be the thread which created
+/// the plugin instance, and any other thread may act as the audio thread. Processing on the main
+/// thread is allowed (e.g. for offline rendering) until the host is seen processing elsewhere.
+///
+/// Violations are logged rather than panicking, so that a misbehaving host doesn't cause the
+/// plugin to be marked as failed. Only the first violation is logged.
+pub struct ThreadCheck {
+    main_thread: ThreadId,
+    separate_audio_thread: AtomicBool,
+    host: OnceLock<Box<dyn HostThreadCheck>>,
+    reported: AtomicBool,
+}
+
+impl ThreadCheck {
+    pub fn new() -> ThreadCheck {
+        ThreadCheck {
+            main_thread: thread::current().id(),
+            separate_audio_thread: AtomicBool::new(false),
+            host: OnceLock::new(),
+            reported: AtomicBool::new(false),
+        }
+    }
+
+    pub fn set_host(&self, host: impl HostThreadCheck + 'static) {
+        let _ = self.host.set(Box::new(host));
+    }
+
+    /// Returns `false` and reports a violation if called off the main thread.
+    #[track_caller]
+    pub fn check_main_thread(&self, what: &str) -> bool {
+        if !cfg!(debug_assertions) {
+            return true;
+        }
+
+        let is_main_thread = if let Some(host) = self.host.get() {
+            host.is_main_thread()
+        } else {
+            thread::current().id() == self.main_thread
+        };
+
+        if !is_main_thread {
+            self.report(what, "main");
+        }
+        is_main_thread
+    }
+
+    /// Returns `false` and reports a violation if called off the audio thread.
+    #[track_caller]
+    pub fn check_audio_thread(&self, what: &str) -> bool {
+        if !cfg!(debug_assertions) {
+            return true;
+        }
+
+        let is_audio_thread = if let Some(host) = self.host.get() {
+            host.is_audio_thread()
+        } else if thread::current().id() != self.main_thread {
+            self.separate_audio_thread.store(true, Ordering::Relaxed);
+            true
+        } else {
+            !self.separate_audio_thread.load(Ordering::Relaxed)
+        };
+
+        if !is_audio_thread {
+            self.report(what, "audio");
+        }
+        is_audio_thread
+    }
+
+    #[track_caller]
+    fn report(&self, what: &str, thread: &str) {
+        if !self.reported.swap(true, Ordering::Relaxed) {
+            log::log(
+                Level::Error,
+                format_args!(
+                    "{what} called off the {thread} thread at {}",
+                    Location::caller()
+                ),
+            );
+        }
+    }
+}
+
+impl Default for ThreadCheck {
+    fn default() -> ThreadCheck {
+        ThreadCheck::new()
+    }
+}
+
+/// A [`SyncCell`] for state which belongs to the main thread. In debug builds, borrowing it from
+/// any other thread is reported through the [`ThreadCheck`].
+pub struct MainThreadCell<T> {
+    thread_check: ThreadCheck,
+    cell: SyncCell<T>,
+}
+
+impl<T> MainThreadCell<T> {
+    pub fn new(data: T) -> MainThreadCell<T> {
+        MainThreadCell {
+            thread_check: ThreadCheck::new(),
+            cell: SyncCell::new(data),
+        }
+    }
+
+    pub fn thread_check(&self) -> &ThreadCheck {
+        &self.thread_check
+    }
+
+    #[track_caller]
+    pub fn try_borrow(&self) -> Result<Guard<'_, T>, BorrowMutError> {
+        self.thread_check.check_main_thread("main-thread method");
+        self.cell.try_borrow()
+    }
+
+    #[track_caller]
+    pub fn borrow(&self) -> Guard<'_, T> {
+        self.thread_check.check_main_thread("main-thread method");
+        self.cell.borrow()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn recorded_threads() {
+        let cell = MainThreadCell::new(0);
+        *cell.borrow() += 1;
+
+        // Processing on the main thread is fine until the host starts using an audio thread.
+        assert!(cell.thread_check().check_audio_thread("process"));
+
+        thread::scope(|scope| {
+            scope.spawn(|| assert!(cell.thread_check().check_audio_thread("process")));
+            scope.spawn(|| assert!(!cell.thread_check().check_main_thread("main-thread method")));
+        });
+
+        assert!(!cell.thread_check().check_audio_thread("process"));
+        assert!(cell.thread_check().reported.load(Ordering::Relaxed));
+
+        // Violations are only reported, so the cell can still be used.
+        thread::scope(|scope| {
+            scope.spawn(|| *cell.borrow() += 1);
+        });
+        assert_eq!(*cell.borrow(), 2);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn host_thread_check() {
+        struct Host;
+
+        impl HostThreadCheck for Host {
+            fn is_main_thread(&self) -> bool {
+                false
+            }
+
+            fn is_audio_thread(&self) -> bool {
+                true
+            }
+        }
+
+        let cell = MainThreadCell::new(());
+        cell.thread_check().set_host(Host);
+
+        assert!(cell.thread_check().check_audio_thread("process"));
+        assert!(!cell.thread_check().check_main_thread("main-thread method"));
+    }
+}
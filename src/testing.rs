@@ -0,0 +1,69 @@
+//! Utilities for testing plugins without a plugin host or a display, enabled with the `testing`
+//! feature.
+
+use crate::events::Event;
+
+pub mod golden;
+
+mod block_size;
+mod clap;
+mod editor;
+#[cfg(test)]
+mod fixtures;
+mod render;
+mod vst3;
+mod wav;
+
+pub use crate::sync::param_gestures::GestureUpdate;
+pub use block_size::{BlockSizeCheck, BlockSizes, Divergence};
+pub use clap::ClapTestHost;
+pub use editor::{EditorHarness, HostCall};
+pub use render::{Automation, AutomationPoint, Renderer};
+pub use vst3::Vst3TestHost;
+pub use wav::Wav;
+
+/// The result of a single call to `process` on one of the test hosts.
+#[derive(Clone, Debug)]
+pub struct ProcessOutput {
+    /// Output audio, indexed by output bus, then channel, then sample.
+    pub outputs: Vec<Vec<Vec<f32>>>,
+    /// Parameter changes reported by the plugin, with values in the range `[0, 1]`.
+    pub events: Vec<Event>,
+}
+
+/// Copies `len` samples of each channel of `src` into a new set of buffers with the given channel
+/// counts, filling in silence for any missing buses, channels or samples.
+fn copy_buffers(src: &[Vec<Vec<f32>>], channel_counts: &[usize], len: usize) -> Vec<Vec<Vec<f32>>> {
+    channel_counts
+        .iter()
+        .enumerate()
+        .map(|(bus, &channel_count)| {
+            (0..channel_count)
+                .map(|channel| {
+                    let mut samples = vec![0.0; len];
+                    if let Some(src) = src.get(bus).and_then(|bus| bus.get(channel)) {
+                        let count = src.len().min(len);
+                        samples[..count].copy_from_slice(&src[..count]);
+                    }
+                    samples
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// A small xorshift generator, so that randomized tests are reproducible from a seed.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        Rng(seed.max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
@@ -0,0 +1,168 @@
+use std::fmt::{self, Display};
+
+use super::{Renderer, Rng};
+use crate::events::Event;
+use crate::plugin::Plugin;
+
+/// How a render is split into blocks.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BlockSizes {
+    /// The whole render in a single block.
+    Whole,
+    /// Uniformly random sizes between one sample and the whole render.
+    Random { seed: u64 },
+    /// Blocks of a single sample.
+    Single,
+}
+
+/// The first sample at which a render diverged from the single-block reference.
+#[derive(Clone, Debug)]
+pub struct Divergence {
+    pub block_sizes: BlockSizes,
+    /// Index of the output bus.
+    pub bus: usize,
+    pub channel: usize,
+    pub sample: usize,
+    pub expected: f32,
+    pub actual: f32,
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:?}: bus {}, channel {}, sample {}: expected {}, got {}",
+            self.block_sizes, self.bus, self.channel, self.sample, self.expected, self.actual
+        )
+    }
+}
+
+/// Checks that a plugin produces the same output regardless of how a render is split into blocks.
+///
+/// A fresh processor is created for each render, with the whole render as its maximum buffer size.
+#[derive(Clone, Debug)]
+pub struct BlockSizeCheck<'a> {
+    pub sample_rate: f64,
+    pub len: usize,
+    /// Input audio, indexed by input bus, then channel.
+    pub inputs: &'a [Vec<Vec<f32>>],
+    /// Events sorted by time, in samples from the start of the render.
+    pub events: &'a [Event],
+    /// Largest allowed absolute difference of any single sample.
+    pub tolerance: f32,
+    /// Seed for the random block sizes.
+    pub seed: u64,
+}
+
+impl<'a> BlockSizeCheck<'a> {
+    fn render<P: Plugin>(&self, block_sizes: BlockSizes) -> Vec<Vec<Vec<f32>>> {
+        let max_buffer_size = self.len.max(1);
+        let mut renderer = Renderer::<P>::new(self.sample_rate, max_buffer_size);
+
+        match block_sizes {
+            BlockSizes::Whole => {
+                renderer.process(self.len, self.inputs, self.events, max_buffer_size)
+            }
+            BlockSizes::Random { seed } => {
+                let mut rng = Rng::new(seed);
+                renderer.process_blocks(self.len, self.inputs, self.events, || {
+                    1 + (rng.next_u64() % max_buffer_size as u64) as usize
+                })
+            }
+            BlockSizes::Single => renderer.process(self.len, self.inputs, self.events, 1),
+        }
+    }
+
+    /// Renders the plugin with random and single-sample blocks, and returns the first divergence
+    /// from a single-block render for each.
+    pub fn run<P: Plugin>(&self) -> Vec<Divergence> {
+        let reference = self.render::<P>(BlockSizes::Whole);
+
+        let mut divergences = Vec::new();
+        for block_sizes in [BlockSizes::Random { seed: self.seed }, BlockSizes::Single] {
+            let outputs = self.render::<P>(block_sizes);
+
+            'search: for (bus, (expected, actual)) in reference.iter().zip(&outputs).enumerate() {
+                for (channel, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                    for (sample, (&expected, &actual)) in expected.iter().zip(actual).enumerate() {
+                        let difference = (expected - actual).abs();
+                        if difference.is_nan() || difference > self.tolerance {
+                            divergences.push(Divergence {
+                                block_sizes,
+                                bus,
+                                channel,
+                                sample,
+                                expected,
+                                actual,
+                            });
+                            break 'search;
+                        }
+                    }
+                }
+            }
+        }
+
+        divergences
+    }
+
+    /// # Panics
+    ///
+    /// Panics if [`BlockSizeCheck::run`] finds any divergence.
+    #[track_caller]
+    pub fn assert_invariant<P: Plugin>(&self) {
+        let divergences = self.run::<P>();
+        if !divergences.is_empty() {
+            let lines: Vec<String> = divergences.iter().map(|d| d.to_string()).collect();
+            panic!("output depends on block size:\n{}", lines.join("\n"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Data;
+    use crate::testing::fixtures::Gain;
+
+    fn check<'a>(inputs: &'a [Vec<Vec<f32>>], events: &'a [Event]) -> BlockSizeCheck<'a> {
+        BlockSizeCheck {
+            sample_rate: 44100.0,
+            len: 256,
+            inputs,
+            events,
+            tolerance: 0.0,
+            seed: 1,
+        }
+    }
+
+    #[test]
+    fn invariant() {
+        let events = [
+            Event {
+                time: 10,
+                data: Data::ParamChange {
+                    index: 0,
+                    value: 0.25,
+                },
+            },
+            Event {
+                time: 100,
+                data: Data::ParamChange {
+                    index: 0,
+                    value: 0.5,
+                },
+            },
+        ];
+
+        let inputs = [vec![vec![1.0; 256]]];
+
+        check(&inputs, &events).assert_invariant::<Gain<false>>();
+
+        let divergences = check(&inputs, &events).run::<Gain<true>>();
+        assert_eq!(divergences.len(), 2);
+
+        let single = &divergences[1];
+        assert_eq!(single.block_sizes, BlockSizes::Single);
+        assert_eq!((single.bus, single.channel, single.sample), (0, 0, 0));
+    }
+}
@@ -0,0 +1,623 @@
+use std::ffi::{CStr, CString, c_char, c_void};
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{mem, ptr, slice};
+
+use clap_sys::ext::{audio_ports::*, gui::*, params::*, state::*};
+use clap_sys::version::CLAP_VERSION;
+use clap_sys::{events::*, host::*, id::*, plugin::*, plugin_factory::*, process::*, stream::*};
+
+use super::{ProcessOutput, copy_buffers};
+use crate::editor::Size;
+use crate::events::{Data, Event};
+use crate::format::clap::{ClapPlugin, Factory};
+use crate::plugin::Plugin;
+
+#[repr(C)]
+struct HostState {
+    clap_host: clap_host,
+    callback_requested: AtomicBool,
+}
+
+unsafe extern "C" fn get_extension(_host: *const clap_host, _id: *const c_char) -> *const c_void {
+    ptr::null()
+}
+
+unsafe extern "C" fn request_restart(_host: *const clap_host) {}
+
+unsafe extern "C" fn request_process(_host: *const clap_host) {}
+
+unsafe extern "C" fn request_callback(host: *const clap_host) {
+    let host = unsafe { &*(host as *const HostState) };
+    host.callback_requested.store(true, Ordering::Release);
+}
+
+struct InputEvents<'a> {
+    events: &'a [clap_event_param_value],
+}
+
+unsafe extern "C" fn input_events_size(list: *const clap_input_events) -> u32 {
+    let events = unsafe { &*((*list).ctx as *const InputEvents) };
+    events.events.len() as u32
+}
+
+unsafe extern "C" fn input_events_get(
+    list: *const clap_input_events,
+    index: u32,
+) -> *const clap_event_header {
+    let events = unsafe { &*((*list).ctx as *const InputEvents) };
+
+    if let Some(event) = events.events.get(index as usize) {
+        return &event.header;
+    }
+
+    ptr::null()
+}
+
+unsafe extern "C" fn output_events_try_push(
+    list: *const clap_output_events,
+    event: *const clap_event_header,
+) -> bool {
+    let events = unsafe { &mut *((*list).ctx as *mut Vec<clap_event_param_value>) };
+
+    let header = unsafe { &*event };
+    if header.space_id == CLAP_CORE_EVENT_SPACE_ID && header.type_ == CLAP_EVENT_PARAM_VALUE {
+        events.push(unsafe { *(event as *const clap_event_param_value) });
+    }
+
+    true
+}
+
+unsafe extern "C" fn ostream_write(
+    stream: *const clap_ostream,
+    buffer: *const c_void,
+    size: u64,
+) -> i64 {
+    let data = unsafe { &mut *((*stream).ctx as *mut Vec<u8>) };
+    let buffer = unsafe { slice::from_raw_parts(buffer as *const u8, size as usize) };
+    data.extend_from_slice(buffer);
+
+    size as i64
+}
+
+unsafe extern "C" fn istream_read(
+    stream: *const clap_istream,
+    buffer: *mut c_void,
+    size: u64,
+) -> i64 {
+    let reader = unsafe { &mut *((*stream).ctx as *mut &mut dyn Read) };
+    let buffer = unsafe { slice::from_raw_parts_mut(buffer as *mut u8, size as usize) };
+
+    // Counts are passed through unchecked, so that a misbehaving reader can stand in for a
+    // misbehaving host.
+    match reader.read(buffer) {
+        Ok(count) => count as i64,
+        Err(_) => -1,
+    }
+}
+
+struct ParamData {
+    id: clap_id,
+    name: String,
+    // Number of discrete values for stepped parameters
+    steps: Option<u32>,
+    is_list: bool,
+}
+
+impl ParamData {
+    fn clap_value(&self, value: f64) -> f64 {
+        if let Some(steps) = self.steps {
+            (value * steps as f64).floor().min((steps - 1) as f64)
+        } else {
+            value
+        }
+    }
+
+    fn normalized(&self, value: f64) -> f64 {
+        if let Some(steps) = self.steps {
+            (value + 0.5) / steps as f64
+        } else {
+            value
+        }
+    }
+}
+
+/// Hosts a plugin in-process through its CLAP interface.
+///
+/// All calls are made on the current thread, which serves as both the main thread and the audio
+/// thread. Parameter values are normalized to `[0, 1]`, as they are for [`Plugin::set_param`].
+pub struct ClapTestHost<P: Plugin + ClapPlugin> {
+    factory: Box<Factory<P>>,
+    host: Box<HostState>,
+    plugin: *const clap_plugin,
+    audio_ports: *const clap_plugin_audio_ports,
+    params_ext: *const clap_plugin_params,
+    state_ext: *const clap_plugin_state,
+    gui_ext: *const clap_plugin_gui,
+    params: Vec<ParamData>,
+    input_channels: Vec<usize>,
+    output_channels: Vec<usize>,
+    max_buffer_size: Option<usize>,
+    steady_time: i64,
+    pending_events: Vec<Event>,
+}
+
+impl<P: Plugin + ClapPlugin> ClapTestHost<P> {
+    /// Creates and initializes an instance of the plugin.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the plugin cannot be created or fails to initialize.
+    pub fn new() -> ClapTestHost<P> {
+        let factory = Box::new(Factory::<P>::new());
+        assert!(unsafe { factory.init() }, "failed to initialize factory");
+
+        let host = Box::new(HostState {
+            clap_host: clap_host {
+                clap_version: CLAP_VERSION,
+                host_data: ptr::null_mut(),
+                name: c"coupler test host".as_ptr(),
+                vendor: c"".as_ptr(),
+                url: c"".as_ptr(),
+                version: c"".as_ptr(),
+                get_extension: Some(get_extension),
+                request_restart: Some(request_restart),
+                request_process: Some(request_process),
+                request_callback: Some(request_callback),
+            },
+            callback_requested: AtomicBool::new(false),
+        });
+
+        let plugin = unsafe {
+            let plugin_factory =
+                factory.get(CLAP_PLUGIN_FACTORY_ID.as_ptr()) as *const clap_plugin_factory;
+            let desc = (*plugin_factory).get_plugin_descriptor.unwrap()(plugin_factory, 0);
+            assert!(!desc.is_null(), "missing plugin descriptor");
+
+            (*plugin_factory).create_plugin.unwrap()(plugin_factory, &host.clap_host, (*desc).id)
+        };
+        assert!(!plugin.is_null(), "failed to create plugin");
+        assert!(
+            unsafe { (*plugin).init.unwrap()(plugin) },
+            "failed to initialize plugin"
+        );
+
+        let extension =
+            |id: &CStr| unsafe { (*plugin).get_extension.unwrap()(plugin, id.as_ptr()) };
+        let audio_ports = extension(CLAP_EXT_AUDIO_PORTS) as *const clap_plugin_audio_ports;
+        let params_ext = extension(CLAP_EXT_PARAMS) as *const clap_plugin_params;
+        let state_ext = extension(CLAP_EXT_STATE) as *const clap_plugin_state;
+        let gui_ext = extension(CLAP_EXT_GUI) as *const clap_plugin_gui;
+        assert!(!audio_ports.is_null(), "missing audio-ports extension");
+        assert!(!params_ext.is_null(), "missing params extension");
+        assert!(!state_ext.is_null(), "missing state extension");
+
+        let mut params = Vec::new();
+        let param_count = unsafe { (*params_ext).count.unwrap()(plugin) };
+        for index in 0..param_count {
+            let mut info: clap_param_info = unsafe { mem::zeroed() };
+            let result = unsafe { (*params_ext).get_info.unwrap()(plugin, index, &mut info) };
+            assert!(result, "failed to get info for parameter {index}");
+
+            let name = unsafe { CStr::from_ptr(info.name.as_ptr()) };
+            let steps = if info.flags & CLAP_PARAM_IS_STEPPED != 0 {
+                Some((info.max_value - info.min_value) as u32 + 1)
+            } else {
+                None
+            };
+
+            params.push(ParamData {
+                id: info.id,
+                name: name.to_string_lossy().into_owned(),
+                steps,
+                is_list: info.flags & CLAP_PARAM_IS_ENUM != 0,
+            });
+        }
+
+        let mut host = ClapTestHost {
+            factory,
+            host,
+            plugin,
+            audio_ports,
+            params_ext,
+            state_ext,
+            gui_ext,
+            params,
+            input_channels: Vec::new(),
+            output_channels: Vec::new(),
+            max_buffer_size: None,
+            steady_time: 0,
+            pending_events: Vec::new(),
+        };
+        host.input_channels = host.port_channels(true);
+        host.output_channels = host.port_channels(false);
+
+        host
+    }
+
+    fn port_channels(&self, is_input: bool) -> Vec<usize> {
+        let audio_ports = unsafe { &*self.audio_ports };
+
+        let count = unsafe { audio_ports.count.unwrap()(self.plugin, is_input) };
+        (0..count)
+            .map(|index| {
+                let mut info: clap_audio_port_info = unsafe { mem::zeroed() };
+                let result =
+                    unsafe { audio_ports.get.unwrap()(self.plugin, index, is_input, &mut info) };
+                assert!(result, "failed to get info for audio port {index}");
+
+                info.channel_count as usize
+            })
+            .collect()
+    }
+
+    fn handle_callback(&mut self) {
+        if self.host.callback_requested.swap(false, Ordering::Acquire) {
+            unsafe { (*self.plugin).on_main_thread.unwrap()(self.plugin) };
+        }
+    }
+
+    fn convert_events(&self, events: &[Event]) -> Vec<clap_event_param_value> {
+        let mut events = events.to_vec();
+        events.sort_by_key(|event| event.time);
+
+        events
+            .iter()
+            .map(|event| match event.data {
+                Data::ParamChange { index, value } => {
+                    let param = &self.params[index];
+
+                    clap_event_param_value {
+                        header: clap_event_header {
+                            size: mem::size_of::<clap_event_param_value>() as u32,
+                            time: event.time as u32,
+                            space_id: CLAP_CORE_EVENT_SPACE_ID,
+                            type_: CLAP_EVENT_PARAM_VALUE,
+                            flags: 0,
+                        },
+                        param_id: param.id,
+                        cookie: ptr::null_mut(),
+                        note_id: -1,
+                        port_index: -1,
+                        channel: -1,
+                        key: -1,
+                        value: param.clap_value(value),
+                    }
+                }
+            })
+            .collect()
+    }
+
+    fn collect_events(&self, events: &[clap_event_param_value]) -> Vec<Event> {
+        events
+            .iter()
+            .filter_map(|event| {
+                let index = self.params.iter().position(|param| param.id == event.param_id)?;
+
+                Some(Event {
+                    time: event.header.time as i64,
+                    data: Data::ParamChange {
+                        index,
+                        value: self.params[index].normalized(event.value),
+                    },
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the number of channels on each input bus.
+    pub fn input_channels(&self) -> &[usize] {
+        &self.input_channels
+    }
+
+    /// Returns the number of channels on each output bus.
+    pub fn output_channels(&self) -> &[usize] {
+        &self.output_channels
+    }
+
+    pub fn activate(&mut self, sample_rate: f64, max_buffer_size: usize) {
+        self.deactivate();
+
+        let plugin = unsafe { &*self.plugin };
+        let result = unsafe {
+            plugin.activate.unwrap()(self.plugin, sample_rate, 1, max_buffer_size as u32)
+        };
+        assert!(result, "failed to activate plugin");
+
+        let result = unsafe { plugin.start_processing.unwrap()(self.plugin) };
+        assert!(result, "failed to start processing");
+
+        self.max_buffer_size = Some(max_buffer_size);
+        self.steady_time = 0;
+    }
+
+    pub fn deactivate(&mut self) {
+        if self.max_buffer_size.take().is_some() {
+            let plugin = unsafe { &*self.plugin };
+            unsafe { plugin.stop_processing.unwrap()(self.plugin) };
+            unsafe { plugin.deactivate.unwrap()(self.plugin) };
+        }
+
+        self.handle_callback();
+    }
+
+    /// Processes a block of `len` samples.
+    ///
+    /// `inputs` is indexed by input bus, then channel. Missing buses, channels or samples are
+    /// treated as silence. `events` must be within the block, and parameter changes made with
+    /// [`ClapTestHost::set_param`] since the last block are delivered at its start.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the plugin is not active, if `len` exceeds the maximum buffer size, or if the
+    /// plugin reports an error.
+    pub fn process(
+        &mut self,
+        len: usize,
+        inputs: &[Vec<Vec<f32>>],
+        events: &[Event],
+    ) -> ProcessOutput {
+        self.try_process(len, inputs, events)
+            .expect("plugin returned an error from process")
+    }
+
+    /// Like [`ClapTestHost::process`], but returns the output in `Err` rather than panicking if the
+    /// plugin reports an error.
+    pub fn try_process(
+        &mut self,
+        len: usize,
+        inputs: &[Vec<Vec<f32>>],
+        events: &[Event],
+    ) -> Result<ProcessOutput, ProcessOutput> {
+        let max_buffer_size = self.max_buffer_size.expect("plugin is not active");
+        assert!(len <= max_buffer_size, "block exceeds maximum buffer size");
+
+        let mut inputs = copy_buffers(inputs, &self.input_channels, len);
+        let mut outputs = copy_buffers(&[], &self.output_channels, len);
+
+        let mut input_ptrs: Vec<Vec<*mut f32>> = inputs
+            .iter_mut()
+            .map(|bus| bus.iter_mut().map(|channel| channel.as_mut_ptr()).collect())
+            .collect();
+        let mut output_ptrs: Vec<Vec<*mut f32>> = outputs
+            .iter_mut()
+            .map(|bus| bus.iter_mut().map(|channel| channel.as_mut_ptr()).collect())
+            .collect();
+
+        let audio_buffer = |ptrs: &mut Vec<*mut f32>| clap_audio_buffer {
+            data32: ptrs.as_mut_ptr(),
+            data64: ptr::null_mut(),
+            channel_count: ptrs.len() as u32,
+            latency: 0,
+            constant_mask: 0,
+        };
+        let audio_inputs: Vec<clap_audio_buffer> =
+            input_ptrs.iter_mut().map(audio_buffer).collect();
+        let mut audio_outputs: Vec<clap_audio_buffer> =
+            output_ptrs.iter_mut().map(audio_buffer).collect();
+
+        let mut all_events = mem::take(&mut self.pending_events);
+        all_events.extend_from_slice(events);
+        let in_events = self.convert_events(&all_events);
+        let in_events_ctx = InputEvents { events: &in_events };
+        let in_events_list = clap_input_events {
+            ctx: &in_events_ctx as *const InputEvents as *mut c_void,
+            size: Some(input_events_size),
+            get: Some(input_events_get),
+        };
+
+        let mut out_events = Vec::new();
+        let out_events_list = clap_output_events {
+            ctx: &mut out_events as *mut Vec<clap_event_param_value> as *mut c_void,
+            try_push: Some(output_events_try_push),
+        };
+
+        let process = clap_process {
+            steady_time: self.steady_time,
+            frames_count: len as u32,
+            transport: ptr::null(),
+            audio_inputs: audio_inputs.as_ptr(),
+            audio_outputs: audio_outputs.as_mut_ptr(),
+            audio_inputs_count: audio_inputs.len() as u32,
+            audio_outputs_count: audio_outputs.len() as u32,
+            in_events: &in_events_list,
+            out_events: &out_events_list,
+        };
+
+        let status = unsafe { (*self.plugin).process.unwrap()(self.plugin, &process) };
+
+        self.steady_time += len as i64;
+        self.handle_callback();
+
+        let output = ProcessOutput {
+            outputs,
+            events: self.collect_events(&out_events),
+        };
+
+        if status == CLAP_PROCESS_ERROR {
+            Err(output)
+        } else {
+            Ok(output)
+        }
+    }
+
+    pub fn param_count(&self) -> usize {
+        self.params.len()
+    }
+
+    pub fn param_name(&self, index: usize) -> &str {
+        &self.params[index].name
+    }
+
+    /// Returns whether the plugin asks for the parameter to be presented as a list of named values.
+    pub fn param_is_list(&self, index: usize) -> bool {
+        self.params[index].is_list
+    }
+
+    /// Converts text to a parameter value through the plugin, as a host would when the user types
+    /// in a value. Returns `None` if the plugin could not parse it.
+    pub fn parse_param(&self, index: usize, text: &str) -> Option<f64> {
+        let param = &self.params[index];
+        let text = CString::new(text).ok()?;
+
+        let mut value = 0.0;
+        let result = unsafe {
+            (*self.params_ext).text_to_value.unwrap()(
+                self.plugin,
+                param.id,
+                text.as_ptr(),
+                &mut value,
+            )
+        };
+
+        result.then(|| param.normalized(value))
+    }
+
+    pub fn get_param(&self, index: usize) -> f64 {
+        let param = &self.params[index];
+
+        let mut value = 0.0;
+        let result =
+            unsafe { (*self.params_ext).get_value.unwrap()(self.plugin, param.id, &mut value) };
+        assert!(result, "failed to get value of parameter {index}");
+
+        param.normalized(value)
+    }
+
+    /// Sets a parameter as a host would when automating it. If the plugin is active, the change is
+    /// delivered at the start of the next call to [`ClapTestHost::process`]. Otherwise, it is
+    /// delivered immediately with `flush`.
+    pub fn set_param(&mut self, index: usize, value: f64) {
+        let event = Event {
+            time: 0,
+            data: Data::ParamChange { index, value },
+        };
+
+        if self.max_buffer_size.is_some() {
+            self.pending_events.push(event);
+            return;
+        }
+
+        let in_events = self.convert_events(&[event]);
+        let in_events_ctx = InputEvents { events: &in_events };
+        let in_events_list = clap_input_events {
+            ctx: &in_events_ctx as *const InputEvents as *mut c_void,
+            size: Some(input_events_size),
+            get: Some(input_events_get),
+        };
+
+        let mut out_events = Vec::new();
+        let out_events_list = clap_output_events {
+            ctx: &mut out_events as *mut Vec<clap_event_param_value> as *mut c_void,
+            try_push: Some(output_events_try_push),
+        };
+
+        unsafe {
+            (*self.params_ext).flush.unwrap()(self.plugin, &in_events_list, &out_events_list)
+        };
+        self.handle_callback();
+    }
+
+    pub fn save(&mut self) -> Vec<u8> {
+        let mut data = Vec::new();
+        let stream = clap_ostream {
+            ctx: &mut data as *mut Vec<u8> as *mut c_void,
+            write: Some(ostream_write),
+        };
+
+        let result = unsafe { (*self.state_ext).save.unwrap()(self.plugin, &stream) };
+        assert!(result, "failed to save plugin state");
+
+        data
+    }
+
+    /// Loads state previously returned by [`ClapTestHost::save`]. Returns `false` if the plugin
+    /// rejected it.
+    pub fn load(&mut self, data: &[u8]) -> bool {
+        self.load_from(data)
+    }
+
+    /// Loads state from `reader`. Byte counts returned by `reader` are passed on to the plugin as
+    /// they are, even if they are larger than the buffer it asked to fill.
+    pub fn load_from(&mut self, mut reader: impl Read) -> bool {
+        let mut reader: &mut dyn Read = &mut reader;
+        let stream = clap_istream {
+            ctx: &mut reader as *mut &mut dyn Read as *mut c_void,
+            read: Some(istream_read),
+        };
+
+        let result = unsafe { (*self.state_ext).load.unwrap()(self.plugin, &stream) };
+        self.handle_callback();
+
+        result
+    }
+
+    fn gui(&self) -> &clap_plugin_gui {
+        assert!(!self.gui_ext.is_null(), "missing gui extension");
+        unsafe { &*self.gui_ext }
+    }
+
+    /// Opens the plugin's editor. Returns `false` if the plugin refused to create it.
+    ///
+    /// The editor is given a null parent window handle, so this is only suitable for editors which
+    /// can operate without creating a native window.
+    pub fn open_editor(&mut self) -> bool {
+        #[cfg(target_os = "windows")]
+        let api = CLAP_WINDOW_API_WIN32;
+
+        #[cfg(target_os = "macos")]
+        let api = CLAP_WINDOW_API_COCOA;
+
+        #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+        let api = CLAP_WINDOW_API_X11;
+
+        let gui = self.gui();
+        if !unsafe { gui.create.unwrap()(self.plugin, api.as_ptr(), false) } {
+            return false;
+        }
+
+        let mut window: clap_window = unsafe { mem::zeroed() };
+        window.api = api.as_ptr();
+        let result = unsafe { gui.set_parent.unwrap()(self.plugin, &window) };
+        self.handle_callback();
+
+        result
+    }
+
+    /// Resizes the editor as a host would when the user resizes its window. Returns `false` if the
+    /// editor rejected the new size.
+    pub fn resize_editor(&mut self, size: Size) -> bool {
+        let gui = self.gui();
+        let width = size.width.round() as u32;
+        let height = size.height.round() as u32;
+        let result = unsafe { gui.set_size.unwrap()(self.plugin, width, height) };
+        self.handle_callback();
+
+        result
+    }
+
+    pub fn close_editor(&mut self) {
+        unsafe { self.gui().destroy.unwrap()(self.plugin) };
+        self.handle_callback();
+    }
+}
+
+impl<P: Plugin + ClapPlugin> Default for ClapTestHost<P> {
+    fn default() -> ClapTestHost<P> {
+        ClapTestHost::new()
+    }
+}
+
+impl<P: Plugin + ClapPlugin> Drop for ClapTestHost<P> {
+    fn drop(&mut self) {
+        let plugin = unsafe { &*self.plugin };
+
+        if self.max_buffer_size.take().is_some() {
+            unsafe { plugin.stop_processing.unwrap()(self.plugin) };
+            unsafe { plugin.deactivate.unwrap()(self.plugin) };
+        }
+
+        unsafe { plugin.destroy.unwrap()(self.plugin) };
+        unsafe { self.factory.deinit() };
+    }
+}
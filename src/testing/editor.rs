@@ -0,0 +1,270 @@
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::mem;
+use std::rc::Rc;
+
+use super::GestureUpdate;
+use crate::collect::collect_params;
+use crate::editor::{
+    AutomationState, Color, Editor, EditorHost, EditorHostInner, MenuItem, ParamMapping,
+    ParentWindow, RawParent, Size,
+};
+use crate::plugin::Plugin;
+use crate::sync::param_gestures::{GestureStates, ParamGestures};
+
+/// A call made by an editor to its [`EditorHost`].
+#[derive(Clone, PartialEq, Debug)]
+pub enum HostCall {
+    BeginGesture(usize),
+    SetParam(usize, f64),
+    EndGesture(usize),
+    RequestResize(Size),
+    SendMessage,
+    ShowContextMenu {
+        param: Option<usize>,
+        x: f64,
+        y: f64,
+        items: Vec<MenuItem>,
+    },
+}
+
+struct RecordingHost {
+    calls: RefCell<Vec<HostCall>>,
+    messages: RefCell<Vec<Box<dyn Any + Send>>>,
+    param_gestures: ParamGestures,
+    accept_resize: Cell<bool>,
+}
+
+impl EditorHostInner for RecordingHost {
+    fn begin_gesture(&self, index: usize) {
+        self.calls.borrow_mut().push(HostCall::BeginGesture(index));
+        self.param_gestures.begin_gesture(index);
+    }
+
+    fn end_gesture(&self, index: usize) {
+        self.calls.borrow_mut().push(HostCall::EndGesture(index));
+        self.param_gestures.end_gesture(index);
+    }
+
+    fn set_param(&self, index: usize, value: f64) {
+        self.calls.borrow_mut().push(HostCall::SetParam(index, value));
+        self.param_gestures.set_value(index, value);
+    }
+
+    fn request_resize(&self, size: Size) -> bool {
+        self.calls.borrow_mut().push(HostCall::RequestResize(size));
+        self.accept_resize.get()
+    }
+
+    fn send_message(&self, message: Box<dyn Any + Send>) {
+        self.calls.borrow_mut().push(HostCall::SendMessage);
+        self.messages.borrow_mut().push(message);
+    }
+
+    fn show_context_menu(&self, param: Option<usize>, x: f64, y: f64, items: &[MenuItem]) -> bool {
+        self.calls.borrow_mut().push(HostCall::ShowContextMenu {
+            param,
+            x,
+            y,
+            items: items.to_vec(),
+        });
+        true
+    }
+}
+
+/// Drives an [`Editor`] against a fake [`EditorHost`] which records every call made to it.
+///
+/// Parameter gestures are coalesced using the same logic as the format wrappers, so
+/// [`EditorHarness::poll_gestures`] returns the sequence of updates a host would actually see.
+pub struct EditorHarness<E> {
+    editor: E,
+    host: Rc<RecordingHost>,
+    gesture_states: GestureStates,
+}
+
+impl<E: Editor> EditorHarness<E> {
+    /// Creates an editor using the provided closure. `param_count` is the number of parameters the
+    /// editor may refer to.
+    pub fn new<F>(param_count: usize, f: F) -> EditorHarness<E>
+    where
+        F: FnOnce(EditorHost) -> E,
+    {
+        let host = Rc::new(RecordingHost {
+            calls: RefCell::new(Vec::new()),
+            messages: RefCell::new(Vec::new()),
+            param_gestures: ParamGestures::with_count(param_count),
+            accept_resize: Cell::new(true),
+        });
+
+        let editor = f(EditorHost::from_inner(host.clone()));
+
+        EditorHarness {
+            editor,
+            host,
+            gesture_states: GestureStates::with_count(param_count),
+        }
+    }
+
+    /// Creates an editor with [`Plugin::editor`].
+    ///
+    /// The editor is given a null parent window handle, so this is only suitable for editors which
+    /// can operate without creating a native window.
+    pub fn from_plugin<P>(plugin: &mut P) -> EditorHarness<E>
+    where
+        P: Plugin<Editor = E>,
+    {
+        let (param_ids, _) = collect_params(plugin);
+
+        #[cfg(target_os = "windows")]
+        let raw_parent = RawParent::Win32(std::ptr::null_mut());
+
+        #[cfg(target_os = "macos")]
+        let raw_parent = RawParent::Cocoa(std::ptr::null_mut());
+
+        #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+        let raw_parent = RawParent::X11(0);
+
+        let parent = unsafe { ParentWindow::from_raw(raw_parent) };
+
+        EditorHarness::new(param_ids.len(), |host| plugin.editor(host, &parent))
+    }
+
+    pub fn editor(&self) -> &E {
+        &self.editor
+    }
+
+    pub fn editor_mut(&mut self) -> &mut E {
+        &mut self.editor
+    }
+
+    pub fn size(&self) -> Size {
+        self.editor.size()
+    }
+
+    pub fn param_changed(&mut self, index: usize, value: f64) {
+        self.editor.param_changed(index, value);
+    }
+
+    /// Resizes the editor the way a host would: the requested size is first constrained by the
+    /// editor's resize hints and then passed to [`Editor::set_size`]. Returns the size that was
+    /// applied, or `None` if the editor is not resizable or rejected the new size.
+    pub fn resize(&mut self, size: Size) -> Option<Size> {
+        let size = self.editor.resize_hints()?.constrain(size);
+
+        if self.editor.set_size(size) {
+            Some(size)
+        } else {
+            None
+        }
+    }
+
+    pub fn set_scale(&mut self, scale: f64) {
+        self.editor.set_scale(scale);
+    }
+
+    pub fn idle(&mut self) {
+        self.editor.idle();
+    }
+
+    /// Simulates the user selecting a plugin-provided context menu item.
+    pub fn context_menu_action(&mut self, id: u32) {
+        self.editor.context_menu_action(id);
+    }
+
+    pub fn param_mapping_changed(&mut self, index: usize, mapping: Option<ParamMapping>) {
+        self.editor.param_mapping_changed(index, mapping);
+    }
+
+    pub fn param_automation_changed(
+        &mut self,
+        index: usize,
+        state: AutomationState,
+        color: Option<Color>,
+    ) {
+        self.editor.param_automation_changed(index, state, color);
+    }
+
+    /// Sets whether calls to [`EditorHost::request_resize`] succeed. Defaults to `true`.
+    pub fn accept_resize(&mut self, accept: bool) {
+        self.host.accept_resize.set(accept);
+    }
+
+    /// Returns and clears the list of calls made to the host since the last call to `take_calls`.
+    pub fn take_calls(&mut self) -> Vec<HostCall> {
+        mem::take(&mut *self.host.calls.borrow_mut())
+    }
+
+    /// Returns and clears the messages sent to the plugin with [`EditorHost::send_message`].
+    pub fn take_messages(&mut self) -> Vec<Box<dyn Any + Send>> {
+        mem::take(&mut *self.host.messages.borrow_mut())
+    }
+
+    /// Returns the gesture updates which would be sent to the host since the last poll.
+    pub fn poll_gestures(&mut self) -> Vec<GestureUpdate> {
+        self.host.param_gestures.poll(&mut self.gesture_states).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixtures::GainEditor;
+
+    fn harness() -> EditorHarness<GainEditor> {
+        EditorHarness::new(1, GainEditor::new)
+    }
+
+    #[test]
+    fn gestures() {
+        let mut harness = harness();
+
+        harness.editor_mut().drag(&[0.25, 0.5]);
+
+        assert_eq!(
+            harness.take_calls(),
+            &[
+                HostCall::BeginGesture(0),
+                HostCall::SetParam(0, 0.25),
+                HostCall::SetParam(0, 0.5),
+                HostCall::EndGesture(0),
+            ]
+        );
+        assert_eq!(
+            harness.poll_gestures(),
+            &[GestureUpdate {
+                index: 0,
+                begin_gesture: true,
+                set_value: Some(0.5),
+                end_gesture: true,
+            }]
+        );
+        assert!(harness.take_calls().is_empty());
+        assert!(harness.poll_gestures().is_empty());
+    }
+
+    #[test]
+    fn param_changed() {
+        let mut harness = harness();
+
+        harness.param_changed(0, 0.75);
+        assert_eq!(harness.editor().value, 0.75);
+    }
+
+    #[test]
+    fn resize() {
+        let mut harness = harness();
+
+        let size = harness.resize(Size {
+            width: 50.0,
+            height: 300.0,
+        });
+        assert_eq!(
+            size,
+            Some(Size {
+                width: 100.0,
+                height: 300.0,
+            })
+        );
+        assert_eq!(harness.size(), size.unwrap());
+    }
+}
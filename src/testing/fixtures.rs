@@ -0,0 +1,182 @@
+//! A minimal plugin shared by the tests for the testing utilities.
+
+use std::{fmt, io};
+
+use crate::buffers::{AnyBuffer, Buffers};
+use crate::bus::{BuildBusConfigs, BuildBuses, BusConfig, BusDir, BusInfo, Layout};
+use crate::editor::{Editor, EditorHost, ParentWindow, ResizeHints, Size};
+use crate::events::{Data, Events};
+use crate::host::Host;
+use crate::params::{BuildParams, ParamInfo};
+use crate::plugin::{BuildInfo, Plugin, PluginInfo};
+use crate::process::{Config, Processor};
+
+/// A mono gain with a single parameter. If `PER_BLOCK` is true, parameter changes take effect at
+/// the start of the block rather than at the event's time.
+pub struct Gain<const PER_BLOCK: bool> {
+    gain: f64,
+}
+
+impl<const PER_BLOCK: bool> Plugin for Gain<PER_BLOCK> {
+    type Processor = GainProcessor<PER_BLOCK>;
+    type Editor = GainEditor;
+
+    fn info(build: impl BuildInfo) {
+        build.info(PluginInfo::default());
+    }
+    fn new(_host: Host) -> Self {
+        Gain { gain: 1.0 }
+    }
+    fn buses(&self, build: impl BuildBuses) {
+        build.bus(
+            "main",
+            BusInfo {
+                name: "Main",
+                dir: BusDir::InOut,
+            },
+        );
+    }
+    fn bus_configs(&self, build: impl BuildBusConfigs) {
+        build.config(
+            "mono",
+            BusConfig {
+                layouts: &[Layout::Mono],
+            },
+        );
+    }
+    fn params(&self, build: impl BuildParams) {
+        build.param("gain", ParamInfo::new("Gain", 1.0));
+    }
+    fn set_param(&mut self, _index: usize, value: f64) {
+        self.gain = value;
+    }
+    fn get_param(&self, _index: usize) -> f64 {
+        self.gain
+    }
+    fn parse_param(&self, _index: usize, _text: &str) -> Option<f64> {
+        None
+    }
+    fn display_param(
+        &self,
+        _index: usize,
+        _value: f64,
+        _write: impl fmt::Write,
+    ) -> Result<(), fmt::Error> {
+        Ok(())
+    }
+    fn save(&self, _output: impl io::Write) -> io::Result<()> {
+        Ok(())
+    }
+    fn load(&mut self, _input: impl io::Read) -> io::Result<()> {
+        Ok(())
+    }
+    fn processor(&mut self, _config: Config) -> Self::Processor {
+        GainProcessor { gain: self.gain }
+    }
+    fn has_editor(&self) -> bool {
+        true
+    }
+    fn editor_size(&self) -> Size {
+        GainEditor::DEFAULT_SIZE
+    }
+    fn editor(&mut self, host: EditorHost, _parent: &ParentWindow) -> Self::Editor {
+        GainEditor::new(host)
+    }
+}
+
+pub struct GainProcessor<const PER_BLOCK: bool> {
+    gain: f64,
+}
+
+impl<const PER_BLOCK: bool> GainProcessor<PER_BLOCK> {
+    fn apply(&mut self, events: Events) {
+        for event in events {
+            match event.data {
+                Data::ParamChange { value, .. } => self.gain = value,
+            }
+        }
+    }
+}
+
+impl<const PER_BLOCK: bool> Processor for GainProcessor<PER_BLOCK> {
+    fn reset(&mut self) {}
+    fn set_param(&mut self, _index: usize, value: f64) {
+        self.gain = value;
+    }
+    fn process(&mut self, mut buffers: Buffers, events: Events) {
+        if PER_BLOCK {
+            self.apply(events);
+        }
+
+        for (mut block, events) in buffers.split_at_events(events) {
+            if !PER_BLOCK {
+                self.apply(events);
+            }
+
+            if let Some(AnyBuffer::Mut(mut buffer)) = block.get(0) {
+                for sample in &mut buffer[0] {
+                    *sample *= self.gain as f32;
+                }
+            }
+        }
+    }
+}
+
+/// A resizable editor with a minimum size, which tracks the value of the gain parameter.
+pub struct GainEditor {
+    pub host: EditorHost,
+    pub value: f64,
+    pub size: Size,
+}
+
+impl GainEditor {
+    pub const DEFAULT_SIZE: Size = Size {
+        width: 200.0,
+        height: 200.0,
+    };
+
+    pub fn new(host: EditorHost) -> GainEditor {
+        GainEditor {
+            host,
+            value: 1.0,
+            size: GainEditor::DEFAULT_SIZE,
+        }
+    }
+
+    /// Performs a gesture on the gain parameter, as if the user had dragged a knob.
+    pub fn drag(&mut self, values: &[f64]) {
+        self.host.begin_gesture(0);
+        for &value in values {
+            self.host.set_param(0, value);
+        }
+        self.host.end_gesture(0);
+    }
+}
+
+impl Editor for GainEditor {
+    fn size(&self) -> Size {
+        self.size
+    }
+
+    fn param_changed(&mut self, index: usize, value: f64) {
+        if index == 0 {
+            self.value = value;
+        }
+    }
+
+    fn resize_hints(&self) -> Option<ResizeHints> {
+        Some(ResizeHints {
+            min_size: Some(Size {
+                width: 100.0,
+                height: 100.0,
+            }),
+            max_size: None,
+            aspect_ratio: None,
+        })
+    }
+
+    fn set_size(&mut self, size: Size) -> bool {
+        self.size = size;
+        true
+    }
+}
@@ -0,0 +1,401 @@
+use std::f32::consts::PI;
+use std::fmt::{self, Display};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
+
+use super::{Renderer, Rng, Wav};
+use crate::events::Event;
+use crate::plugin::Plugin;
+
+/// Set to any value to overwrite reference files with the current output instead of comparing.
+pub const UPDATE_GOLDEN_VAR: &str = "COUPLER_UPDATE_GOLDEN";
+
+const SPECTRUM_SIZE: usize = 1024;
+const SPECTRUM_FLOOR_DB: f32 = -120.0;
+
+/// Returns a sine wave with the given frequency and an amplitude of 0.5.
+pub fn sine(frequency: f32, sample_rate: f64, len: usize) -> Vec<f32> {
+    let step = 2.0 * PI * frequency / sample_rate as f32;
+    (0..len).map(|i| 0.5 * (step * i as f32).sin()).collect()
+}
+
+/// Returns a single full-scale sample followed by silence.
+pub fn impulse(len: usize) -> Vec<f32> {
+    let mut samples = vec![0.0; len];
+    if let Some(first) = samples.first_mut() {
+        *first = 1.0;
+    }
+    samples
+}
+
+/// Returns uniform white noise in the range `[-0.5, 0.5)`. The same seed always produces the same
+/// signal.
+pub fn white_noise(seed: u64, len: usize) -> Vec<f32> {
+    let mut rng = Rng::new(seed);
+    (0..len)
+        .map(|_| (rng.next_u64() >> 40) as f32 / (1u64 << 24) as f32 - 0.5)
+        .collect()
+}
+
+/// Maximum allowed differences between rendered audio and a reference.
+#[derive(Copy, Clone, Debug)]
+pub struct Tolerance {
+    /// Largest absolute difference of any single sample.
+    pub peak: f32,
+    /// Root mean square of the difference over each channel.
+    pub rms: f32,
+    /// Mean difference in dB between magnitude spectra of corresponding frames, if spectra should
+    /// be compared.
+    pub spectral_db: Option<f32>,
+}
+
+impl Default for Tolerance {
+    fn default() -> Tolerance {
+        Tolerance {
+            peak: 1e-4,
+            rms: 1e-5,
+            spectral_db: None,
+        }
+    }
+}
+
+/// Measured differences between rendered audio and a reference.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Difference {
+    pub peak: f32,
+    pub rms: f32,
+    pub spectral_db: f32,
+}
+
+#[derive(Clone, Debug)]
+pub enum Mismatch {
+    Shape {
+        expected: (usize, usize),
+        actual: (usize, usize),
+    },
+    SampleRate {
+        expected: u32,
+        actual: u32,
+    },
+    Difference(Difference),
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mismatch::Shape { expected, actual } => write!(
+                f,
+                "expected {} channels of {} samples, got {} channels of {} samples",
+                expected.0, expected.1, actual.0, actual.1
+            ),
+            Mismatch::SampleRate { expected, actual } => {
+                write!(f, "expected a sample rate of {expected}, got {actual}")
+            }
+            Mismatch::Difference(diff) => write!(
+                f,
+                "output differs from reference (peak {:e}, rms {:e}, spectral {} dB)",
+                diff.peak, diff.rms, diff.spectral_db
+            ),
+        }
+    }
+}
+
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let a = start + k;
+                let b = a + len / 2;
+
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Computes Hann-windowed magnitude spectra in dB for consecutive frames of `samples`.
+fn spectra(samples: &[f32]) -> Vec<Vec<f32>> {
+    let mut frames = Vec::new();
+
+    for frame in samples.chunks(SPECTRUM_SIZE) {
+        let mut re = vec![0.0; SPECTRUM_SIZE];
+        let mut im = vec![0.0; SPECTRUM_SIZE];
+        for (i, &sample) in frame.iter().enumerate() {
+            let window = 0.5 - 0.5 * (2.0 * PI * i as f32 / SPECTRUM_SIZE as f32).cos();
+            re[i] = sample * window;
+        }
+
+        fft(&mut re, &mut im);
+
+        let magnitudes = re[..SPECTRUM_SIZE / 2]
+            .iter()
+            .zip(&im)
+            .map(|(re, im)| (20.0 * (re * re + im * im).sqrt().log10()).max(SPECTRUM_FLOOR_DB))
+            .collect();
+        frames.push(magnitudes);
+    }
+
+    frames
+}
+
+/// Compares rendered audio against a reference.
+pub fn compare(
+    actual: &Wav,
+    expected: &Wav,
+    tolerance: &Tolerance,
+) -> Result<Difference, Mismatch> {
+    let actual_shape = (actual.channels.len(), actual.len());
+    let expected_shape = (expected.channels.len(), expected.len());
+    if actual_shape != expected_shape {
+        return Err(Mismatch::Shape {
+            expected: expected_shape,
+            actual: actual_shape,
+        });
+    }
+
+    if actual.sample_rate != expected.sample_rate {
+        return Err(Mismatch::SampleRate {
+            expected: expected.sample_rate,
+            actual: actual.sample_rate,
+        });
+    }
+
+    let mut diff = Difference::default();
+    for (actual, expected) in actual.channels.iter().zip(&expected.channels) {
+        let mut sum = 0.0;
+        for (&a, &e) in actual.iter().zip(expected) {
+            let d = (a - e).abs();
+            // NaN compares as unequal to everything, so make sure it registers as a difference.
+            diff.peak = if d.is_nan() {
+                f32::INFINITY
+            } else {
+                diff.peak.max(d)
+            };
+            sum += d as f64 * d as f64;
+        }
+
+        if !actual.is_empty() {
+            let rms = (sum / actual.len() as f64).sqrt() as f32;
+            diff.rms = if rms.is_nan() {
+                f32::INFINITY
+            } else {
+                diff.rms.max(rms)
+            };
+        }
+
+        if tolerance.spectral_db.is_some() {
+            for (a, e) in spectra(actual).iter().zip(&spectra(expected)) {
+                let mean =
+                    a.iter().zip(e).map(|(a, e)| (a - e).abs()).sum::<f32>() / a.len() as f32;
+                diff.spectral_db = if mean.is_nan() {
+                    f32::INFINITY
+                } else {
+                    diff.spectral_db.max(mean)
+                };
+            }
+        }
+    }
+
+    let within_spectral = tolerance.spectral_db.is_none_or(|limit| diff.spectral_db <= limit);
+    if diff.peak <= tolerance.peak && diff.rms <= tolerance.rms && within_spectral {
+        Ok(diff)
+    } else {
+        Err(Mismatch::Difference(diff))
+    }
+}
+
+/// Compares `actual` against the WAV file at `path`, or writes `actual` to `path` if `update` is
+/// true.
+pub fn check_golden(
+    path: impl AsRef<Path>,
+    actual: &Wav,
+    tolerance: &Tolerance,
+    update: bool,
+) -> Result<(), String> {
+    let path = path.as_ref();
+
+    if update {
+        let write = || -> io::Result<()> {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            actual.write(BufWriter::new(File::create(path)?))
+        };
+
+        return write().map_err(|err| format!("failed to write {}: {err}", path.display()));
+    }
+
+    let expected = match File::open(path) {
+        Ok(file) => Wav::read(BufReader::new(file)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Err(format!(
+                "reference file {} does not exist; set {UPDATE_GOLDEN_VAR}=1 to create it",
+                path.display()
+            ));
+        }
+        Err(err) => Err(err),
+    }
+    .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+
+    compare(actual, &expected, tolerance).map(|_| ()).map_err(|mismatch| {
+        format!(
+            "{}: {mismatch}; set {UPDATE_GOLDEN_VAR}=1 to accept the new output",
+            path.display()
+        )
+    })
+}
+
+/// Like [`check_golden`], updating the reference file if [`UPDATE_GOLDEN_VAR`] is set.
+///
+/// # Panics
+///
+/// Panics if the output does not match the reference or the reference cannot be read.
+#[track_caller]
+pub fn assert_golden(path: impl AsRef<Path>, actual: &Wav, tolerance: &Tolerance) {
+    let update = std::env::var_os(UPDATE_GOLDEN_VAR).is_some();
+    if let Err(message) = check_golden(path, actual, tolerance, update) {
+        panic!("{message}");
+    }
+}
+
+/// The inputs for a golden-audio render.
+#[derive(Clone, Debug)]
+pub struct GoldenRender<'a> {
+    pub sample_rate: f64,
+    pub block_size: usize,
+    pub len: usize,
+    /// Input audio, indexed by input bus, then channel.
+    pub inputs: &'a [Vec<Vec<f32>>],
+    /// Parameter values to set before rendering, by key, in the range `[0, 1]`.
+    pub params: &'a [(&'a str, f64)],
+    pub events: &'a [Event],
+}
+
+impl<'a> GoldenRender<'a> {
+    /// Renders the plugin, returning its output buses as consecutive channels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a parameter key does not exist.
+    pub fn render<P: Plugin>(&self) -> Wav {
+        let mut renderer = Renderer::<P>::new(self.sample_rate, self.block_size);
+
+        for &(key, value) in self.params {
+            let index =
+                renderer.param_index(key).unwrap_or_else(|| panic!("unknown parameter `{key}`"));
+            renderer.set_param(index, value);
+        }
+
+        let outputs = renderer.process(self.len, self.inputs, self.events, self.block_size);
+
+        Wav {
+            sample_rate: self.sample_rate as u32,
+            channels: outputs.into_iter().flatten().collect(),
+        }
+    }
+
+    /// Renders the plugin and asserts that the result matches the reference file at `path`. See
+    /// [`assert_golden`].
+    #[track_caller]
+    pub fn assert_matches<P: Plugin>(&self, path: impl AsRef<Path>, tolerance: &Tolerance) {
+        assert_golden(path, &self.render::<P>(), tolerance);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wav(channels: Vec<Vec<f32>>) -> Wav {
+        Wav {
+            sample_rate: 48000,
+            channels,
+        }
+    }
+
+    #[test]
+    fn tolerance() {
+        let expected = wav(vec![sine(440.0, 48000.0, 4096)]);
+
+        let mut actual = expected.clone();
+        assert!(compare(&actual, &expected, &Tolerance::default()).is_ok());
+
+        actual.channels[0][100] += 1e-5;
+        assert!(compare(&actual, &expected, &Tolerance::default()).is_ok());
+
+        actual.channels[0][100] += 1e-3;
+        assert!(matches!(
+            compare(&actual, &expected, &Tolerance::default()),
+            Err(Mismatch::Difference(_))
+        ));
+
+        actual.channels[0][100] = f32::NAN;
+        assert!(compare(&actual, &expected, &Tolerance::default()).is_err());
+
+        let shorter = wav(vec![expected.channels[0][..100].to_vec()]);
+        assert!(matches!(
+            compare(&shorter, &expected, &Tolerance::default()),
+            Err(Mismatch::Shape { .. })
+        ));
+    }
+
+    #[test]
+    fn spectral() {
+        let tolerance = Tolerance {
+            peak: 1.0,
+            rms: 1.0,
+            spectral_db: Some(1.0),
+        };
+
+        let expected = wav(vec![sine(1000.0, 48000.0, 4096)]);
+        let actual = wav(vec![sine(1000.0, 48000.0, 4096)]);
+        assert!(compare(&actual, &expected, &tolerance).is_ok());
+
+        let shifted = wav(vec![sine(3000.0, 48000.0, 4096)]);
+        assert!(compare(&shifted, &expected, &tolerance).is_err());
+    }
+
+    #[test]
+    fn update() {
+        let path = std::env::temp_dir()
+            .join(format!("coupler-golden-{}", std::process::id()))
+            .join("noise.wav");
+        let signal = wav(vec![white_noise(1, 256), impulse(256)]);
+
+        assert!(check_golden(&path, &signal, &Tolerance::default(), false).is_err());
+        check_golden(&path, &signal, &Tolerance::default(), true).unwrap();
+        check_golden(&path, &signal, &Tolerance::default(), false).unwrap();
+
+        let other = wav(vec![white_noise(2, 256), impulse(256)]);
+        assert!(check_golden(&path, &other, &Tolerance::default(), false).is_err());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,401 @@
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+
+use super::Wav;
+use crate::buffers::{BufferData, BufferType, Buffers};
+use crate::bus::{BusDir, Layout};
+use crate::collect::{OwnedBusInfo, collect_bus_configs, collect_buses, collect_param_keys};
+use crate::events::{Data, Event, Events};
+use crate::host::{Host, HostInner};
+use crate::plugin::Plugin;
+use crate::process::{Config, Processor};
+use crate::rt_check;
+
+struct RenderHost {}
+
+impl HostInner for RenderHost {}
+
+/// A single parameter change in an [`Automation`] script.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AutomationPoint {
+    /// Time in seconds from the start of the render.
+    pub time: f64,
+    /// Key of the parameter, as passed to [`BuildParams::param`](crate::params::BuildParams::param).
+    pub key: String,
+    /// Value in the range `[0, 1]`.
+    pub value: f64,
+}
+
+/// A list of parameter changes to apply while rendering.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Automation {
+    pub points: Vec<AutomationPoint>,
+}
+
+impl Automation {
+    pub fn new() -> Automation {
+        Automation { points: Vec::new() }
+    }
+
+    pub fn point(mut self, time: f64, key: &str, value: f64) -> Automation {
+        self.points.push(AutomationPoint {
+            time,
+            key: key.to_string(),
+            value,
+        });
+        self
+    }
+
+    /// Parses a script with one change per line, in the form `<time> <key> <value>`. Blank lines
+    /// and lines starting with `#` are ignored.
+    pub fn parse(text: &str) -> io::Result<Automation> {
+        let mut automation = Automation::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {message}", index + 1),
+                )
+            };
+
+            let mut fields = line.split_whitespace();
+            let (Some(time), Some(key), Some(value), None) =
+                (fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                return Err(error("expected `<time> <key> <value>`"));
+            };
+
+            let time = time.parse::<f64>().map_err(|_| error("invalid time"))?;
+            let value = value.parse::<f64>().map_err(|_| error("invalid value"))?;
+            if !time.is_finite() || time < 0.0 {
+                return Err(error("time must be a non-negative number of seconds"));
+            }
+            if !(0.0..=1.0).contains(&value) {
+                return Err(error("value must be in the range [0, 1]"));
+            }
+
+            automation = automation.point(time, key, value);
+        }
+
+        Ok(automation)
+    }
+}
+
+/// Runs a plugin's [`Processor`] directly over in-memory audio, without going through a plugin
+/// format.
+///
+/// The processor is created for the plugin's first bus configuration. Buffers passed to and
+/// returned from [`Renderer::process`] are indexed by bus, then channel, then sample, counting
+/// only input buses or only output buses respectively.
+pub struct Renderer<P: Plugin> {
+    plugin: P,
+    processor: P::Processor,
+    sample_rate: f64,
+    max_buffer_size: usize,
+    buses: Vec<OwnedBusInfo>,
+    layouts: Vec<Layout>,
+    buffer_data: Vec<BufferData>,
+    param_keys: HashMap<String, usize>,
+    block_events: Vec<Event>,
+}
+
+impl<P: Plugin> Renderer<P> {
+    pub fn new(sample_rate: f64, max_buffer_size: usize) -> Renderer<P> {
+        let mut plugin = P::new(Host::from_inner(Arc::new(RenderHost {})));
+
+        let (_bus_ids, buses) = collect_buses(&plugin);
+        let (_bus_config_ids, bus_configs) = collect_bus_configs(&plugin);
+        let layouts =
+            bus_configs.into_iter().next().map(|config| config.layouts).unwrap_or_default();
+
+        let mut buffer_data = Vec::new();
+        let mut total_channels = 0;
+        for (info, layout) in buses.iter().zip(&layouts) {
+            let buffer_type = match info.dir {
+                BusDir::In => BufferType::Const,
+                BusDir::Out | BusDir::InOut => BufferType::Mut,
+            };
+            let channel_count = layout.channel_count();
+
+            buffer_data.push(BufferData {
+                buffer_type,
+                start: total_channels,
+                end: total_channels + channel_count,
+            });
+
+            total_channels += channel_count;
+        }
+
+        let param_keys = collect_param_keys(&plugin);
+
+        let processor = plugin.processor(Config {
+            layouts: &layouts,
+            sample_rate,
+            max_buffer_size,
+        });
+
+        Renderer {
+            plugin,
+            processor,
+            sample_rate,
+            max_buffer_size,
+            buses,
+            layouts,
+            buffer_data,
+            param_keys,
+            block_events: Vec::new(),
+        }
+    }
+
+    pub fn plugin(&self) -> &P {
+        &self.plugin
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    fn channel_counts(&self, is_input: bool) -> Vec<usize> {
+        self.buses
+            .iter()
+            .zip(&self.layouts)
+            .filter(|(bus, _)| match bus.dir {
+                BusDir::In => is_input,
+                BusDir::Out => !is_input,
+                BusDir::InOut => true,
+            })
+            .map(|(_, layout)| layout.channel_count())
+            .collect()
+    }
+
+    /// Returns the number of channels on each input bus.
+    pub fn input_channels(&self) -> Vec<usize> {
+        self.channel_counts(true)
+    }
+
+    /// Returns the number of channels on each output bus.
+    pub fn output_channels(&self) -> Vec<usize> {
+        self.channel_counts(false)
+    }
+
+    /// Returns the index of the parameter with the given key.
+    pub fn param_index(&self, key: &str) -> Option<usize> {
+        self.param_keys.get(key).copied()
+    }
+
+    /// Sets a parameter on both the plugin and the processor.
+    pub fn set_param(&mut self, index: usize, value: f64) {
+        self.plugin.set_param(index, value);
+        rt_check::check("Processor::set_param", || {
+            self.processor.set_param(index, value)
+        });
+    }
+
+    /// Loads plugin state and recreates the processor from it.
+    pub fn load(&mut self, input: impl io::Read) -> io::Result<()> {
+        self.plugin.load(input)?;
+        self.processor = self.plugin.processor(Config {
+            layouts: &self.layouts,
+            sample_rate: self.sample_rate,
+            max_buffer_size: self.max_buffer_size,
+        });
+
+        Ok(())
+    }
+
+    pub fn reset(&mut self) {
+        rt_check::check("Processor::reset", || self.processor.reset());
+    }
+
+    /// Processes `len` samples in blocks of at most `block_size` samples. `events` must be sorted
+    /// by time, which is measured in samples from the start of `inputs`.
+    ///
+    /// Missing buses, channels or samples in `inputs` are treated as silence.
+    pub fn process(
+        &mut self,
+        len: usize,
+        inputs: &[Vec<Vec<f32>>],
+        events: &[Event],
+        block_size: usize,
+    ) -> Vec<Vec<Vec<f32>>> {
+        self.process_blocks(len, inputs, events, || block_size)
+    }
+
+    /// Like [`Renderer::process`], but calls `block_size` to choose the length of each block.
+    /// Lengths are clamped to between one sample and the maximum buffer size.
+    pub fn process_blocks(
+        &mut self,
+        len: usize,
+        inputs: &[Vec<Vec<f32>>],
+        events: &[Event],
+        mut block_size: impl FnMut() -> usize,
+    ) -> Vec<Vec<Vec<f32>>> {
+        let mut buses = Vec::with_capacity(self.buses.len());
+        let mut input_index = 0;
+        for (info, layout) in self.buses.iter().zip(&self.layouts) {
+            let mut channels = vec![vec![0.0; len]; layout.channel_count()];
+
+            if info.dir != BusDir::Out {
+                if let Some(input) = inputs.get(input_index) {
+                    for (dst, src) in channels.iter_mut().zip(input) {
+                        let count = src.len().min(len);
+                        dst[..count].copy_from_slice(&src[..count]);
+                    }
+                }
+                input_index += 1;
+            }
+
+            buses.push(channels);
+        }
+
+        let ptrs: Vec<*mut f32> =
+            buses.iter_mut().flatten().map(|channel| channel.as_mut_ptr()).collect();
+
+        let mut buffers = unsafe { Buffers::from_raw_parts(&self.buffer_data, &ptrs, 0, len) };
+        let all_events = Events::new(events);
+
+        let mut start = 0;
+        while start < len {
+            let end = (start + block_size().clamp(1, self.max_buffer_size.max(1))).min(len);
+
+            // Events outside the render are delivered in the first or last block.
+            let first = if start == 0 {
+                0
+            } else {
+                events.partition_point(|event| event.time < start as i64)
+            };
+            let last = if end == len {
+                events.len()
+            } else {
+                events.partition_point(|event| event.time < end as i64)
+            };
+
+            self.block_events.clear();
+            for event in all_events.slice(first..last).unwrap() {
+                self.block_events.push(Event {
+                    time: (event.time - start as i64).clamp(0, (end - start - 1) as i64),
+                    data: event.data,
+                });
+            }
+
+            let block = buffers.slice(start..end).unwrap();
+            let block_events = Events::new(&self.block_events);
+            rt_check::check("Processor::process", || {
+                self.processor.process(block, block_events)
+            });
+
+            for event in &self.block_events {
+                match event.data {
+                    Data::ParamChange { index, value } => self.plugin.set_param(index, value),
+                }
+            }
+
+            start = end;
+        }
+
+        self.buses
+            .iter()
+            .zip(buses)
+            .filter(|(info, _)| info.dir != BusDir::In)
+            .map(|(_, channels)| channels)
+            .collect()
+    }
+
+    /// Renders a WAV file with the given automation. Input channels are assigned to input buses in
+    /// order, and output channels are taken from output buses in order.
+    ///
+    /// The input is not resampled, so its sample rate must match the renderer's.
+    pub fn render_wav(
+        &mut self,
+        input: &Wav,
+        automation: &Automation,
+        block_size: usize,
+    ) -> io::Result<Wav> {
+        if input.sample_rate as f64 != self.sample_rate {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "input sample rate does not match the renderer",
+            ));
+        }
+
+        let mut events = Vec::with_capacity(automation.points.len());
+        for point in &automation.points {
+            let Some(index) = self.param_index(&point.key) else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unknown parameter `{}`", point.key),
+                ));
+            };
+
+            events.push(Event {
+                time: (point.time * self.sample_rate).round() as i64,
+                data: Data::ParamChange {
+                    index,
+                    value: point.value,
+                },
+            });
+        }
+        events.sort_by_key(|event| event.time);
+
+        let mut channels = input.channels.iter();
+        let inputs: Vec<Vec<Vec<f32>>> = self
+            .input_channels()
+            .iter()
+            .map(|&count| channels.by_ref().take(count).cloned().collect())
+            .collect();
+
+        let outputs = self.process(input.len(), &inputs, &events, block_size);
+
+        Ok(Wav {
+            sample_rate: input.sample_rate,
+            channels: outputs.into_iter().flatten().collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixtures::Gain;
+
+    #[test]
+    fn automation() {
+        let mut renderer = Renderer::<Gain<false>>::new(4.0, 3);
+
+        let input = Wav {
+            sample_rate: 4,
+            channels: vec![vec![1.0; 8]],
+        };
+        let automation =
+            Automation::parse("# halve, then mute\n0.5 gain 0.5\n1.75 gain 0\n").unwrap();
+
+        let output = renderer.render_wav(&input, &automation, 3).unwrap();
+        assert_eq!(
+            output.channels,
+            vec![vec![1.0, 1.0, 0.5, 0.5, 0.5, 0.5, 0.5, 0.0]]
+        );
+        assert_eq!(renderer.plugin().get_param(0), 0.0);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Automation::parse("0 gain").is_err());
+        assert!(Automation::parse("-1 gain 0.5").is_err());
+        assert!(Automation::parse("0 gain 2").is_err());
+
+        let mut renderer = Renderer::<Gain<false>>::new(4.0, 3);
+        let input = Wav {
+            sample_rate: 4,
+            channels: vec![vec![1.0; 4]],
+        };
+        let automation = Automation::new().point(0.0, "missing", 0.5);
+        assert!(renderer.render_wav(&input, &automation, 3).is_err());
+    }
+}
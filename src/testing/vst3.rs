@@ -0,0 +1,750 @@
+use std::cell::{Cell, RefCell};
+use std::ffi::c_void;
+use std::io::Read;
+use std::marker::PhantomData;
+use std::{mem, ptr, slice};
+
+#[cfg(target_os = "linux")]
+use vst3::ComRef;
+#[cfg(target_os = "linux")]
+use vst3::Steinberg::Linux::*;
+use vst3::Steinberg::Vst::*;
+use vst3::Steinberg::*;
+use vst3::{Class, ComPtr, ComWrapper, Interface};
+
+use super::{ProcessOutput, copy_buffers};
+use crate::editor::Size;
+use crate::events::{Data, Event};
+use crate::format::vst3::{Vst3Plugin, get_plugin_factory};
+use crate::plugin::Plugin;
+
+struct ParamValueQueue {
+    id: ParamID,
+    points: RefCell<Vec<(int32, ParamValue)>>,
+}
+
+impl Class for ParamValueQueue {
+    type Interfaces = (IParamValueQueue,);
+}
+
+impl IParamValueQueueTrait for ParamValueQueue {
+    unsafe fn getParameterId(&self) -> ParamID {
+        self.id
+    }
+
+    unsafe fn getPointCount(&self) -> int32 {
+        self.points.borrow().len() as int32
+    }
+
+    unsafe fn getPoint(
+        &self,
+        index: int32,
+        sample_offset: *mut int32,
+        value: *mut ParamValue,
+    ) -> tresult {
+        if let Some(&(offset, point_value)) = self.points.borrow().get(index as usize) {
+            unsafe {
+                *sample_offset = offset;
+                *value = point_value;
+            }
+            return kResultOk;
+        }
+
+        kInvalidArgument
+    }
+
+    unsafe fn addPoint(
+        &self,
+        sample_offset: int32,
+        value: ParamValue,
+        index: *mut int32,
+    ) -> tresult {
+        let mut points = self.points.borrow_mut();
+
+        let position = points.partition_point(|&(offset, _)| offset <= sample_offset);
+        points.insert(position, (sample_offset, value));
+        if !index.is_null() {
+            unsafe { *index = position as int32 };
+        }
+
+        kResultOk
+    }
+}
+
+struct ParameterChanges {
+    queues: RefCell<Vec<ComWrapper<ParamValueQueue>>>,
+}
+
+impl ParameterChanges {
+    fn new() -> ParameterChanges {
+        ParameterChanges {
+            queues: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn queue_index(&self, id: ParamID) -> usize {
+        let mut queues = self.queues.borrow_mut();
+
+        if let Some(index) = queues.iter().position(|queue| queue.id == id) {
+            return index;
+        }
+
+        queues.push(ComWrapper::new(ParamValueQueue {
+            id,
+            points: RefCell::new(Vec::new()),
+        }));
+        queues.len() - 1
+    }
+
+    fn add_point(&self, id: ParamID, offset: int32, value: ParamValue) {
+        let index = self.queue_index(id);
+        self.queues.borrow()[index].points.borrow_mut().push((offset, value));
+    }
+}
+
+impl Class for ParameterChanges {
+    type Interfaces = (IParameterChanges,);
+}
+
+impl IParameterChangesTrait for ParameterChanges {
+    unsafe fn getParameterCount(&self) -> int32 {
+        self.queues.borrow().len() as int32
+    }
+
+    unsafe fn getParameterData(&self, index: int32) -> *mut IParamValueQueue {
+        if let Some(queue) = self.queues.borrow().get(index as usize) {
+            return queue.as_com_ref::<IParamValueQueue>().unwrap().as_ptr();
+        }
+
+        ptr::null_mut()
+    }
+
+    unsafe fn addParameterData(
+        &self,
+        id: *const ParamID,
+        index: *mut int32,
+    ) -> *mut IParamValueQueue {
+        let queue_index = self.queue_index(unsafe { *id });
+        if !index.is_null() {
+            unsafe { *index = queue_index as int32 };
+        }
+
+        self.queues.borrow()[queue_index]
+            .as_com_ref::<IParamValueQueue>()
+            .unwrap()
+            .as_ptr()
+    }
+}
+
+struct MemoryStream {
+    data: RefCell<Vec<u8>>,
+    position: Cell<usize>,
+}
+
+impl Class for MemoryStream {
+    type Interfaces = (IBStream,);
+}
+
+impl IBStreamTrait for MemoryStream {
+    unsafe fn read(
+        &self,
+        buffer: *mut c_void,
+        num_bytes: int32,
+        bytes_read: *mut int32,
+    ) -> tresult {
+        let data = self.data.borrow();
+        let position = self.position.get().min(data.len());
+        let count = (num_bytes.max(0) as usize).min(data.len() - position);
+
+        if count > 0 {
+            let buffer = unsafe { slice::from_raw_parts_mut(buffer as *mut u8, count) };
+            buffer.copy_from_slice(&data[position..position + count]);
+            self.position.set(position + count);
+        }
+
+        if !bytes_read.is_null() {
+            unsafe { *bytes_read = count as int32 };
+        }
+
+        kResultOk
+    }
+
+    unsafe fn write(
+        &self,
+        buffer: *mut c_void,
+        num_bytes: int32,
+        bytes_written: *mut int32,
+    ) -> tresult {
+        let mut data = self.data.borrow_mut();
+        let position = self.position.get();
+        let count = num_bytes.max(0) as usize;
+
+        if count > 0 {
+            let buffer = unsafe { slice::from_raw_parts(buffer as *const u8, count) };
+            if data.len() < position + count {
+                data.resize(position + count, 0);
+            }
+            data[position..position + count].copy_from_slice(buffer);
+            self.position.set(position + count);
+        }
+
+        if !bytes_written.is_null() {
+            unsafe { *bytes_written = count as int32 };
+        }
+
+        kResultOk
+    }
+
+    unsafe fn seek(&self, pos: int64, mode: int32, result: *mut int64) -> tresult {
+        // Values from IBStream::IStreamSeekMode in ibstream.h.
+        let base = match mode {
+            0 => 0,
+            1 => self.position.get() as int64,
+            2 => self.data.borrow().len() as int64,
+            _ => return kInvalidArgument,
+        };
+
+        let position = base + pos;
+        if position < 0 {
+            return kInvalidArgument;
+        }
+        self.position.set(position as usize);
+
+        if !result.is_null() {
+            unsafe { *result = position };
+        }
+
+        kResultOk
+    }
+
+    unsafe fn tell(&self, pos: *mut int64) -> tresult {
+        if !pos.is_null() {
+            unsafe { *pos = self.position.get() as int64 };
+        }
+
+        kResultOk
+    }
+}
+
+// A read-only stream which passes on whatever byte counts its reader returns, so that a misbehaving
+// reader can stand in for a misbehaving host.
+struct ReaderStream {
+    reader: RefCell<Box<dyn Read>>,
+}
+
+impl Class for ReaderStream {
+    type Interfaces = (IBStream,);
+}
+
+impl IBStreamTrait for ReaderStream {
+    unsafe fn read(
+        &self,
+        buffer: *mut c_void,
+        num_bytes: int32,
+        bytes_read: *mut int32,
+    ) -> tresult {
+        let buffer =
+            unsafe { slice::from_raw_parts_mut(buffer as *mut u8, num_bytes.max(0) as usize) };
+
+        let Ok(count) = self.reader.borrow_mut().read(buffer) else {
+            return kResultFalse;
+        };
+
+        if !bytes_read.is_null() {
+            unsafe { *bytes_read = count as int32 };
+        }
+
+        kResultOk
+    }
+
+    unsafe fn write(
+        &self,
+        _buffer: *mut c_void,
+        _num_bytes: int32,
+        _bytes_written: *mut int32,
+    ) -> tresult {
+        kNotImplemented
+    }
+
+    unsafe fn seek(&self, _pos: int64, _mode: int32, _result: *mut int64) -> tresult {
+        kNotImplemented
+    }
+
+    unsafe fn tell(&self, _pos: *mut int64) -> tresult {
+        kNotImplemented
+    }
+}
+
+fn string_from_wchars(wchars: &[char16]) -> String {
+    let utf16 = wchars.iter().map(|&c| c as u16).take_while(|&c| c != 0);
+    char::decode_utf16(utf16)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+// Provides the run loop through which the editor registers its idle timer on Linux.
+#[cfg(target_os = "linux")]
+struct Frame {
+    timers: RefCell<Vec<ComPtr<ITimerHandler>>>,
+}
+
+#[cfg(target_os = "linux")]
+impl Class for Frame {
+    type Interfaces = (IPlugFrame, IRunLoop);
+}
+
+#[cfg(target_os = "linux")]
+impl IPlugFrameTrait for Frame {
+    unsafe fn resizeView(&self, _view: *mut IPlugView, _newSize: *mut ViewRect) -> tresult {
+        kNotImplemented
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl IRunLoopTrait for Frame {
+    unsafe fn registerEventHandler(
+        &self,
+        _handler: *mut IEventHandler,
+        _fd: FileDescriptor,
+    ) -> tresult {
+        kNotImplemented
+    }
+
+    unsafe fn unregisterEventHandler(&self, _handler: *mut IEventHandler) -> tresult {
+        kResultOk
+    }
+
+    unsafe fn registerTimer(
+        &self,
+        handler: *mut ITimerHandler,
+        _milliseconds: TimerInterval,
+    ) -> tresult {
+        let Some(handler) = (unsafe { ComRef::from_raw(handler) }) else {
+            return kInvalidArgument;
+        };
+
+        self.timers.borrow_mut().push(handler.to_com_ptr());
+        kResultOk
+    }
+
+    unsafe fn unregisterTimer(&self, handler: *mut ITimerHandler) -> tresult {
+        self.timers.borrow_mut().retain(|timer| timer.as_ptr() != handler);
+        kResultOk
+    }
+}
+
+struct ParamData {
+    id: ParamID,
+    name: String,
+    is_list: bool,
+}
+
+/// Hosts a plugin in-process through its VST3 interfaces.
+///
+/// All calls are made on the current thread, which serves as both the main thread and the audio
+/// thread. Parameter values are normalized to `[0, 1]`, as they are for [`Plugin::set_param`].
+pub struct Vst3TestHost<P: Plugin + Vst3Plugin> {
+    component: ComPtr<IComponent>,
+    processor: ComPtr<IAudioProcessor>,
+    controller: ComPtr<IEditController>,
+    view: Option<ComPtr<IPlugView>>,
+    #[cfg(target_os = "linux")]
+    frame: ComWrapper<Frame>,
+    params: Vec<ParamData>,
+    input_channels: Vec<usize>,
+    output_channels: Vec<usize>,
+    max_buffer_size: Option<usize>,
+    pending_events: Vec<Event>,
+    _marker: PhantomData<P>,
+}
+
+impl<P: Plugin + Vst3Plugin> Vst3TestHost<P> {
+    /// Creates and initializes an instance of the plugin.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the plugin cannot be created or fails to initialize.
+    pub fn new() -> Vst3TestHost<P> {
+        let factory = get_plugin_factory::<P>() as *mut IPluginFactory;
+        let factory = unsafe { ComPtr::from_raw(factory) }.expect("failed to get factory");
+
+        let mut class_info: PClassInfo = unsafe { mem::zeroed() };
+        let result = unsafe { factory.getClassInfo(0, &mut class_info) };
+        assert_eq!(result, kResultOk, "failed to get class info");
+
+        let mut obj = ptr::null_mut();
+        let result = unsafe {
+            factory.createInstance(
+                class_info.cid.as_ptr(),
+                IComponent::IID.as_ptr() as FIDString,
+                &mut obj,
+            )
+        };
+        assert_eq!(result, kResultOk, "failed to create plugin");
+        let component = unsafe { ComPtr::from_raw(obj as *mut IComponent) }.unwrap();
+
+        let result = unsafe { component.initialize(ptr::null_mut()) };
+        assert_eq!(result, kResultOk, "failed to initialize plugin");
+
+        let processor = component.cast::<IAudioProcessor>().expect("missing IAudioProcessor");
+        let controller = component.cast::<IEditController>().expect("missing IEditController");
+
+        let mut params = Vec::new();
+        for index in 0..unsafe { controller.getParameterCount() } {
+            let mut info: ParameterInfo = unsafe { mem::zeroed() };
+            let result = unsafe { controller.getParameterInfo(index, &mut info) };
+            assert_eq!(
+                result, kResultOk,
+                "failed to get info for parameter {index}"
+            );
+
+            let is_list = ParameterInfo_::ParameterFlags_::kIsList as int32;
+            params.push(ParamData {
+                id: info.id,
+                name: string_from_wchars(&info.title),
+                is_list: info.flags & is_list != 0,
+            });
+        }
+
+        let bus_channels = |dir: BusDirections| -> Vec<usize> {
+            let media_type = MediaTypes_::kAudio as MediaType;
+            let dir = dir as BusDirection;
+
+            let count = unsafe { component.getBusCount(media_type, dir) };
+            (0..count)
+                .map(|index| {
+                    let mut info: BusInfo = unsafe { mem::zeroed() };
+                    let result = unsafe { component.getBusInfo(media_type, dir, index, &mut info) };
+                    assert_eq!(result, kResultOk, "failed to get info for bus {index}");
+
+                    info.channelCount as usize
+                })
+                .collect()
+        };
+        let input_channels = bus_channels(BusDirections_::kInput);
+        let output_channels = bus_channels(BusDirections_::kOutput);
+
+        Vst3TestHost {
+            component,
+            processor,
+            controller,
+            view: None,
+            #[cfg(target_os = "linux")]
+            frame: ComWrapper::new(Frame {
+                timers: RefCell::new(Vec::new()),
+            }),
+            params,
+            input_channels,
+            output_channels,
+            max_buffer_size: None,
+            pending_events: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the number of channels on each input bus.
+    pub fn input_channels(&self) -> &[usize] {
+        &self.input_channels
+    }
+
+    /// Returns the number of channels on each output bus.
+    pub fn output_channels(&self) -> &[usize] {
+        &self.output_channels
+    }
+
+    pub fn activate(&mut self, sample_rate: f64, max_buffer_size: usize) {
+        self.deactivate();
+
+        let mut setup = ProcessSetup {
+            processMode: ProcessModes_::kRealtime as int32,
+            symbolicSampleSize: SymbolicSampleSizes_::kSample32 as int32,
+            maxSamplesPerBlock: max_buffer_size as int32,
+            sampleRate: sample_rate,
+        };
+        let result = unsafe { self.processor.setupProcessing(&mut setup) };
+        assert_eq!(result, kResultOk, "failed to set up processing");
+
+        let result = unsafe { self.component.setActive(1) };
+        assert_eq!(result, kResultOk, "failed to activate plugin");
+
+        let result = unsafe { self.processor.setProcessing(1) };
+        assert_eq!(result, kResultOk, "failed to start processing");
+
+        self.max_buffer_size = Some(max_buffer_size);
+    }
+
+    pub fn deactivate(&mut self) {
+        if self.max_buffer_size.take().is_some() {
+            unsafe { self.processor.setProcessing(0) };
+            unsafe { self.component.setActive(0) };
+        }
+    }
+
+    /// Processes a block of `len` samples.
+    ///
+    /// `inputs` is indexed by input bus, then channel. Missing buses, channels or samples are
+    /// treated as silence. `events` must be within the block, and parameter changes made with
+    /// [`Vst3TestHost::set_param`] since the last block are delivered at its start.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the plugin is not active, if `len` exceeds the maximum buffer size, or if the
+    /// plugin reports an error.
+    pub fn process(
+        &mut self,
+        len: usize,
+        inputs: &[Vec<Vec<f32>>],
+        events: &[Event],
+    ) -> ProcessOutput {
+        self.try_process(len, inputs, events)
+            .expect("plugin returned an error from process")
+    }
+
+    /// Like [`Vst3TestHost::process`], but returns the output in `Err` rather than panicking if the
+    /// plugin reports an error.
+    pub fn try_process(
+        &mut self,
+        len: usize,
+        inputs: &[Vec<Vec<f32>>],
+        events: &[Event],
+    ) -> Result<ProcessOutput, ProcessOutput> {
+        let max_buffer_size = self.max_buffer_size.expect("plugin is not active");
+        assert!(len <= max_buffer_size, "block exceeds maximum buffer size");
+
+        let mut inputs = copy_buffers(inputs, &self.input_channels, len);
+        let mut outputs = copy_buffers(&[], &self.output_channels, len);
+
+        let mut input_ptrs: Vec<Vec<*mut f32>> = inputs
+            .iter_mut()
+            .map(|bus| bus.iter_mut().map(|channel| channel.as_mut_ptr()).collect())
+            .collect();
+        let mut output_ptrs: Vec<Vec<*mut f32>> = outputs
+            .iter_mut()
+            .map(|bus| bus.iter_mut().map(|channel| channel.as_mut_ptr()).collect())
+            .collect();
+
+        let audio_bus_buffers = |ptrs: &mut Vec<*mut f32>| AudioBusBuffers {
+            numChannels: ptrs.len() as int32,
+            silenceFlags: 0,
+            __field0: AudioBusBuffers__type0 {
+                channelBuffers32: ptrs.as_mut_ptr(),
+            },
+        };
+        let mut audio_inputs: Vec<AudioBusBuffers> =
+            input_ptrs.iter_mut().map(audio_bus_buffers).collect();
+        let mut audio_outputs: Vec<AudioBusBuffers> =
+            output_ptrs.iter_mut().map(audio_bus_buffers).collect();
+
+        let mut all_events = mem::take(&mut self.pending_events);
+        all_events.extend_from_slice(events);
+        all_events.sort_by_key(|event| event.time);
+
+        let input_changes = ComWrapper::new(ParameterChanges::new());
+        for event in &all_events {
+            match event.data {
+                Data::ParamChange { index, value } => {
+                    input_changes.add_point(self.params[index].id, event.time as int32, value);
+                }
+            }
+        }
+        let output_changes = ComWrapper::new(ParameterChanges::new());
+
+        let mut data = ProcessData {
+            processMode: ProcessModes_::kRealtime as int32,
+            symbolicSampleSize: SymbolicSampleSizes_::kSample32 as int32,
+            numSamples: len as int32,
+            numInputs: audio_inputs.len() as int32,
+            numOutputs: audio_outputs.len() as int32,
+            inputs: audio_inputs.as_mut_ptr(),
+            outputs: audio_outputs.as_mut_ptr(),
+            inputParameterChanges: input_changes
+                .as_com_ref::<IParameterChanges>()
+                .unwrap()
+                .as_ptr(),
+            outputParameterChanges: output_changes
+                .as_com_ref::<IParameterChanges>()
+                .unwrap()
+                .as_ptr(),
+            inputEvents: ptr::null_mut(),
+            outputEvents: ptr::null_mut(),
+            processContext: ptr::null_mut(),
+        };
+
+        let result = unsafe { self.processor.process(&mut data) };
+
+        let mut events = Vec::new();
+        for queue in output_changes.queues.borrow().iter() {
+            let Some(index) = self.params.iter().position(|param| param.id == queue.id) else {
+                continue;
+            };
+
+            for &(offset, value) in queue.points.borrow().iter() {
+                events.push(Event {
+                    time: offset as i64,
+                    data: Data::ParamChange { index, value },
+                });
+            }
+        }
+        events.sort_by_key(|event| event.time);
+
+        let output = ProcessOutput { outputs, events };
+
+        if result == kResultOk {
+            Ok(output)
+        } else {
+            Err(output)
+        }
+    }
+
+    pub fn param_count(&self) -> usize {
+        self.params.len()
+    }
+
+    pub fn param_name(&self, index: usize) -> &str {
+        &self.params[index].name
+    }
+
+    /// Returns whether the plugin asks for the parameter to be presented as a list of named values.
+    pub fn param_is_list(&self, index: usize) -> bool {
+        self.params[index].is_list
+    }
+
+    pub fn get_param(&self, index: usize) -> f64 {
+        unsafe { self.controller.getParamNormalized(self.params[index].id) }
+    }
+
+    /// Sets a parameter as a host would when automating it. If the plugin is active, the change is
+    /// delivered at the start of the next call to [`Vst3TestHost::process`]. Otherwise, it is set
+    /// directly on the edit controller.
+    pub fn set_param(&mut self, index: usize, value: f64) {
+        if self.max_buffer_size.is_some() {
+            self.pending_events.push(Event {
+                time: 0,
+                data: Data::ParamChange { index, value },
+            });
+            return;
+        }
+
+        let result = unsafe { self.controller.setParamNormalized(self.params[index].id, value) };
+        assert_eq!(result, kResultOk, "failed to set parameter {index}");
+    }
+
+    pub fn save(&mut self) -> Vec<u8> {
+        let stream = ComWrapper::new(MemoryStream {
+            data: RefCell::new(Vec::new()),
+            position: Cell::new(0),
+        });
+
+        let stream_ptr = stream.as_com_ref::<IBStream>().unwrap().as_ptr();
+        let result = unsafe { self.component.getState(stream_ptr) };
+        assert_eq!(result, kResultOk, "failed to save plugin state");
+
+        stream.data.take()
+    }
+
+    /// Loads state previously returned by [`Vst3TestHost::save`]. Returns `false` if the plugin
+    /// rejected it.
+    pub fn load(&mut self, data: &[u8]) -> bool {
+        let stream = ComWrapper::new(MemoryStream {
+            data: RefCell::new(data.to_vec()),
+            position: Cell::new(0),
+        });
+
+        let stream_ptr = stream.as_com_ref::<IBStream>().unwrap().as_ptr();
+        unsafe { self.component.setState(stream_ptr) == kResultOk }
+    }
+
+    /// Loads state from `reader`. Byte counts returned by `reader` are passed on to the plugin as
+    /// they are, even if they are larger than the buffer it asked to fill.
+    pub fn load_from(&mut self, reader: impl Read + 'static) -> bool {
+        let stream = ComWrapper::new(ReaderStream {
+            reader: RefCell::new(Box::new(reader)),
+        });
+
+        let stream_ptr = stream.as_com_ref::<IBStream>().unwrap().as_ptr();
+        unsafe { self.component.setState(stream_ptr) == kResultOk }
+    }
+
+    fn view(&self) -> &ComPtr<IPlugView> {
+        self.view.as_ref().expect("editor is not open")
+    }
+
+    /// Opens the plugin's editor. Returns `false` if the plugin has no editor or refused to create
+    /// it.
+    ///
+    /// The editor is given a null parent window handle, so this is only suitable for editors which
+    /// can operate without creating a native window.
+    pub fn open_editor(&mut self) -> bool {
+        self.close_editor();
+
+        #[cfg(target_os = "windows")]
+        let platform_type = kPlatformTypeHWND;
+
+        #[cfg(target_os = "macos")]
+        let platform_type = kPlatformTypeNSView;
+
+        #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+        let platform_type = kPlatformTypeX11EmbedWindowID;
+
+        let view = unsafe { self.controller.createView(ViewType::kEditor) };
+        let Some(view) = (unsafe { ComPtr::from_raw(view) }) else {
+            return false;
+        };
+
+        #[cfg(target_os = "linux")]
+        {
+            let frame = self.frame.as_com_ref::<IPlugFrame>().unwrap();
+            unsafe { view.setFrame(frame.as_ptr()) };
+        }
+
+        if unsafe { view.attached(ptr::null_mut(), platform_type) } != kResultOk {
+            return false;
+        }
+
+        self.view = Some(view);
+        true
+    }
+
+    /// Resizes the editor as a host would when the user resizes its window. Returns `false` if the
+    /// editor rejected the new size.
+    pub fn resize_editor(&mut self, size: Size) -> bool {
+        let mut rect = ViewRect {
+            left: 0,
+            top: 0,
+            right: size.width.round() as int32,
+            bottom: size.height.round() as int32,
+        };
+
+        unsafe { self.view().onSize(&mut rect) == kResultOk }
+    }
+
+    /// Fires the timers the editor has registered with the host's run loop, which VST3 hosts only
+    /// provide on Linux.
+    #[cfg(target_os = "linux")]
+    pub fn idle_editor(&mut self) {
+        let timers = self.frame.timers.borrow().clone();
+        for timer in timers {
+            unsafe { timer.onTimer() };
+        }
+    }
+
+    pub fn close_editor(&mut self) {
+        if let Some(view) = self.view.take() {
+            unsafe { view.removed() };
+        }
+    }
+}
+
+impl<P: Plugin + Vst3Plugin> Default for Vst3TestHost<P> {
+    fn default() -> Vst3TestHost<P> {
+        Vst3TestHost::new()
+    }
+}
+
+impl<P: Plugin + Vst3Plugin> Drop for Vst3TestHost<P> {
+    fn drop(&mut self) {
+        self.close_editor();
+        self.deactivate();
+        unsafe { self.component.terminate() };
+    }
+}
@@ -0,0 +1,206 @@
+use std::io::{self, Read, Write};
+
+// Format tags from mmreg.h.
+const WAVE_FORMAT_PCM: u16 = 0x0001;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 0x0003;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+/// Audio read from or written to a WAV file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Wav {
+    pub sample_rate: u32,
+    /// Samples in the range `[-1, 1]`, indexed by channel.
+    pub channels: Vec<Vec<f32>>,
+}
+
+impl Wav {
+    /// Returns the number of samples in each channel.
+    pub fn len(&self) -> usize {
+        self.channels.first().map_or(0, |channel| channel.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads integer PCM (8, 16, 24 or 32 bits) or floating-point (32 or 64 bits) WAV data.
+    pub fn read(mut input: impl Read) -> io::Result<Wav> {
+        let mut bytes = Vec::new();
+        input.read_to_end(&mut bytes)?;
+
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return Err(invalid_data("not a WAV file"));
+        }
+
+        let mut format = None;
+        let mut data = None;
+
+        let mut offset = 12;
+        while offset + 8 <= bytes.len() {
+            let id = &bytes[offset..offset + 4];
+            let size = u32_at(&bytes, offset + 4) as usize;
+            let start = offset + 8;
+            let end = start.checked_add(size).ok_or_else(|| invalid_data("invalid chunk size"))?;
+            let chunk = bytes.get(start..end.min(bytes.len())).unwrap_or(&[]);
+
+            match id {
+                b"fmt " => {
+                    if chunk.len() < 16 {
+                        return Err(invalid_data("fmt chunk is too short"));
+                    }
+
+                    let mut tag = u16_at(chunk, 0);
+                    if tag == WAVE_FORMAT_EXTENSIBLE {
+                        if chunk.len() < 26 {
+                            return Err(invalid_data("fmt chunk is too short"));
+                        }
+                        tag = u16_at(chunk, 24);
+                    }
+
+                    let channel_count = u16_at(chunk, 2) as usize;
+                    let sample_rate = u32_at(chunk, 4);
+                    let bits = u16_at(chunk, 14);
+                    format = Some((tag, channel_count, sample_rate, bits));
+                }
+                b"data" => data = Some(chunk),
+                _ => {}
+            }
+
+            // Chunks are padded to an even number of bytes.
+            offset = end.saturating_add(size & 1);
+        }
+
+        let (tag, channel_count, sample_rate, bits) =
+            format.ok_or_else(|| invalid_data("missing fmt chunk"))?;
+        let data = data.ok_or_else(|| invalid_data("missing data chunk"))?;
+
+        if channel_count == 0 {
+            return Err(invalid_data("WAV file has no channels"));
+        }
+
+        let decode: fn(&[u8]) -> f32 = match (tag, bits) {
+            (WAVE_FORMAT_PCM, 8) => |b| (b[0] as f32 - 128.0) / 128.0,
+            (WAVE_FORMAT_PCM, 16) => |b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0,
+            (WAVE_FORMAT_PCM, 24) => {
+                |b| (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8388608.0
+            }
+            (WAVE_FORMAT_PCM, 32) => {
+                |b| i32::from_le_bytes(b.try_into().unwrap()) as f32 / 2147483648.0
+            }
+            (WAVE_FORMAT_IEEE_FLOAT, 32) => |b| f32::from_le_bytes(b.try_into().unwrap()),
+            (WAVE_FORMAT_IEEE_FLOAT, 64) => |b| f64::from_le_bytes(b.try_into().unwrap()) as f32,
+            _ => return Err(invalid_data("unsupported sample format")),
+        };
+
+        let sample_size = bits as usize / 8;
+        let frame_size = sample_size * channel_count;
+        let len = data.len() / frame_size;
+
+        let mut channels = vec![Vec::with_capacity(len); channel_count];
+        for frame in data.chunks_exact(frame_size) {
+            for (channel, sample) in channels.iter_mut().zip(frame.chunks_exact(sample_size)) {
+                channel.push(decode(sample));
+            }
+        }
+
+        Ok(Wav {
+            sample_rate,
+            channels,
+        })
+    }
+
+    /// Writes 32-bit floating-point WAV data. Channels shorter than the first are padded with
+    /// silence.
+    pub fn write(&self, mut output: impl Write) -> io::Result<()> {
+        let channel_count = self.channels.len();
+        let len = self.len();
+
+        let frame_size = 4 * channel_count;
+        let data_size = u32::try_from(frame_size * len)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too much audio data"))?;
+
+        let mut bytes = Vec::with_capacity(44 + data_size as usize);
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+        bytes.extend_from_slice(b"WAVE");
+
+        bytes.extend_from_slice(b"fmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&WAVE_FORMAT_IEEE_FLOAT.to_le_bytes());
+        bytes.extend_from_slice(&(channel_count as u16).to_le_bytes());
+        bytes.extend_from_slice(&self.sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(self.sample_rate * frame_size as u32).to_le_bytes());
+        bytes.extend_from_slice(&(frame_size as u16).to_le_bytes());
+        bytes.extend_from_slice(&32u16.to_le_bytes());
+
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_size.to_le_bytes());
+        for index in 0..len {
+            for channel in &self.channels {
+                let sample = channel.get(index).copied().unwrap_or(0.0);
+                bytes.extend_from_slice(&sample.to_le_bytes());
+            }
+        }
+
+        output.write_all(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let wav = Wav {
+            sample_rate: 48000,
+            channels: vec![vec![0.0, 0.5, -0.5, 1.0], vec![-1.0, 0.25, 0.0, 0.125]],
+        };
+
+        let mut bytes = Vec::new();
+        wav.write(&mut bytes).unwrap();
+        assert_eq!(Wav::read(&bytes[..]).unwrap(), wav);
+    }
+
+    #[test]
+    fn pcm16() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&40u32.to_le_bytes());
+        bytes.extend_from_slice(b"WAVE");
+        bytes.extend_from_slice(b"fmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&WAVE_FORMAT_PCM.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&44100u32.to_le_bytes());
+        bytes.extend_from_slice(&88200u32.to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&4u32.to_le_bytes());
+        bytes.extend_from_slice(&16384i16.to_le_bytes());
+        bytes.extend_from_slice(&i16::MIN.to_le_bytes());
+
+        let wav = Wav::read(&bytes[..]).unwrap();
+        assert_eq!(wav.sample_rate, 44100);
+        assert_eq!(wav.channels, vec![vec![0.5, -1.0]]);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(Wav::read(&b"RIFF"[..]).is_err());
+        assert!(Wav::read(&b"RIFF\0\0\0\0WAVEdata\xff\xff\xff\xff"[..]).is_err());
+    }
+}